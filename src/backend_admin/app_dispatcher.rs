@@ -5,7 +5,7 @@ use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
 use winit::window::Window;
 use std::sync::Arc;
 
-use crate::backend_admin::state::State;
+use crate::backend_admin::{simulation::BackendKind, state::State};
 
 /// Setup for logical App struct \n
 /// App implements ApplicationHandler for resuming of app, WindowEvent handling \n
@@ -13,6 +13,7 @@ use crate::backend_admin::state::State;
 pub struct App {
     state: Option<State>,
     proxy: Option<EventLoopProxy<State>>,
+    backend: BackendKind,
     aspect_ratio: f32,
     size: Option<PhysicalSize<u32>>,
     minimum_size: PhysicalSize<u32>,
@@ -20,10 +21,11 @@ pub struct App {
 }
 
 impl App  {
-    pub fn new (fun: impl FnOnce()-> EventLoopProxy<State>) -> App {
+    pub fn new (backend: BackendKind, fun: impl FnOnce()-> EventLoopProxy<State>) -> App {
         App {
             state: None,
             proxy:  Some(fun()), // smuggle proxy into app using move closure for downstream requests
+            backend: backend,
             aspect_ratio: 16.0 / 9.0, // width/height,
             size: None,
             minimum_size: PhysicalSize::new(740, 360), // 40x a_r
@@ -69,7 +71,7 @@ impl ApplicationHandler<State> for App {
             // Need async context for requests using pollset
             // injected back into app through user event
             if let Some(prx) = self.proxy.take() {
-                let state = pollster::block_on(State::new(window.clone())).expect("Couldn't get state");
+                let state = pollster::block_on(State::new(window.clone(), self.backend)).expect("Couldn't get state");
                 self.user_event(&event_loop,state);
             } // end of setup: go to App::user_event() :)
         }
@@ -146,7 +148,9 @@ impl ApplicationHandler<State> for App {
             },       
             WindowEvent::RedrawRequested => {
                 match state_.render() {
-                    Ok(_) => {state_.gfx_ctx.window.request_redraw();},
+                    Ok(_) => {
+                        if let Some(window) = &state_.gfx_ctx.window { window.request_redraw(); }
+                    },
                     Err(e) => {
                         println!("Unable to render {}", e);
                     }
@@ -1,7 +1,7 @@
 
 use crate::{
     backend_admin::gpu::gfx_context::GraphicsContext,
-    world::{voxel_grid::VoxelGrid, world::BoundingBox}
+    world::{voxel_grid::{Dims3, VoxelGrid}, world::BoundingBox}
 };
 use rand::Rng;
 
@@ -34,11 +34,7 @@ impl Bridge {
             1
         ];
 
-        let laplacian_dispatch: DispatchDims = [
-            voxel_grid.dims[0].div_ceil(LAPLACIAN_GROUPS[0]),
-            voxel_grid.dims[1].div_ceil(LAPLACIAN_GROUPS[1]),
-            voxel_grid.dims[2].div_ceil(LAPLACIAN_GROUPS[2])
-        ];
+        let laplacian_dispatch = voxel_dispatch(&voxel_grid.dims);
 
         let seed = rand::rng().random::<u32>();
 
@@ -67,4 +63,13 @@ impl Bridge {
             1
         ];
     }
-}
\ No newline at end of file
+}
+
+/// Workgroups covering a grid of these dims with the 8 * 4 * 8 voxel passes (laplacian, implicit, multigrid levels)
+pub fn voxel_dispatch(dims: &Dims3) -> DispatchDims {
+    [
+        dims[0].div_ceil(LAPLACIAN_GROUPS[0]),
+        dims[1].div_ceil(LAPLACIAN_GROUPS[1]),
+        dims[2].div_ceil(LAPLACIAN_GROUPS[2])
+    ]
+}
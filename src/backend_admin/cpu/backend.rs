@@ -0,0 +1,278 @@
+use crate::{
+    backend_admin::{
+        cpu::{cahn_hilliard::cahn_hilliard_step, implicit::{implicit_rhs, implicit_step}, laplacian::laplacian_step, multigrid, network::network_step, noise::noise_step, reaction::reaction_step, vesicle::vesicle_step},
+        gpu::{gfx_context::GraphicsContext, resources::Resources},
+        simulation::SimulationBackend},
+    world::{
+        boundary::BoundaryConditions,
+        cahn_hilliard::CahnHilliard,
+        diffusion::Diffusion,
+        field::VoxelField,
+        integrator::{ImplicitSolver, Integrator},
+        multigrid::{ConvergenceHistory, Helmholtz},
+        network::ReactionNetwork,
+        noise::ThermalNoise,
+        reaction::Reaction,
+        species::SpeciesRegistry,
+        vesicle::Vesicle,
+        voxel_grid::Dims3}
+};
+
+/// SimulationBackend on the CPU
+/// Same ping/pong scheme as the GPU: read_ping true means ping holds the current field, for every species
+pub struct CpuBackend {
+    dims: Dims3,
+    species: Vec<PingPong>, // one per species of registry
+    read_ping: bool,
+    registry: SpeciesRegistry, // per-species D, boundaries and initial conditions
+    bounds: BoundaryConditions, // for species without their own
+    diffusion: Diffusion, // ditto, see SpeciesRegistry::diffusion()
+    integrator: Integrator,
+    reaction: Option<Reaction>,
+    network: Option<ReactionNetwork>, // indexed as registry
+    cahn_hilliard: Option<CahnHilliard>, // replaces species 0's diffusion while set
+    vesicle: Option<Vesicle>, // ditto, unless Cahn-Hilliard is set too
+    vesicle_targets: Option<[f64; 2]>, // [Σ v, Σ a] the vesicle's constraints hold, None until its next step
+    noise: Option<(ThermalNoise, u32)>, // kicks species 0 after every step, with the seed keying it
+    step_index: (u64, u32), // (step, sub-step) the next step's noise is drawn at
+    history: Option<ConvergenceHistory> // last multigrid solve
+}
+
+struct PingPong {
+    ping: Vec<f32>,
+    pong: Vec<f32>
+}
+
+impl PingPong {
+    fn new(len: usize) -> Self {
+        PingPong { ping: vec![0.0; len], pong: vec![0.0; len] }
+    }
+
+    fn current(&self, read_ping: bool) -> &[f32] {
+        if read_ping { &self.ping } else { &self.pong }
+    }
+
+    /// (current, other)
+    fn buffers(&mut self, read_ping: bool) -> (&mut [f32], &mut [f32]) {
+        if read_ping { (&mut self.ping, &mut self.pong) } else { (&mut self.pong, &mut self.ping) }
+    }
+}
+
+impl CpuBackend {
+    pub fn new(dims: Dims3) -> Self {
+        assert!(dims[0] > 0 && dims[1] > 0 && dims[2] > 0);
+        let len = (dims[0] * dims[1] * dims[2]) as usize;
+        CpuBackend {
+            dims: dims,
+            species: vec![PingPong::new(len)],
+            read_ping: true,
+            registry: SpeciesRegistry::default(),
+            bounds: BoundaryConditions::default(),
+            diffusion: Diffusion::default(),
+            integrator: Integrator::default(),
+            reaction: None,
+            network: None,
+            cahn_hilliard: None,
+            vesicle: None,
+            vesicle_targets: None,
+            noise: None,
+            step_index: (0, 0),
+            history: None
+        }
+    }
+
+    fn len(&self) -> usize {
+        (self.dims[0] * self.dims[1] * self.dims[2]) as usize
+    }
+
+    /// Stores registry's species, see SimulationBackend::set_species()
+    /// Needs no Resources, so the CPU backend runs without a GraphicsContext
+    pub fn set_registry(&mut self, registry: &SpeciesRegistry) {
+        let len = self.len();
+        self.registry = registry.clone();
+        self.species.resize_with(registry.len(), || PingPong::new(len));
+        self.network = self.network.take().and_then(|network| network.resolve(registry).ok());
+    }
+
+    /// Diffusion of a species, see SpeciesRegistry::diffusion()
+    fn species_diffusion(&self, species: usize) -> Diffusion {
+        self.registry.diffusion(species, &self.diffusion, self.reaction.as_ref())
+    }
+
+    /// Boundary faces of a species, see SpeciesRegistry::boundaries()
+    fn species_bounds(&self, species: usize) -> BoundaryConditions {
+        self.registry.boundaries(species, &self.bounds)
+    }
+
+    /// Γ of the thermal noise, see world/noise.rs
+    fn noise_mobility(&self) -> f32 {
+        match (&self.cahn_hilliard, &self.vesicle) {
+            (Some(model), _) => model.mobility,
+            (None, Some(model)) => model.mobility,
+            (None, None) => self.species_diffusion(0).coefficient
+        }
+    }
+
+    /// Explicit steps write the other buffer, implicit ones relax the current buffer in place using the other for the right hand side
+    /// Cahn-Hilliard and vesicle steps follow whichever of the two the integrator does, see cahn_hilliard_step()
+    fn diffuse(&mut self, species: usize, timestep: f32) {
+        let (dims, bounds, read_ping) = (self.dims, self.species_bounds(species), self.read_ping);
+        let diffusion = self.species_diffusion(species);
+        let (current, other) = self.species[species].buffers(read_ping);
+        if let Some(model) = &self.cahn_hilliard && species == 0 {
+            let flip = self.integrator.is_explicit();
+            self.history = Some(cahn_hilliard_step(current, other, &dims, timestep, &bounds, &diffusion.spacing, model, flip));
+            return;
+        }
+        if let Some(model) = &self.vesicle && species == 0 {
+            vesicle_step(current, other, &dims, timestep, &bounds, &diffusion.spacing, model, &mut self.vesicle_targets, self.integrator.is_explicit());
+            return;
+        }
+        match self.integrator {
+            Integrator::Explicit => laplacian_step(current, other, &dims, timestep, &bounds, &diffusion),
+            Integrator::Implicit { theta, solver: ImplicitSolver::GaussSeidel { iterations } } => {
+                implicit_step(current, other, &dims, timestep, &bounds, &diffusion, theta, iterations);
+            },
+            Integrator::Implicit { theta, solver: ImplicitSolver::Multigrid(config) } => {
+                let problem = Helmholtz::implicit_diffusion(theta, timestep, diffusion.coefficient);
+                implicit_rhs(current, other, &dims, timestep, &bounds, &diffusion, theta);
+                self.history = Some(multigrid::solve(current, other, &dims, &diffusion.spacing, &bounds, &problem, &config));
+            }
+        }
+    }
+}
+
+impl SimulationBackend for CpuBackend {
+    fn dims(&self) -> Dims3 {
+        self.dims
+    }
+
+    fn init(&mut self, seed: u32) {
+        self.read_ping = true;
+        self.vesicle_targets = None;
+        for (species, buffers) in self.species.iter_mut().enumerate() {
+            if let Some(s) = self.registry.get(species) {
+                buffers.ping = s.initial.field(&self.dims, seed, species); // random ones as init.wgsl
+            }
+        }
+        if let Some(reaction) = &self.reaction {
+            for (species, field) in reaction.initial_fields(&self.dims, seed).into_iter().enumerate() {
+                self.species[species].ping = field;
+            }
+        }
+        if let Some(model) = &self.cahn_hilliard {
+            self.species[0].ping = model.initial_field(&self.dims, seed);
+        }
+        else if let Some(model) = &self.vesicle {
+            self.species[0].ping = model.initial_field(&self.dims, &self.diffusion.spacing);
+        }
+    }
+
+    /// Every species diffuses from the same parity, which flips once after all of them on explicit steps
+    /// Reaction, network and noise then update the current buffers in place
+    fn step(&mut self, timestep: f32) {
+        for species in 0..self.species.len() {
+            self.diffuse(species, timestep);
+        }
+        if self.integrator.is_explicit() { self.read_ping = !self.read_ping; }
+
+        if let Some(reaction) = &self.reaction {
+            let rates = reaction.rates();
+            let read_ping = self.read_ping;
+            let [u, v] = self.species.get_disjoint_mut([0, 1]).expect("Reacting needs two species");
+            reaction_step(u.buffers(read_ping).0, v.buffers(read_ping).0, &rates, timestep);
+        }
+
+        if let Some(network) = &self.network {
+            let read_ping = self.read_ping;
+            network_step(self.species.iter_mut().map(|s| s.buffers(read_ping).0).collect(), network, timestep);
+        }
+
+        if let Some((noise, seed)) = &self.noise {
+            let amplitudes = noise.amplitudes(self.noise_mobility(), timestep, &self.diffusion.spacing);
+            let (dims, bounds, (step, substep)) = (self.dims, self.species_bounds(0), self.step_index);
+            noise_step(self.species[0].buffers(self.read_ping).0, &dims, &bounds, noise, &amplitudes, *seed, step, substep);
+        }
+        self.step_index.1 = self.step_index.1.wrapping_add(1);
+    }
+
+    fn read_species(&mut self, species: usize) -> VoxelField {
+        VoxelField::new(self.dims, self.species[species].current(self.read_ping).to_vec())
+    }
+
+    fn write_species(&mut self, species: usize, field: &[f32]) {
+        assert!(field.len() == self.len(), "Field length does not match dims\n");
+        let read_ping = self.read_ping;
+        self.species[species].buffers(read_ping).0.copy_from_slice(field);
+        if species == 0 { self.vesicle_targets = None; }
+    }
+
+    fn species_count(&self) -> usize {
+        self.species.len()
+    }
+
+    fn restore_field(&mut self, field: &[f32], read_ping: bool) {
+        self.read_ping = read_ping;
+        self.write_species(0, field);
+    }
+
+    fn read_ping(&self) -> bool {
+        self.read_ping
+    }
+
+    fn set_boundaries(&mut self, bounds: &BoundaryConditions) {
+        self.bounds = *bounds;
+    }
+
+    fn set_diffusion(&mut self, diffusion: &Diffusion) {
+        self.diffusion = *diffusion;
+    }
+
+    fn set_integrator(&mut self, integrator: &Integrator) {
+        self.integrator = *integrator;
+        self.history = None;
+    }
+
+    fn set_species(&mut self, registry: &SpeciesRegistry, _resources: &Resources) {
+        self.set_registry(registry);
+    }
+
+    fn set_reaction(&mut self, reaction: Option<&Reaction>) {
+        assert!(reaction.is_none() || self.species.len() >= 2, "A reaction needs two species, see SpeciesRegistry::with_reaction()\n");
+        self.reaction = reaction.copied();
+    }
+
+    fn set_network(&mut self, network: Option<&ReactionNetwork>) {
+        assert!(network.is_none_or(|n| n.species_count() == self.species.len()), "Network is not indexed as the registry, see ReactionNetwork::resolve()\n");
+        self.network = network.cloned();
+    }
+
+    fn set_cahn_hilliard(&mut self, model: Option<&CahnHilliard>) {
+        self.cahn_hilliard = model.copied();
+        self.history = None;
+    }
+
+    fn set_vesicle(&mut self, model: Option<&Vesicle>) {
+        self.vesicle = model.copied();
+        self.vesicle_targets = None;
+    }
+
+    fn set_noise(&mut self, noise: Option<&ThermalNoise>, seed: u32) {
+        self.noise = noise.map(|noise| (*noise, seed));
+    }
+
+    fn set_step_index(&mut self, step: u64, substep: u32) {
+        self.step_index = (step, substep);
+    }
+
+    fn solver_history(&self) -> Option<&ConvergenceHistory> {
+        self.history.as_ref()
+    }
+
+    /// Uploads a species' current field into its ping plane each frame
+    fn sync_for_render(&mut self, resources: &Resources, gfx_ctx: &GraphicsContext, species: usize) -> bool {
+        let species = species.min(self.species.len() - 1);
+        resources.write_voxels(gfx_ctx, species, self.species[species].current(self.read_ping), &self.dims);
+        true
+    }
+}
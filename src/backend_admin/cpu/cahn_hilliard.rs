@@ -0,0 +1,44 @@
+use crate::backend_admin::cpu::{implicit::stencil, laplacian::par_map_voxels, multigrid};
+use crate::world::{
+    boundary::BoundaryConditions,
+    cahn_hilliard::CahnHilliard,
+    diffusion::Diffusion,
+    field::voxel_index,
+    multigrid::ConvergenceHistory,
+    voxel_grid::Dims3};
+
+// CPU reference for cahn_hilliard.wgsl and the two solves GpuBackend follows it with
+// The explicit part is evaluated per voxel first and the stencil then run over it,
+// which is what the shader does when it evaluates it at each neighbour
+
+/// One Cahn-Hilliard step, see world/cahn_hilliard.rs
+/// field holds φ, scratch is the other ping/pong buffer
+/// flip: the step's result lands in scratch rather than field, for steps whose parity flips (explicit integrators),
+/// so φ' is wherever every other species' step left its field
+pub fn cahn_hilliard_step(field: &mut [f32], scratch: &mut [f32], dims: &Dims3, timestep: f32, bounds: &BoundaryConditions, spacing: &[f32; 3], model: &CahnHilliard, flip: bool) -> ConvergenceHistory {
+    let bounds = bounds.zero_flux();
+    let [first, second] = model.factors(timestep);
+    let config = model.solver();
+
+    // RIGHT HAND SIDE, then (1 - a L) w = rhs in place of φ
+    cahn_hilliard_rhs(field, scratch, dims, timestep, &bounds, spacing, model);
+    multigrid::solve(field, scratch, dims, spacing, &bounds, &first, &config);
+
+    // (1 - b L) φ' = w, w in both buffers so either can serve as the first guess
+    scratch.copy_from_slice(field);
+    if flip { multigrid::solve(scratch, field, dims, spacing, &bounds, &second, &config) }
+    else { multigrid::solve(field, scratch, dims, spacing, &bounds, &second, &config) }
+}
+
+/// rhs = φ + dt M L (A (φ³ - φ) - S φ), as cahn_hilliard.wgsl's rhs pass, bounds already zero flux
+pub fn cahn_hilliard_rhs(field: &[f32], rhs: &mut [f32], dims: &Dims3, timestep: f32, bounds: &BoundaryConditions, spacing: &[f32; 3], model: &CahnHilliard) {
+    let weights = Diffusion::new(model.mobility, *spacing).stencil_weights();
+    let (depth, stabiliser) = (model.well_depth, model.stabiliser(timestep));
+    let explicit: Vec<f32> = field.iter().map(|phi| (depth * ((phi * phi * phi) - phi)) - (stabiliser * phi)).collect();
+    let explicit_weight = weights[0] * timestep;
+    par_map_voxels(rhs, dims, |x, y, z| {
+        let idx = voxel_index(dims, x, y, z);
+        let (neighbours, weight) = stencil(&explicit, dims, x, y, z, bounds, &weights, &[1.0; 3]);
+        field[idx] + (explicit_weight * (neighbours - (weight * explicit[idx])))
+    });
+}
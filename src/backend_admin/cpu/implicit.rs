@@ -0,0 +1,79 @@
+use crate::backend_admin::cpu::laplacian::par_map_voxels;
+use crate::world::{
+    boundary::{Boundary, BoundaryConditions},
+    diffusion::Diffusion,
+    field::voxel_index,
+    integrator::{sweep_colour, sweep_colours},
+    voxel_grid::Dims3};
+
+// CPU reference for implicit.wgsl
+// Same right hand side, same sweep colours (red-black, plus a third on odd periodic axes) and the same stencil with Neumann faces dropped,
+// so after a fixed number of sweeps both backends hold the same iterate
+
+/// One implicit theta step, (1 - θ dt D L) u' = (1 + (1 - θ) dt D L) u
+/// field holds u and is relaxed in place into u' by iterations Gauss-Seidel sweeps over every colour,
+/// rhs is scratch of the same length (the other ping/pong buffer)
+pub fn implicit_step(field: &mut [f32], rhs: &mut [f32], dims: &Dims3, timestep: f32, bounds: &BoundaryConditions, diffusion: &Diffusion, theta: f32, iterations: u32) {
+    let len = (dims[0] * dims[1] * dims[2]) as usize;
+    assert!(field.len() == len && rhs.len() == len, "Field length does not match dims\n");
+    let weights = diffusion.stencil_weights();
+
+    // RIGHT HAND SIDE
+    implicit_rhs(field, rhs, dims, timestep, bounds, diffusion, theta);
+
+    // SWEEPS, single threaded: each colour reads the other in place
+    let implicit_weight = theta * weights[0] * timestep;
+    for _ in 0..iterations {
+        for colour in 0..sweep_colours(dims, bounds) {
+            for z in 0..dims[2] {
+                for y in 0..dims[1] {
+                    for x in (0..dims[0]).filter(|x| sweep_colour(dims, bounds, *x, y, z) == colour) {
+                        let (neighbours, weight) = stencil(field, dims, x, y, z, bounds, &weights, &[1.0; 3]);
+                        let idx = voxel_index(dims, x, y, z);
+                        field[idx] = (rhs[idx] + (implicit_weight * neighbours)) / (1.0 + (implicit_weight * weight));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// rhs = (1 + (1 - θ) dt D L) u, as implicit.wgsl's rhs pass
+pub fn implicit_rhs(field: &[f32], rhs: &mut [f32], dims: &Dims3, timestep: f32, bounds: &BoundaryConditions, diffusion: &Diffusion, theta: f32) {
+    let weights = diffusion.stencil_weights();
+    let explicit_weight = (1.0 - theta) * weights[0] * timestep;
+    par_map_voxels(rhs, dims, |x, y, z| {
+        let c_i = field[voxel_index(dims, x, y, z)];
+        let (neighbours, weight) = stencil(field, dims, x, y, z, bounds, &weights, &[1.0; 3]);
+        c_i + (explicit_weight * (neighbours - (weight * c_i)))
+    });
+}
+
+/// (sum of weighted neighbours, sum of their weights), so L u = neighbours - weight * u at the centre
+/// A Neumann face contributes neither, which is what its edge valued halo amounts to in laplacian.wgsl
+/// weights[1..3] scale each axis, [0] is ignored
+/// dirichlet scales Dirichlet faces per axis: 1.0 when the halo holds the value, as in every single grid pass,
+/// more on coarse multigrid levels (see multigrid::Level::dirichlet_weights)
+pub fn stencil(src: &[f32], dims: &Dims3, x: u32, y: u32, z: u32, bounds: &BoundaryConditions, weights: &[f32; 4], dirichlet: &[f32; 3]) -> (f32, f32) {
+    let [x_bounds, y_bounds, z_bounds] = &bounds.faces;
+    let side = |neighbour: Option<usize>, boundary: Boundary, wrap_idx: usize, axis: usize| -> (f32, f32) {
+        let weight = weights[axis + 1];
+        match (neighbour, boundary) {
+            (Some(idx), _) => (src[idx] * weight, weight),
+            (None, Boundary::Periodic) => (src[wrap_idx] * weight, weight),
+            (None, Boundary::Dirichlet(value)) => (value * (weight * dirichlet[axis]), weight * dirichlet[axis]),
+            (None, Boundary::Neumann) => (0.0, 0.0)
+        }
+    };
+
+    // same order as implicit.wgsl, None past a face
+    let terms = [
+        side((x > 0).then(|| voxel_index(dims, x - 1, y, z)), x_bounds[0], voxel_index(dims, dims[0] - 1, y, z), 0),
+        side((x + 1 < dims[0]).then(|| voxel_index(dims, x + 1, y, z)), x_bounds[1], voxel_index(dims, 0, y, z), 0),
+        side((y > 0).then(|| voxel_index(dims, x, y - 1, z)), y_bounds[0], voxel_index(dims, x, dims[1] - 1, z), 1),
+        side((y + 1 < dims[1]).then(|| voxel_index(dims, x, y + 1, z)), y_bounds[1], voxel_index(dims, x, 0, z), 1),
+        side((z > 0).then(|| voxel_index(dims, x, y, z - 1)), z_bounds[0], voxel_index(dims, x, y, dims[2] - 1), 2),
+        side((z + 1 < dims[2]).then(|| voxel_index(dims, x, y, z + 1)), z_bounds[1], voxel_index(dims, x, y, 0), 2)
+    ];
+    terms.iter().fold((0.0, 0.0), |(neighbours, weight), (n, w)| (neighbours + n, weight + w))
+}
@@ -0,0 +1,134 @@
+use std::thread;
+use crate::world::{boundary::BoundaryConditions, diffusion::Diffusion, field::voxel_index, voxel_grid::Dims3};
+
+// CPU reference for laplacian.wgsl
+// Same flat indexing as the shaders (x + y * dims[0] + z * dims[0] * dims[1]),
+// same boundary halos (see world/boundary.rs) and the same explicit Euler update,
+// so GPU output can be checked voxel by voxel against it
+
+/// One explicit Euler step of the 7-point stencil, D and dx from diffusion as in laplacian.wgsl
+/// Reads src, writes dst (ping -> pong), split across threads in z slabs
+/// A neighbour outside the grid takes its value from bounds, per axis and face
+pub fn laplacian_step(src: &[f32], dst: &mut [f32], dims: &Dims3, timestep: f32, bounds: &BoundaryConditions, diffusion: &Diffusion) {
+    let len = (dims[0] * dims[1] * dims[2]) as usize;
+    assert!(src.len() == len && dst.len() == len, "Field length does not match dims\n");
+    let weights = diffusion.stencil_weights();
+    par_map_voxels(dst, dims, |x, y, z| next_value(src, dims, x, y, z, timestep, bounds, &weights));
+}
+
+/// Writes f(x, y, z) to every voxel of dst, split across threads in z slabs
+/// Shared by the CPU passes that read one buffer and write another, RDME's counts included
+pub fn par_map_voxels<T: Send>(dst: &mut [T], dims: &Dims3, f: impl Fn(u32, u32, u32) -> T + Sync) {
+    let plane = (dims[0] * dims[1]) as usize;
+    let threads = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(dims[2] as usize);
+    let slab_depth = (dims[2] as usize).div_ceil(threads); // z planes per thread
+    let f = &f;
+
+    thread::scope(|scope| {
+        for (slab, chunk) in dst.chunks_mut(slab_depth * plane).enumerate() {
+            let z_start = (slab * slab_depth) as u32;
+            scope.spawn(move || {
+                for (offset, out) in chunk.iter_mut().enumerate() {
+                    let z = z_start + (offset / plane) as u32;
+                    let y = ((offset % plane) / dims[0] as usize) as u32;
+                    let x = (offset % dims[0] as usize) as u32;
+                    *out = f(x, y, z);
+                }
+            });
+        }
+    });
+}
+
+/// Explicit Euler update for a single voxel
+/// weights is Diffusion::stencil_weights(), [D, 1/dx^2, 1/dy^2, 1/dz^2]
+fn next_value(src: &[f32], dims: &Dims3, x: u32, y: u32, z: u32, timestep: f32, bounds: &BoundaryConditions, weights: &[f32; 4]) -> f32 {
+    let c_i = src[voxel_index(dims, x, y, z)];
+    let [x_bounds, y_bounds, z_bounds] = &bounds.faces;
+
+    // halos at each face: wrapped neighbour is the voxel on the opposite face (periodic)
+    let c_i_xmin = if x > 0 { src[voxel_index(dims, x - 1, y, z)] }
+        else { x_bounds[0].halo(c_i, src[voxel_index(dims, dims[0] - 1, y, z)]) };
+    let c_i_xplus = if x + 1 < dims[0] { src[voxel_index(dims, x + 1, y, z)] }
+        else { x_bounds[1].halo(c_i, src[voxel_index(dims, 0, y, z)]) };
+    let c_i_ymin = if y > 0 { src[voxel_index(dims, x, y - 1, z)] }
+        else { y_bounds[0].halo(c_i, src[voxel_index(dims, x, dims[1] - 1, z)]) };
+    let c_i_yplus = if y + 1 < dims[1] { src[voxel_index(dims, x, y + 1, z)] }
+        else { y_bounds[1].halo(c_i, src[voxel_index(dims, x, 0, z)]) };
+    let c_i_zmin = if z > 0 { src[voxel_index(dims, x, y, z - 1)] }
+        else { z_bounds[0].halo(c_i, src[voxel_index(dims, x, y, dims[2] - 1)]) };
+    let c_i_zplus = if z + 1 < dims[2] { src[voxel_index(dims, x, y, z + 1)] }
+        else { z_bounds[1].halo(c_i, src[voxel_index(dims, x, y, 0)]) };
+
+    // LAPLACIAN, same operation order as laplacian.wgsl
+    let laplacian = ((c_i_xmin + c_i_xplus - (2.0 * c_i)) * weights[1])
+        + ((c_i_ymin + c_i_yplus - (2.0 * c_i)) * weights[2])
+        + ((c_i_zmin + c_i_zplus - (2.0 * c_i)) * weights[3]);
+    c_i + ((weights[0] * timestep) * laplacian)
+}
+
+/// Largest absolute voxel-wise difference between two fields, and where it occurs
+/// Used to check GPU output against laplacian_step
+pub fn max_abs_difference(a: &[f32], b: &[f32]) -> (usize, f32) {
+    assert!(a.len() == b.len(), "Fields differ in length\n");
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (a - b).abs())
+        .enumerate()
+        .fold((0, 0.0), |(max_idx, max), (idx, diff)| {
+            if diff > max { (idx, diff) } else { (max_idx, max) }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conserves_mass_under_neumann_and_periodic_bounds() {
+        let dims = [7, 5, 6];
+        let diffusion = Diffusion::new(1.0, [1.0, 1.25, 1.5]);
+        let timestep = diffusion.stable_timestep();
+        let total = |field: &[f32]| field.iter().map(|c| *c as f64).sum::<f64>();
+        for spec in ["xyz=neumann", "xyz=periodic", "x=periodic,z=periodic"] {
+            let bounds = BoundaryConditions::parse(spec).unwrap();
+            let mut src: Vec<f32> = (0..7 * 5 * 6).map(|i| 0.5 + 0.4 * (i as f32 * 0.9).sin()).collect();
+            let mut dst = vec![0.0; src.len()];
+            let before = total(&src);
+            for _ in 0..10 {
+                laplacian_step(&src, &mut dst, &dims, timestep, &bounds, &diffusion);
+                std::mem::swap(&mut src, &mut dst);
+            }
+            assert!((total(&src) - before).abs() < 1e-4, "{}: total moved from {} to {}", spec, before, total(&src));
+        }
+    }
+
+    #[test]
+    fn one_step_matches_the_hand_computed_stencil() {
+        // a unit spike in the middle of a 3x3x3 grid, dy twice dx and dz, and a Dirichlet 1 on the x- face
+        let dims = [3, 3, 3];
+        let diffusion = Diffusion::new(2.0, [1.0, 2.0, 1.0]);
+        let bounds = BoundaryConditions::parse("x-=dirichlet:1").unwrap();
+        let mut src = vec![0.0; 27];
+        src[voxel_index(&dims, 1, 1, 1)] = 1.0;
+        let mut dst = vec![0.0; 27];
+        laplacian_step(&src, &mut dst, &dims, 0.05, &bounds, &diffusion);
+
+        // D dt = 0.1, weights 1, 0.25, 1: the spike loses 0.1 * 2 * 2.25, each face neighbour gains 0.1 * its weight,
+        // and the x = 0 plane gains 0.1 from the reservoir
+        let mut expected = vec![0.0; 27];
+        expected[voxel_index(&dims, 1, 1, 1)] = 0.55;
+        for (x, y, z, gain) in [(0, 1, 1, 0.1), (2, 1, 1, 0.1), (1, 0, 1, 0.025), (1, 2, 1, 0.025), (1, 1, 0, 0.1), (1, 1, 2, 0.1)] {
+            expected[voxel_index(&dims, x, y, z)] = gain;
+        }
+        for y in 0..3 {
+            for z in 0..3 {
+                expected[voxel_index(&dims, 0, y, z)] += 0.1;
+            }
+        }
+        let (idx, difference) = max_abs_difference(&dst, &expected);
+        assert!(difference < 1e-6, "voxel {} is off by {}", idx, difference);
+    }
+}
@@ -0,0 +1,12 @@
+pub mod laplacian;
+pub mod implicit;
+pub mod multigrid;
+pub mod reaction;
+pub mod network;
+pub mod cahn_hilliard;
+pub mod vesicle;
+pub mod noise;
+pub mod rdme;
+pub mod particles;
+pub mod backend;
+pub mod rdme_backend;
\ No newline at end of file
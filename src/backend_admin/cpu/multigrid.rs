@@ -0,0 +1,191 @@
+use crate::{
+    backend_admin::cpu::{implicit::stencil, laplacian::par_map_voxels},
+    world::{
+        boundary::{Boundary, BoundaryConditions},
+        field::voxel_index,
+        integrator::{sweep_colour, sweep_colours},
+        multigrid::{self, ConvergenceHistory, Helmholtz, Level, MultigridConfig},
+        voxel_grid::{Dims3, P3}}
+};
+
+// CPU reference for multigrid.wgsl
+// Same hierarchy, smoother, restriction and prolongation, so residual histories can be compared cycle by cycle
+
+/// Solves (α - β L) u = f by V-cycles, u holds the initial guess and is overwritten with the solution
+/// spacing is the finest level's voxel spacing, bounds its faces (coarse levels use multigrid::coarse_bounds)
+pub fn solve(u: &mut [f32], f: &[f32], dims: &Dims3, spacing: &P3, bounds: &BoundaryConditions, problem: &Helmholtz, config: &MultigridConfig) -> ConvergenceHistory {
+    let levels = multigrid::hierarchy(dims);
+    assert!(u.len() == levels[0].len() && f.len() == levels[0].len(), "Field length does not match dims\n");
+
+    let mut us: Vec<Vec<f32>> = levels.iter().map(|l| vec![0.0; l.len()]).collect();
+    let mut fs: Vec<Vec<f32>> = levels.iter().map(|l| vec![0.0; l.len()]).collect();
+    let mut rs: Vec<Vec<f32>> = levels.iter().map(|l| vec![0.0; l.len()]).collect();
+    us[0].copy_from_slice(u);
+    fs[0].copy_from_slice(f);
+
+    let solver = Solver {
+        levels: &levels,
+        spacing: *spacing,
+        bounds: *bounds,
+        coarse_bounds: multigrid::coarse_bounds(bounds),
+        problem: *problem,
+        config: *config
+    };
+
+    let f_norm = norm(f);
+    let scale = if f_norm > 0.0 { f_norm } else { 1.0 }; // f = 0: absolute residual
+    let mut history = ConvergenceHistory::default();
+    history.residuals.push(solver.residual(0, &us[0], &fs[0], &mut rs[0]) / scale);
+    for _ in 0..config.max_cycles {
+        if history.residuals.last().is_some_and(|r| *r <= config.tolerance as f64) { break; }
+        solver.v_cycle(0, &mut us, &mut fs, &mut rs);
+        history.residuals.push(solver.residual(0, &us[0], &fs[0], &mut rs[0]) / scale);
+    }
+    history.converged = history.residuals.last().is_some_and(|r| *r <= config.tolerance as f64);
+
+    u.copy_from_slice(&us[0]);
+    history
+}
+
+struct Solver<'a> {
+    levels: &'a [Level],
+    spacing: P3,
+    bounds: BoundaryConditions, // finest level
+    coarse_bounds: BoundaryConditions,
+    problem: Helmholtz,
+    config: MultigridConfig
+}
+
+impl Solver<'_> {
+    fn bounds(&self, level: usize) -> &BoundaryConditions {
+        if level == 0 { &self.bounds } else { &self.coarse_bounds }
+    }
+
+    fn weights(&self, level: usize) -> [f32; 4] {
+        self.problem.weights(&self.levels[level].spacing(&self.spacing))
+    }
+
+    fn v_cycle(&self, level: usize, us: &mut [Vec<f32>], fs: &mut [Vec<f32>], rs: &mut [Vec<f32>]) {
+        if level + 1 == self.levels.len() {
+            self.smooth(level, &mut us[level], &fs[level], self.config.coarse_sweeps);
+            return;
+        }
+        self.smooth(level, &mut us[level], &fs[level], self.config.pre_smooth);
+        self.residual(level, &us[level], &fs[level], &mut rs[level]);
+        self.restrict(level, &rs[level], &mut fs[level + 1]);
+        us[level + 1].fill(0.0);
+        self.v_cycle(level + 1, us, fs, rs);
+        let (fine, coarse) = us.split_at_mut(level + 1);
+        self.prolong(level, &coarse[0], &mut fine[level]);
+        self.smooth(level, &mut us[level], &fs[level], self.config.post_smooth);
+    }
+
+    /// Red-black Gauss-Seidel (a third colour on odd periodic axes, see integrator::sweep_colour), u = (f + neighbours) / (α + weights)
+    fn smooth(&self, level: usize, u: &mut [f32], f: &[f32], sweeps: u32) {
+        let dims = self.levels[level].dims;
+        let weights = self.weights(level);
+        let bounds = self.bounds(level);
+        let dirichlet = self.levels[level].dirichlet_weights();
+        for _ in 0..sweeps {
+            for colour in 0..sweep_colours(&dims, bounds) {
+                for z in 0..dims[2] {
+                    for y in 0..dims[1] {
+                        for x in (0..dims[0]).filter(|x| sweep_colour(&dims, bounds, *x, y, z) == colour) {
+                            let (neighbours, weight) = stencil(u, &dims, x, y, z, bounds, &weights, &dirichlet);
+                            let idx = voxel_index(&dims, x, y, z);
+                            u[idx] = (f[idx] + neighbours) / (weights[0] + weight);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// r = f - (α - β L) u, returns ||r||
+    fn residual(&self, level: usize, u: &[f32], f: &[f32], r: &mut [f32]) -> f64 {
+        let dims = self.levels[level].dims;
+        let weights = self.weights(level);
+        let bounds = self.bounds(level);
+        let dirichlet = self.levels[level].dirichlet_weights();
+        par_map_voxels(r, &dims, |x, y, z| {
+            let idx = voxel_index(&dims, x, y, z);
+            let (neighbours, weight) = stencil(u, &dims, x, y, z, bounds, &weights, &dirichlet);
+            f[idx] - (((weights[0] + weight) * u[idx]) - neighbours)
+        });
+        norm(r)
+    }
+
+    /// Coarse f is the mean of the fine residual over each coarse voxel's children
+    fn restrict(&self, level: usize, r: &[f32], coarse_f: &mut [f32]) {
+        let fine = self.levels[level].dims;
+        let coarse = self.levels[level + 1].dims;
+        let ratio = multigrid::ratios(&self.levels[level], &self.levels[level + 1]);
+        par_map_voxels(coarse_f, &coarse, |cx, cy, cz| {
+            let (mut sum, mut count) = (0.0, 0.0);
+            for z in (cz * ratio[2])..((cz + 1) * ratio[2]) {
+                for y in (cy * ratio[1])..((cy + 1) * ratio[1]) {
+                    for x in (cx * ratio[0])..((cx + 1) * ratio[0]) {
+                        sum += r[voxel_index(&fine, x, y, z)];
+                        count += 1.0;
+                    }
+                }
+            }
+            sum / count
+        });
+    }
+
+    /// Adds the trilinearly interpolated coarse correction to u
+    /// Corrections just outside the coarse grid come from the coarse faces: zero (Dirichlet), wrapped (periodic) or the edge voxel (Neumann)
+    fn prolong(&self, level: usize, coarse_u: &[f32], u: &mut [f32]) {
+        let fine = self.levels[level].dims;
+        let coarse = self.levels[level + 1].dims;
+        let ratio = multigrid::ratios(&self.levels[level], &self.levels[level + 1]);
+        let at = |x: i32, y: i32, z: i32| -> f32 {
+            let mut idx = [0; 3];
+            for (axis, c) in [x, y, z].into_iter().enumerate() {
+                idx[axis] = match coarse_index(c, coarse[axis], &self.coarse_bounds.faces[axis]) {
+                    Some(c) => c,
+                    None => return 0.0 // Dirichlet halo
+                };
+            }
+            coarse_u[voxel_index(&coarse, idx[0], idx[1], idx[2])]
+        };
+        let lerp = |a: f32, b: f32, t: f32| a + ((b - a) * t);
+        for z in 0..fine[2] {
+            let (z0, tz) = interpolation(z, ratio[2]);
+            for y in 0..fine[1] {
+                let (y0, ty) = interpolation(y, ratio[1]);
+                for x in 0..fine[0] {
+                    let (x0, tx) = interpolation(x, ratio[0]);
+                    let (x1, y1, z1) = (x0 + 1, y0 + 1, z0 + 1);
+                    let correction = lerp(
+                        lerp(lerp(at(x0, y0, z0), at(x1, y0, z0), tx), lerp(at(x0, y1, z0), at(x1, y1, z0), tx), ty),
+                        lerp(lerp(at(x0, y0, z1), at(x1, y0, z1), tx), lerp(at(x0, y1, z1), at(x1, y1, z1), tx), ty),
+                        tz);
+                    u[voxel_index(&fine, x, y, z)] += correction;
+                }
+            }
+        }
+    }
+}
+
+/// Lower coarse voxel of fine voxel i's centre (-1 before the first) and the weight of the one above it
+fn interpolation(i: u32, ratio: u32) -> (i32, f32) {
+    let position = ((i as f32 + 0.5) / ratio as f32) - 0.5; // in coarse voxel centres
+    let floor = position.floor();
+    (floor as i32, position - floor)
+}
+
+/// Coarse index c along an axis of length dim, resolving one voxel past either face; None for a Dirichlet halo
+fn coarse_index(c: i32, dim: u32, faces: &[Boundary; 2]) -> Option<u32> {
+    let face = if c < 0 { 0 } else if c >= dim as i32 { 1 } else { return Some(c as u32) };
+    match faces[face] {
+        Boundary::Periodic => Some(c.rem_euclid(dim as i32) as u32),
+        Boundary::Dirichlet(_) => None,
+        Boundary::Neumann => Some(c.clamp(0, dim as i32 - 1) as u32)
+    }
+}
+
+pub fn norm(field: &[f32]) -> f64 {
+    field.iter().map(|v| (*v as f64) * (*v as f64)).sum::<f64>().sqrt()
+}
@@ -0,0 +1,41 @@
+use std::thread;
+use crate::world::{network::ReactionNetwork, species::MAX_SPECIES};
+
+// CPU reference for network.wgsl
+// Pointwise like cpu/reaction.rs, every species' field split into the same equal chunks across threads
+
+/// One explicit Euler step of the network in place on every species' current field: c += dt dc/dt
+/// fields holds the network's species in registry order, all of one length
+pub fn network_step(fields: Vec<&mut [f32]>, network: &ReactionNetwork, timestep: f32) {
+    assert!(fields.len() == network.species_count(), "Network and fields differ in species\n");
+    let len = fields[0].len();
+    assert!(fields.iter().all(|f| f.len() == len), "Species differ in length\n");
+    let threads = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let chunk = len.div_ceil(threads).max(1);
+
+    // chunks[c][s]: species s's part of chunk c
+    let mut chunks: Vec<Vec<&mut [f32]>> = (0..len.div_ceil(chunk)).map(|_| Vec::new()).collect();
+    for field in fields {
+        for (c, part) in field.chunks_mut(chunk).enumerate() {
+            chunks[c].push(part);
+        }
+    }
+
+    thread::scope(|scope| {
+        for mut species in chunks {
+            scope.spawn(move || {
+                let (mut concentrations, mut rates) = ([0.0; MAX_SPECIES], [0.0; MAX_SPECIES]);
+                let n = species.len();
+                for i in 0..species[0].len() {
+                    for (c, field) in concentrations.iter_mut().zip(species.iter()) { *c = field[i]; }
+                    network.rates(&concentrations, &mut rates);
+                    for (field, rate) in species.iter_mut().zip(&rates[..n]) {
+                        field[i] += timestep * rate;
+                    }
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,112 @@
+use crate::backend_admin::cpu::laplacian::par_map_voxels;
+use crate::world::{
+    boundary::{Boundary, BoundaryConditions},
+    field::voxel_index,
+    noise::ThermalNoise,
+    rng::{self, NOISE_STREAM},
+    voxel_grid::Dims3};
+
+// CPU reference for noise.wgsl
+// Same counters, same faces and the same order of summation, so both backends kick every voxel alike
+// up to the rounding of ln, cos and sin in the Box-Muller transform
+
+/// Adds one step's thermal noise to field in place, see world/noise.rs
+/// amplitudes is ThermalNoise::amplitudes() for the step, step and substep the index the noise is counted by
+pub fn noise_step(field: &mut [f32], dims: &Dims3, bounds: &BoundaryConditions, noise: &ThermalNoise, amplitudes: &[f32; 3], seed: u32, step: u64, substep: u32) {
+    let key = [seed, NOISE_STREAM];
+    let normals = |idx: usize| rng::normals([idx as u32, substep, step as u32, (step >> 32) as u32], key);
+    let mut kicks = vec![0.0; field.len()];
+    par_map_voxels(&mut kicks, dims, |x, y, z| {
+        let idx = voxel_index(dims, x, y, z);
+        if !noise.conserved { return amplitudes[0] * normals(idx)[0]; }
+
+        // each voxel owns the face to its upper neighbour along every axis, the transfer across it is amplitude * normal
+        let own = normals(idx);
+        let mut kick = 0.0;
+        for (axis, p) in [x, y, z].into_iter().enumerate() {
+            let periodic = bounds.faces[axis][0] == Boundary::Periodic;
+            let lower = match (p > 0, periodic) {
+                (true, _) => Some(p - 1),
+                (false, true) => Some(dims[axis] - 1),
+                (false, false) => None
+            };
+            let incoming = match lower {
+                Some(l) => {
+                    let mut n = [x, y, z];
+                    n[axis] = l;
+                    amplitudes[axis] * normals(voxel_index(dims, n[0], n[1], n[2]))[axis]
+                },
+                None => 0.0
+            };
+            let outgoing = if p + 1 < dims[axis] || periodic { amplitudes[axis] * own[axis] } else { 0.0 };
+            kick += incoming - outgoing;
+        }
+        kick
+    });
+    for (phi, kick) in field.iter_mut().zip(kicks) {
+        *phi += kick;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIMS: Dims3 = [5, 4, 3];
+
+    fn kicked(conserved: bool, seed: u32, step: u64, substep: u32) -> Vec<f32> {
+        let mut field = vec![0.0; 60];
+        let noise = ThermalNoise::new(1.0, conserved);
+        noise_step(&mut field, &DIMS, &BoundaryConditions::uniform(Boundary::Periodic), &noise, &[0.5, 0.25, 0.125], seed, step, substep);
+        field
+    }
+
+    #[test]
+    fn noise_step_reproduces_for_the_same_seed_and_index() {
+        for conserved in [false, true] {
+            let field = kicked(conserved, 7, 3, 1);
+            assert!(field.iter().any(|phi| *phi != 0.0));
+            assert_eq!(field, kicked(conserved, 7, 3, 1));
+            assert_ne!(field, kicked(conserved, 8, 3, 1));
+            assert_ne!(field, kicked(conserved, 7, 4, 1));
+            assert_ne!(field, kicked(conserved, 7, 3, 2));
+        }
+    }
+
+    #[test]
+    fn conserved_noise_keeps_the_total() {
+        let dims = [8, 7, 6];
+        let noise = ThermalNoise::new(0.5, true);
+        let amplitudes = noise.amplitudes(1.0, 0.1, &[1.0, 1.25, 1.5]);
+        for bounds in [BoundaryConditions::uniform(Boundary::Periodic), BoundaryConditions::uniform(Boundary::Neumann),
+                       BoundaryConditions::parse("x=periodic").unwrap()] {
+            let mut field: Vec<f32> = (0..8 * 7 * 6).map(|i| 0.5 + 0.3 * (i as f32).sin()).collect();
+            let total = |field: &[f32]| field.iter().map(|phi| *phi as f64).sum::<f64>();
+            let before = total(&field);
+            for step in 0..4 {
+                noise_step(&mut field, &dims, &bounds, &noise, &amplitudes, 5, step, 0);
+            }
+            assert!((total(&field) - before).abs() < 1e-4, "{:?}: total moved from {} to {}", bounds, before, total(&field));
+        }
+    }
+
+    #[test]
+    fn non_conserved_kicks_have_the_temperature_variance() {
+        let dims = [16, 16, 16];
+        let noise = ThermalNoise::new(0.5, false);
+        let amplitudes = noise.amplitudes(2.0, 0.1, &[1.0, 1.0, 1.0]);
+        assert_eq!(amplitudes[0], (2.0f32 * 2.0 * 0.5 * 0.1).sqrt());
+        let mut kicks = Vec::new();
+        for step in 0..4 {
+            let mut field = vec![0.0; 16 * 16 * 16];
+            noise_step(&mut field, &dims, &BoundaryConditions::default(), &noise, &amplitudes, 9, step, 0);
+            kicks.extend(field.into_iter().map(|kick| kick as f64));
+        }
+        let n = kicks.len() as f64;
+        let mean = kicks.iter().sum::<f64>() / n;
+        let variance = kicks.iter().map(|k| (k - mean) * (k - mean)).sum::<f64>() / (n - 1.0);
+        let expected = (amplitudes[0] as f64).powi(2);
+        assert!(mean.abs() < 5.0 * (expected / n).sqrt(), "mean {}", mean);
+        assert!((variance / expected - 1.0).abs() < 5.0 * (2.0 / n).sqrt(), "variance {} against {}", variance, expected);
+    }
+}
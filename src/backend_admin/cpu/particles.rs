@@ -0,0 +1,86 @@
+use crate::world::{
+    boundary::{Boundary, BoundaryConditions},
+    brownian_motion::Particles,
+    field::voxel_index,
+    voxel_grid::{Dims3, P3}};
+
+// CPU reference for particles.wgsl's spatial hash
+// Cells are the grid's voxels: a counting sort groups the particles by the voxel they sit in,
+// and a particle's neighbours within a cutoff of at most one voxel spacing lie in the 27 cells around its own
+// The sort is stable here, the shader's order within a cell is whatever its atomics gave, so only the grouping matches
+
+/// Particles grouped by cell, cell c's are order[cell_start[c]..cell_start[c + 1]]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellList {
+    pub cell_start: Vec<u32>, // cells + 1 entries, the exclusive scan of the counts per cell
+    pub order: Vec<u32> // particle indices, cell by cell
+}
+
+impl CellList {
+    /// Counting sort of particles into the voxels of a dims grid of spacing nm
+    pub fn new(particles: &Particles, spacing: &P3, dims: &Dims3) -> Self {
+        let cells: Vec<usize> = (0..particles.len())
+            .map(|particle| {
+                let [x, y, z] = particles.voxel(particle, spacing, dims);
+                voxel_index(dims, x, y, z)
+            })
+            .collect();
+
+        let mut cell_start = vec![0u32; (dims[0] * dims[1] * dims[2]) as usize + 1];
+        for cell in &cells { cell_start[*cell] += 1; }
+        let mut running = 0;
+        for start in cell_start.iter_mut() {
+            let count = *start;
+            *start = running;
+            running += count;
+        }
+
+        let mut next = cell_start.clone();
+        let mut order = vec![0u32; particles.len()];
+        for (particle, cell) in cells.iter().enumerate() {
+            order[next[*cell] as usize] = particle as u32;
+            next[*cell] += 1;
+        }
+        CellList { cell_start: cell_start, order: order }
+    }
+
+    /// Particles of a cell
+    pub fn cell(&self, cell: usize) -> &[u32] {
+        &self.order[self.cell_start[cell] as usize..self.cell_start[cell + 1] as usize]
+    }
+}
+
+/// Particles within cutoff nm of each particle, itself excluded, in Particles' order
+/// Periodic axes of bounds measure to the nearest image, the others stop at the walls, as particles.wgsl
+pub fn neighbour_counts(particles: &Particles, list: &CellList, spacing: &P3, dims: &Dims3, bounds: &BoundaryConditions, cutoff: f32) -> Vec<u32> {
+    assert!(cutoff > 0.0 && spacing.iter().all(|dx| cutoff <= *dx), "Neighbour cutoff should be > 0.0 and at most the voxel spacing\n");
+    let periodic = [0, 1, 2].map(|axis| bounds.faces[axis][0] == Boundary::Periodic);
+    let extent: P3 = [0, 1, 2].map(|axis| dims[axis] as f32 * spacing[axis]);
+
+    (0..particles.len()).map(|particle| {
+        let position = particles.positions[particle];
+        let own = particles.voxel(particle, spacing, dims).map(|c| c as i64);
+        let mut count = 0;
+        for offset in (0..27).map(|n| [n % 3 - 1, n / 3 % 3 - 1, n / 9 - 1]) {
+            let mut cell = [0u32; 3];
+            let inside = (0..3).all(|axis| {
+                let dims = dims[axis] as i64;
+                let c = own[axis] + offset[axis];
+                cell[axis] = c.rem_euclid(dims) as u32;
+                if periodic[axis] { dims >= 3 || (0..dims).contains(&offset[axis]) } // each cell once
+                else { (0..dims).contains(&c) }
+            });
+            if !inside { continue; }
+
+            for other in list.cell(voxel_index(dims, cell[0], cell[1], cell[2])) {
+                if *other as usize == particle { continue; }
+                let d = [0, 1, 2].map(|axis| {
+                    let d = particles.positions[*other as usize][axis] - position[axis];
+                    if periodic[axis] { d - extent[axis] * (d / extent[axis] + 0.5).floor() } else { d }
+                });
+                if d[0] * d[0] + d[1] * d[1] + d[2] * d[2] < cutoff * cutoff { count += 1; }
+            }
+        }
+        count
+    }).collect()
+}
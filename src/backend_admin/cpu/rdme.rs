@@ -0,0 +1,246 @@
+use std::thread;
+use crate::{
+    backend_admin::cpu::laplacian::par_map_voxels,
+    world::{
+        boundary::{Boundary, BoundaryConditions},
+        field::voxel_index,
+        network::{MAX_REACTIONS, ReactionNetwork},
+        rdme::Rdme,
+        rng::{BATH_STREAM, HOP_STREAM, SSA_STREAM, UniformStream, binomial, poisson},
+        species::MAX_SPECIES,
+        voxel_grid::Dims3}
+};
+
+// RDME passes over integer molecule counts, see world/rdme.rs
+// Counter-based draws let every voxel gather its molecules in one pass, as cpu/noise.rs gathers its face fluxes:
+// a voxel draws its own hops and redraws those of its six neighbours from their counters, so each hop is drawn
+// identically by the voxel it leaves and the voxel it enters and molecules are conserved exactly
+// Counters are [voxel, draw block, step lo, step hi] under key [seed, stream ^ sub-step],
+// hops and bath draws start their blocks at leap_block(), reactions at 0
+
+pub const MAX_LEAPS: u32 = 1 << 13; // hop leaps per step the draw blocks leave room for
+pub const MAX_EVENTS: u32 = 10_000; // reaction events per voxel per step, past which the voxel's reactions stop for the step
+
+/// Clock step and sub-step a step's draws are counted by, with the seed keying them
+#[derive(Debug, Copy, Clone)]
+pub struct DrawIndex {
+    pub seed: u32,
+    pub step: u64,
+    pub substep: u32
+}
+
+impl DrawIndex {
+    fn stream(&self, stream: u32, voxel: usize, block: u32) -> UniformStream {
+        UniformStream::new([voxel as u32, block, self.step as u32, (self.step >> 32) as u32], [self.seed, stream ^ self.substep])
+    }
+}
+
+/// First draw block of a species' hops in one leap of a step, the bath's faces are 2^13 blocks apart above it
+pub fn leap_block(leap: u32, species: usize) -> u32 {
+    assert!(leap < MAX_LEAPS, "More hop leaps than draw blocks, sub-step by RdmeBackend::stability_limit()\n");
+    (leap * MAX_SPECIES as u32 + species as u32) << 16
+}
+
+/// Molecules of a voxel hopping through each face, x-, x+, y-, y+, z-, z+: a multinomial drawn as conditional binomials
+/// probabilities per axis, twice their sum at most 1, the rest stay
+fn hops(count: u32, probabilities: &[f64; 3], stream: &mut UniformStream) -> [u32; 6] {
+    let (mut hops, mut left, mut rest) = ([0; 6], count, 1.0);
+    for (face, hop) in hops.iter_mut().enumerate() {
+        let p = probabilities[face / 2];
+        *hop = binomial(left, (p / rest).min(1.0), stream);
+        left -= *hop;
+        rest -= p;
+    }
+    hops
+}
+
+/// One leap of every molecule in src hopping to a neighbour or staying, written to dst
+/// probabilities are D dt / dx² per axis for the leap, twice their sum at most 1, see world/rdme.rs for the faces
+/// Reads src, writes dst, split across threads in z slabs
+pub fn hop_step(src: &[u32], dst: &mut [u32], dims: &Dims3, probabilities: &[f64; 3], bounds: &BoundaryConditions, model: &Rdme, draws: &DrawIndex, block: u32) {
+    let len = (dims[0] * dims[1] * dims[2]) as usize;
+    assert!(src.len() == len && dst.len() == len, "Field length does not match dims\n");
+    assert!(2.0 * probabilities.iter().sum::<f64>() <= 1.0 + 1e-9, "Hop probabilities sum above 1, sub-step the leap\n");
+    let hops_from = |voxel: usize| hops(src[voxel], probabilities, &mut draws.stream(HOP_STREAM, voxel, block));
+
+    par_map_voxels(dst, dims, |x, y, z| {
+        let (idx, position) = (voxel_index(dims, x, y, z), [x, y, z]);
+        let own = hops_from(idx);
+        let mut count = src[idx];
+        for (face, out) in own.into_iter().enumerate() {
+            let (axis, upper) = (face / 2, face % 2 == 1);
+            let edge = if upper { position[axis] + 1 == dims[axis] } else { position[axis] == 0 };
+            let boundary = bounds.faces[axis][face % 2];
+            if !edge || boundary != Boundary::Neumann { count -= out; } // Neumann faces turn hops back
+            if !edge || boundary == Boundary::Periodic {
+                // the neighbour's hops through its face back to this voxel
+                let mut neighbour = position;
+                neighbour[axis] = if upper { (position[axis] + 1) % dims[axis] } else { (position[axis] + dims[axis] - 1) % dims[axis] };
+                count += hops_from(voxel_index(dims, neighbour[0], neighbour[1], neighbour[2]))[face ^ 1];
+            }
+            else if let Boundary::Dirichlet(value) = boundary {
+                let mean = probabilities[axis] * value as f64 * model.volume as f64;
+                count += poisson(mean, &mut draws.stream(BATH_STREAM, idx, block | ((face as u32) << 13)));
+            }
+        }
+        count
+    });
+}
+
+/// The network's reactions over one step of timestep in every voxel, in place, by Gillespie's direct method
+/// fields holds the network's species in registry order, all of one length, split into the same equal chunks across threads
+/// Returns how many voxels hit MAX_EVENTS, whose reactions ran short of timestep
+pub fn ssa_step(fields: Vec<&mut [u32]>, network: &ReactionNetwork, model: &Rdme, timestep: f32, draws: &DrawIndex) -> usize {
+    assert!(fields.len() == network.species_count(), "Network and fields differ in species\n");
+    let len = fields[0].len();
+    assert!(fields.iter().all(|f| f.len() == len), "Species differ in length\n");
+    let threads = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let chunk = len.div_ceil(threads).max(1);
+
+    // chunks[c][s]: species s's part of chunk c
+    let mut chunks: Vec<Vec<&mut [u32]>> = (0..len.div_ceil(chunk)).map(|_| Vec::new()).collect();
+    for field in fields {
+        for (c, part) in field.chunks_mut(chunk).enumerate() {
+            chunks[c].push(part);
+        }
+    }
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = chunks.into_iter().enumerate().map(|(c, mut species)| {
+            scope.spawn(move || {
+                let (mut counts, mut capped) = ([0; MAX_SPECIES], 0);
+                let n = species.len();
+                for i in 0..species[0].len() {
+                    for (count, field) in counts.iter_mut().zip(species.iter()) { *count = field[i]; }
+                    if !react(&mut counts[..n], network, model, timestep as f64, &mut draws.stream(SSA_STREAM, c * chunk + i, 0)) { capped += 1; }
+                    for (field, count) in species.iter_mut().zip(&counts[..n]) {
+                        field[i] = *count;
+                    }
+                }
+                capped
+            })
+        }).collect();
+        handles.into_iter().map(|h| h.join().expect("SSA thread panicked\n")).sum()
+    })
+}
+
+/// Fires reactions in one voxel until the next would fall past timestep, or MAX_EVENTS have fired
+/// Returns false when the cap cut the step short
+fn react(counts: &mut [u32], network: &ReactionNetwork, model: &Rdme, timestep: f64, stream: &mut UniformStream) -> bool {
+    let mut propensities = [0.0f64; MAX_REACTIONS];
+    let mut time = 0.0;
+    for _ in 0..MAX_EVENTS {
+        for (a, reaction) in propensities.iter_mut().zip(&network.reactions) {
+            *a = model.propensity(reaction, counts);
+        }
+        let total: f64 = propensities.iter().sum();
+        if total <= 0.0 { return true; }
+        time -= (stream.uniform() as f64).ln() / total;
+        if time > timestep { return true; }
+
+        let mut pick = stream.uniform() as f64 * total;
+        let fired = propensities.iter().position(|a| { pick -= a; pick < 0.0 })
+            .or(propensities.iter().rposition(|a| *a > 0.0)) // pick rounded past the last one
+            .expect("Some reaction has a positive propensity");
+        let reaction = &network.reactions[fired];
+        for (species, n) in &reaction.reactants { counts[*species] -= n; }
+        for (species, n) in &reaction.products { counts[*species] = counts[*species].saturating_add(*n); }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend_admin::cpu::laplacian::laplacian_step,
+        world::{diffusion::Diffusion, species::SpeciesRegistry}};
+
+    const DIMS: Dims3 = [6, 5, 4];
+
+    fn counts(model: &Rdme, phase: f32) -> Vec<u32> {
+        (0..DIMS[0] * DIMS[1] * DIMS[2]).map(|i| model.count(0.5 + 0.4 * (i as f32 * 0.7 + phase).sin())).collect()
+    }
+
+    /// Mean of hop_step over many seeds against the explicit laplacian step of the same concentrations, within 5 standard errors
+    fn hop_means_match_laplacian(spec: &str) {
+        let bounds = BoundaryConditions::parse(spec).unwrap();
+        let model = Rdme::new(2000.0);
+        let diffusion = Diffusion::new(1.0, [1.0, 1.25, 1.5]);
+        let timestep = 0.1;
+        let [d, wx, wy, wz] = diffusion.stencil_weights();
+        let probabilities = [wx, wy, wz].map(|w| (d * timestep * w) as f64);
+
+        let src = counts(&model, 0.0);
+        let concentrations: Vec<f32> = src.iter().map(|n| model.concentration(*n)).collect();
+        let mut expected = vec![0.0; src.len()];
+        laplacian_step(&concentrations, &mut expected, &DIMS, timestep, &bounds, &diffusion);
+
+        let seeds = 400;
+        let (mut sum, mut squares) = (vec![0.0f64; src.len()], vec![0.0f64; src.len()]);
+        let mut dst = vec![0; src.len()];
+        for seed in 0..seeds {
+            let draws = DrawIndex { seed: seed, step: 0, substep: 0 };
+            hop_step(&src, &mut dst, &DIMS, &probabilities, &bounds, &model, &draws, leap_block(0, 0));
+            for (i, n) in dst.iter().enumerate() {
+                sum[i] += *n as f64;
+                squares[i] += (*n as f64).powi(2);
+            }
+        }
+        for i in 0..src.len() {
+            let mean = sum[i] / seeds as f64;
+            let error = ((squares[i] / seeds as f64 - mean * mean) / seeds as f64).sqrt();
+            let target = expected[i] as f64 * model.volume as f64;
+            assert!((mean - target).abs() < 5.0 * error + 0.01, "{}: voxel {} mean {} against {} ± {}", spec, i, mean, target, error);
+        }
+    }
+
+    #[test]
+    fn hop_means_match_laplacian_neumann() {
+        hop_means_match_laplacian("xyz=neumann");
+    }
+
+    #[test]
+    fn hop_means_match_laplacian_periodic() {
+        hop_means_match_laplacian("xyz=periodic");
+    }
+
+    #[test]
+    fn hop_means_match_laplacian_dirichlet() {
+        hop_means_match_laplacian("x=dirichlet:0.8,y-=dirichlet:0.2,y+=dirichlet:1.5,z=periodic");
+    }
+
+    #[test]
+    fn ssa_step_reproduces_with_the_same_seed() {
+        let registry = SpeciesRegistry::parse("A;B").unwrap();
+        let (network, _) = ReactionNetwork::parse("u + A -> B, k=0.5\nB -> u, k=0.2\n2 A <-> B, k=0.05, kr=0.1", &registry).unwrap();
+        let model = Rdme::new(50.0);
+        let initial: Vec<Vec<u32>> = (0..3).map(|s| counts(&model, s as f32)).collect();
+
+        let run = |seed: u32| {
+            let mut fields = initial.clone();
+            for step in 0..5 {
+                let draws = DrawIndex { seed: seed, step: step, substep: 0 };
+                ssa_step(fields.iter_mut().map(|f| f.as_mut_slice()).collect(), &network, &model, 0.5, &draws);
+            }
+            fields
+        };
+        assert_eq!(run(11), run(11));
+        assert_ne!(run(11), run(12));
+        assert_ne!(run(11), initial);
+    }
+
+    #[test]
+    fn ssa_step_caps_events_per_voxel() {
+        let registry = SpeciesRegistry::parse("A").unwrap();
+        let (network, _) = ReactionNetwork::parse("u <-> A, k=1e6, kr=1e6", &registry).unwrap();
+        let model = Rdme::new(50.0);
+        let mut fields = vec![vec![25; 8], vec![25; 8]];
+        let draws = DrawIndex { seed: 3, step: 0, substep: 0 };
+        assert_eq!(ssa_step(fields.iter_mut().map(|f| f.as_mut_slice()).collect(), &network, &model, 1.0, &draws), 8);
+        assert!(fields[0].iter().zip(&fields[1]).all(|(u, a)| u + a == 50));
+        assert_eq!(ssa_step(fields.iter_mut().map(|f| f.as_mut_slice()).collect(), &network, &model, 1e-9, &draws), 0);
+    }
+}
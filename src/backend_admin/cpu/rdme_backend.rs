@@ -0,0 +1,235 @@
+use crate::{
+    backend_admin::{
+        cpu::rdme::{DrawIndex, MAX_EVENTS, MAX_LEAPS, hop_step, leap_block, ssa_step},
+        gpu::{gfx_context::GraphicsContext, resources::Resources},
+        simulation::SimulationBackend},
+    world::{
+        boundary::BoundaryConditions,
+        cahn_hilliard::CahnHilliard,
+        diffusion::Diffusion,
+        field::VoxelField,
+        integrator::Integrator,
+        multigrid::ConvergenceHistory,
+        network::ReactionNetwork,
+        noise::ThermalNoise,
+        rdme::Rdme,
+        reaction::Reaction,
+        species::SpeciesRegistry,
+        stability::StabilityLimit,
+        vesicle::Vesicle,
+        voxel_grid::Dims3}
+};
+
+/// SimulationBackend on the CPU over whole molecules, see world/rdme.rs
+/// Fields are read and written as concentrations, count / Ω, and rounded to whole molecules on the way in
+/// Every step hops the molecules in as many leaps as keep each molecule's hop probability at most 1, whatever the integrator,
+/// at most MAX_LEAPS of them, longer steps being sub-stepped by the stability_limit() it reports,
+/// then reacts them by the network
+/// The two-species reaction, Cahn-Hilliard, vesicle and thermal noise are continuum models and ignored,
+/// a reaction can be written as a network instead, and the noise is intrinsic
+pub struct RdmeBackend {
+    dims: Dims3,
+    model: Rdme,
+    species: Vec<Vec<u32>>, // molecules per voxel, one per species of registry
+    scratch: Vec<u32>, // hop_step's destination, swapped with the species it hopped
+    registry: SpeciesRegistry, // per-species D, boundaries and initial conditions
+    bounds: BoundaryConditions, // for species without their own
+    diffusion: Diffusion, // ditto, see SpeciesRegistry::diffusion()
+    network: Option<ReactionNetwork>, // indexed as registry
+    seed: u32, // keys every draw, from init() or set_noise()
+    step_index: (u64, u32), // (step, sub-step) the next step's draws are counted by
+    notices: Vec<String>, // what the setters ignored and when the event cap starts or stops biting, see take_notices()
+    capped: bool // the last step's reactions hit MAX_EVENTS in some voxel
+}
+
+impl RdmeBackend {
+    pub fn new(dims: Dims3, model: Rdme) -> Self {
+        assert!(dims[0] > 0 && dims[1] > 0 && dims[2] > 0);
+        let len = (dims[0] * dims[1] * dims[2]) as usize;
+        RdmeBackend {
+            dims: dims,
+            model: model,
+            species: vec![vec![0; len]],
+            scratch: vec![0; len],
+            registry: SpeciesRegistry::default(),
+            bounds: BoundaryConditions::default(),
+            diffusion: Diffusion::default(),
+            network: None,
+            seed: 0,
+            step_index: (0, 0),
+            notices: Vec::new(),
+            capped: false
+        }
+    }
+
+    fn len(&self) -> usize {
+        (self.dims[0] * self.dims[1] * self.dims[2]) as usize
+    }
+
+    /// Stores registry's species, see SimulationBackend::set_species()
+    /// Needs no Resources, so the RDME backend runs without a GraphicsContext
+    pub fn set_registry(&mut self, registry: &SpeciesRegistry) {
+        let len = self.len();
+        self.registry = registry.clone();
+        self.species.resize_with(registry.len(), || vec![0; len]);
+        self.network = self.network.take().and_then(|network| network.resolve(registry).ok());
+    }
+
+    /// Molecules of a species in every voxel
+    pub fn counts(&self, species: usize) -> &[u32] {
+        &self.species[species]
+    }
+
+    /// Hops every species over timestep in leaps short enough that no molecule hops with probability above 1,
+    /// all species taking the same number: leaps ≥ 2 D dt Σ 1/dx², dt over Diffusion::stable_timestep()
+    fn diffuse(&mut self, timestep: f32) {
+        // per species, D dt / dx² per axis over the whole step
+        let probabilities: Vec<[f64; 3]> = (0..self.species.len())
+            .map(|s| {
+                let [d, wx, wy, wz] = self.registry.diffusion(s, &self.diffusion, None).stencil_weights();
+                [wx, wy, wz].map(|w| d as f64 * timestep as f64 * w as f64)
+            })
+            .collect();
+        let leaps = probabilities.iter()
+            .map(|p| (2.0 * p.iter().sum::<f64>()).ceil() as u32)
+            .fold(1, u32::max);
+        let draws = DrawIndex { seed: self.seed, step: self.step_index.0, substep: self.step_index.1 };
+        for leap in 0..leaps {
+            for (species, p) in probabilities.iter().enumerate() {
+                if p.iter().all(|p| *p == 0.0) { continue; }
+                let bounds = self.registry.boundaries(species, &self.bounds);
+                let p = p.map(|p| p / leaps as f64);
+                hop_step(&self.species[species], &mut self.scratch, &self.dims, &p, &bounds, &self.model, &draws, leap_block(leap, species));
+                std::mem::swap(&mut self.species[species], &mut self.scratch);
+            }
+        }
+    }
+}
+
+impl SimulationBackend for RdmeBackend {
+    fn dims(&self) -> Dims3 {
+        self.dims
+    }
+
+    /// Every species from its InitialCondition, rounded to whole molecules
+    fn init(&mut self, seed: u32) {
+        self.seed = seed;
+        for (species, counts) in self.species.iter_mut().enumerate() {
+            if let Some(s) = self.registry.get(species) {
+                *counts = s.initial.field(&self.dims, seed, species).into_iter().map(|c| self.model.count(c)).collect();
+            }
+        }
+    }
+
+    /// Hops, then reactions, both drawn at the step index, whose sub-step each step() advances
+    /// A voxel's reactions stop for the step at MAX_EVENTS, with a notice when that starts or stops
+    fn step(&mut self, timestep: f32) {
+        self.diffuse(timestep);
+        if let Some(network) = &self.network {
+            let draws = DrawIndex { seed: self.seed, step: self.step_index.0, substep: self.step_index.1 };
+            let capped = ssa_step(self.species.iter_mut().map(|s| s.as_mut_slice()).collect(), network, &self.model, timestep, &draws);
+            // only worth a notice when capping starts or stops, as stability reports its limiting
+            if (capped > 0) != self.capped {
+                self.notices.push(if capped > 0 {
+                    format!("RDME stopped reactions in {} voxels at {} events per step, they run slow until dt or the rates are lowered", capped, MAX_EVENTS)
+                }
+                else {
+                    "RDME reactions fit within the event cap again".to_string()
+                });
+                self.capped = capped > 0;
+            }
+        }
+        self.step_index.1 = self.step_index.1.wrapping_add(1);
+    }
+
+    fn read_species(&mut self, species: usize) -> VoxelField {
+        VoxelField::new(self.dims, self.species[species].iter().map(|n| self.model.concentration(*n)).collect())
+    }
+
+    fn write_species(&mut self, species: usize, field: &[f32]) {
+        assert!(field.len() == self.len(), "Field length does not match dims\n");
+        for (count, c) in self.species[species].iter_mut().zip(field) {
+            *count = self.model.count(*c);
+        }
+    }
+
+    fn species_count(&self) -> usize {
+        self.species.len()
+    }
+
+    /// Counts have no ping/pong, the parity is ignored
+    fn restore_field(&mut self, field: &[f32], _read_ping: bool) {
+        self.write_species(0, field);
+    }
+
+    fn read_ping(&self) -> bool {
+        true
+    }
+
+    fn set_boundaries(&mut self, bounds: &BoundaryConditions) {
+        self.bounds = *bounds;
+    }
+
+    fn set_diffusion(&mut self, diffusion: &Diffusion) {
+        self.diffusion = *diffusion;
+    }
+
+    /// Hops are leapt whatever the integrator, see diffuse()
+    fn set_integrator(&mut self, _integrator: &Integrator) {}
+
+    fn set_species(&mut self, registry: &SpeciesRegistry, _resources: &Resources) {
+        self.set_registry(registry);
+    }
+
+    fn set_reaction(&mut self, reaction: Option<&Reaction>) {
+        if let Some(reaction) = reaction { self.notices.push(format!("RDME ignores the {} reaction, write it as a --network", reaction.name())); }
+    }
+
+    fn set_network(&mut self, network: Option<&ReactionNetwork>) {
+        assert!(network.is_none_or(|n| n.species_count() == self.species.len()), "Network is not indexed as the registry, see ReactionNetwork::resolve()\n");
+        self.network = network.cloned();
+    }
+
+    fn set_cahn_hilliard(&mut self, model: Option<&CahnHilliard>) {
+        if model.is_some() { self.notices.push("RDME ignores Cahn-Hilliard, species 0 keeps hopping".to_string()); }
+    }
+
+    fn set_vesicle(&mut self, model: Option<&Vesicle>) {
+        if model.is_some() { self.notices.push("RDME ignores the vesicle, species 0 keeps hopping".to_string()); }
+    }
+
+    /// Only takes the seed, RDME's noise is its own
+    fn set_noise(&mut self, noise: Option<&ThermalNoise>, seed: u32) {
+        if let Some(noise) = noise { self.notices.push(format!("RDME ignores {}, its hops and reactions are already stochastic", noise.name())); }
+        self.seed = seed;
+    }
+
+    fn set_step_index(&mut self, step: u64, substep: u32) {
+        self.step_index = (step, substep);
+    }
+
+    /// MAX_LEAPS leaps of the fastest species' Diffusion::stable_timestep(), one spare for rounding,
+    /// so a step never runs out of draw blocks
+    fn stability_limit(&self, species: &SpeciesRegistry, diffusion: &Diffusion) -> Option<StabilityLimit> {
+        let tightest = (0..species.len())
+            .map(|s| species.diffusion(s, diffusion, None).stable_timestep())
+            .fold(f32::INFINITY, f32::min);
+        Some(StabilityLimit { operator: "hop", max_dt: (MAX_LEAPS - 1) as f32 * tightest })
+    }
+
+    fn take_notices(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.notices)
+    }
+
+    fn solver_history(&self) -> Option<&ConvergenceHistory> {
+        None
+    }
+
+    /// Uploads a species' concentrations into its ping plane each frame
+    fn sync_for_render(&mut self, resources: &Resources, gfx_ctx: &GraphicsContext, species: usize) -> bool {
+        let species = species.min(self.species.len() - 1);
+        let field: Vec<f32> = self.species[species].iter().map(|n| self.model.concentration(*n)).collect();
+        resources.write_voxels(gfx_ctx, species, &field, &self.dims);
+        true
+    }
+}
@@ -0,0 +1,26 @@
+use std::thread;
+use crate::world::reaction::Polynomial;
+
+// CPU reference for reaction.wgsl
+// Pointwise, so voxels are split across threads in equal chunks rather than z slabs
+
+/// One explicit Euler reaction step in place: u += dt R_u(u, v), v += dt R_v(u, v)
+pub fn reaction_step(u: &mut [f32], v: &mut [f32], rates: &Polynomial, timestep: f32) {
+    assert!(u.len() == v.len(), "Species differ in length\n");
+    let threads = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let chunk = u.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        for (u_chunk, v_chunk) in u.chunks_mut(chunk).zip(v.chunks_mut(chunk)) {
+            scope.spawn(move || {
+                for (u, v) in u_chunk.iter_mut().zip(v_chunk.iter_mut()) {
+                    let (u_i, v_i) = (*u, *v);
+                    *u = u_i + (timestep * rates.rate(0, u_i, v_i));
+                    *v = v_i + (timestep * rates.rate(1, u_i, v_i));
+                }
+            });
+        }
+    });
+}
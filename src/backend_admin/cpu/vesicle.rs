@@ -0,0 +1,57 @@
+use crate::backend_admin::cpu::{implicit::stencil, laplacian::par_map_voxels};
+use crate::world::{
+    boundary::BoundaryConditions,
+    diffusion::Diffusion,
+    field::voxel_index,
+    vesicle::{Vesicle, VesicleSums},
+    voxel_grid::Dims3};
+
+// CPU reference for vesicle.wgsl
+// Same three passes: W into the other buffer, δE/δφ into a potential buffer alongside the sums the multipliers need,
+// then the relaxation into the other buffer. The shader reduces per workgroup in f32 before the f64 total,
+// so the multipliers (and nothing else) differ by rounding between backends
+
+/// One vesicle step, see world/vesicle.rs
+/// field holds φ, scratch is the other ping/pong buffer
+/// targets are [Σ v, Σ a] to hold, taken from this step's field when None
+/// flip: the step's result lands in scratch rather than field, as cahn_hilliard_step()
+/// Returns the multipliers [λ_V, λ_A] the step used
+pub fn vesicle_step(field: &mut [f32], scratch: &mut [f32], dims: &Dims3, timestep: f32, bounds: &BoundaryConditions, spacing: &[f32; 3], model: &Vesicle, targets: &mut Option<[f64; 2]>, flip: bool) -> [f32; 2] {
+    let bounds = bounds.zero_flux();
+    let weights = Diffusion::new(model.mobility, *spacing).stencil_weights();
+    let laplacian = |src: &[f32], x: u32, y: u32, z: u32| {
+        let (neighbours, weight) = stencil(src, dims, x, y, z, &bounds, &weights, &[1.0; 3]);
+        neighbours - (weight * src[voxel_index(dims, x, y, z)])
+    };
+
+    // CURVATURE W
+    par_map_voxels(scratch, dims, |x, y, z| model.curvature(field[voxel_index(dims, x, y, z)], laplacian(field, x, y, z)));
+
+    // POTENTIALS AND SUMS
+    let mut potential = vec![0.0; field.len()];
+    par_map_voxels(&mut potential, dims, |x, y, z| {
+        let idx = voxel_index(dims, x, y, z);
+        model.bending_potential(field[idx], scratch[idx], laplacian(scratch, x, y, z))
+    });
+    let mut sums = VesicleSums::default();
+    for z in 0..dims[2] {
+        for y in 0..dims[1] {
+            for x in 0..dims[0] {
+                let idx = voxel_index(dims, x, y, z);
+                let (phi, lap) = (field[idx], laplacian(field, x, y, z));
+                sums.add(&VesicleSums::terms((phi + 1.0) / 2.0, model.area_density(phi, lap), model.area_potential(phi, lap), potential[idx]));
+            }
+        }
+    }
+    let targets = *targets.get_or_insert([sums.volume, sums.area]);
+    let multipliers = model.multipliers(&sums, &targets, field.len(), timestep);
+
+    // RELAX
+    par_map_voxels(scratch, dims, |x, y, z| {
+        let idx = voxel_index(dims, x, y, z);
+        let phi = field[idx];
+        model.relax(phi, potential[idx], model.area_potential(phi, laplacian(field, x, y, z)), &multipliers, timestep)
+    });
+    if !flip { field.copy_from_slice(scratch); }
+    multipliers
+}
@@ -0,0 +1,586 @@
+use wgpu::{BindGroup, BindGroupEntry, BindGroupLayout, Buffer, BufferBinding, BufferUsages, ComputePipeline, Device, Queue};
+use wgpu::util::DeviceExt;
+use std::num::NonZero;
+use crate::{
+    backend_admin::{
+        bridge::{Bridge, DispatchDims},
+        gpu::{compute::Compute, gfx_context::GraphicsContext, multigrid::{GpuMultigrid, MultigridPipelines, storage_entry}, resources::{FieldSlice, Resources, Uniforms}, vesicle::{GpuVesicle, VesiclePipelines}},
+        simulation::SimulationBackend},
+    world::{
+        boundary::BoundaryConditions,
+        cahn_hilliard::CahnHilliard,
+        diffusion::Diffusion,
+        field::VoxelField,
+        integrator::{self, ImplicitSolver, Integrator},
+        multigrid::{ConvergenceHistory, Helmholtz},
+        network::{MAX_REACTANTS, MAX_REACTIONS, ReactionNetwork},
+        noise::ThermalNoise,
+        reaction::{MAX_TERMS, Reaction, SPECIES},
+        species::{InitialCondition, MAX_SPECIES, SpeciesRegistry},
+        vesicle::Vesicle,
+        voxel_grid::Dims3}
+};
+
+/// SimulationBackend on the GPU: the init, laplacian, implicit, multigrid, reaction, network, Cahn-Hilliard, vesicle and noise passes from Compute
+/// Holds its own handles to the device, queue and Resources' voxel buffers (wgpu handles are reference counted)
+/// and its own uniforms buffers, so stepping never disturbs the raymarch uniforms
+/// Every species lives in its own plane of Resources' ping/pong pair, see set_species()
+pub struct GpuBackend {
+    device: Device,
+    queue: Queue,
+    dims: Dims3,
+
+    species: Vec<SpeciesBuffers>, // one per species of registry
+    sim_bg_layout: BindGroupLayout, // binds each species' planes
+    reaction_bg_layout: BindGroupLayout,
+    reaction_bg: Option<(BindGroup, Buffer)>, // species 0 and 1 and the rates buffer, while reacting
+    voxels: (FieldSlice, FieldSlice), // Resources' whole ping/pong buffers, every species' plane
+    plane_stride: u64, // bytes between planes
+    network_bg_layout: BindGroupLayout,
+    network_bg: Option<(BindGroup, Buffer)>, // the whole buffers and the packed reactions, while a network is set
+
+    init_p: ComputePipeline,
+    laplacian_p: ComputePipeline,
+    implicit_rhs_p: ComputePipeline,
+    implicit_sweep_p: ComputePipeline,
+    multigrid_p: MultigridPipelines,
+    reaction_p: ComputePipeline,
+    network_p: ComputePipeline,
+    cahn_hilliard_p: ComputePipeline,
+    vesicle_p: VesiclePipelines,
+    noise_p: ComputePipeline,
+    init_dispatch: DispatchDims,
+    laplacian_dispatch: DispatchDims,
+
+    read_ping: bool, // true when ping holds the current field, for every species
+    seed: u32,
+    registry: SpeciesRegistry, // per-species D, boundaries and initial conditions
+    bounds: BoundaryConditions, // written into the uniforms on every dispatch, for species without their own
+    diffusion: Diffusion, // ditto, see SpeciesRegistry::diffusion()
+    integrator: Integrator, // picks the passes step() dispatches
+    reaction: Option<Reaction>,
+    network: Option<ReactionNetwork>, // indexed as registry
+    cahn_hilliard: Option<CahnHilliard>, // replaces species 0's diffusion while set
+    vesicle: Option<Vesicle>, // ditto, unless Cahn-Hilliard is set too
+    vesicle_targets: Option<[f64; 2]>, // [Σ v, Σ a] the vesicle's constraints hold, None until its next step
+    noise: Option<(ThermalNoise, u32)>, // kicks species 0 after every step, with the seed keying it
+    step_index: (u64, u32), // (step, sub-step) the next step's noise is drawn at
+    vesicle_buffers: Option<GpuVesicle>, // allocated by the first vesicle step
+    history: Option<ConvergenceHistory> // last multigrid solve
+}
+
+/// One species' ping/pong planes, the uniforms its passes read and the sim_bg_layout bind group over them
+struct SpeciesBuffers {
+    ping: FieldSlice,
+    pong: FieldSlice,
+    uniforms: Buffer,
+    bg: BindGroup,
+    multigrid: Option<GpuMultigrid> // level buffers, allocated by the first multigrid step
+}
+
+impl SpeciesBuffers {
+    fn new(device: &Device, layout: &BindGroupLayout, (ping, pong): (FieldSlice, FieldSlice), uniforms: Uniforms) -> Self {
+        let uniforms = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Simulation uniform buffer"),
+            contents: uniforms.flatten_u8(),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Simulation bind group"),
+            layout: layout,
+            entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(BufferBinding {
+                    buffer: &uniforms,
+                    offset: 0,
+                    size: NonZero::new((std::mem::size_of::<Uniforms>()) as u64)
+                }),
+            },
+            ping.entry(1),
+            pong.entry(2)]
+        });
+
+        SpeciesBuffers {
+            ping: ping,
+            pong: pong,
+            uniforms: uniforms,
+            bg: bg,
+            multigrid: None
+        }
+    }
+
+    /// (current, other)
+    fn buffers(&self, read_ping: bool) -> (&FieldSlice, &FieldSlice) {
+        if read_ping { (&self.ping, &self.pong) } else { (&self.pong, &self.ping) }
+    }
+}
+
+/// reaction.wgsl's Rates: term counts, then MAX_TERMS (coefficient, power of u, power of v, 0) per species
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ReactionRates {
+    counts: [u32; 4],
+    terms: [[f32; 4]; MAX_TERMS * SPECIES]
+}
+
+impl ReactionRates {
+    fn new(reaction: &Reaction) -> Self {
+        let rates = reaction.rates();
+        let mut packed = ReactionRates { counts: [0; 4], terms: [[0.0; 4]; MAX_TERMS * SPECIES] };
+        for species in 0..SPECIES {
+            let terms = rates.terms(species);
+            packed.counts[species] = terms.len() as u32;
+            for (i, term) in terms.iter().enumerate() {
+                packed.terms[(species * MAX_TERMS) + i] = [term.coefficient, term.powers[0] as f32, term.powers[1] as f32, 0.0];
+            }
+        }
+        packed
+    }
+
+    fn flatten_u8(&self) -> &[u8] {
+        let ptr = self as *const _ as *const u8;
+        unsafe {
+            std::slice::from_raw_parts(ptr, std::mem::size_of::<ReactionRates>())
+        }
+    }
+}
+
+/// network.wgsl's Network: the reaction count, then MAX_REACTIONS MassActions
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct NetworkRates {
+    counts: [u32; 4],
+    reactions: [PackedMassAction; MAX_REACTIONS]
+}
+
+/// (k, 0, 0, 0), reactant species, their coefficients (0 past the last), products - reactants per species
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct PackedMassAction {
+    rate: [f32; 4],
+    reactants: [u32; 4],
+    orders: [u32; 4],
+    net: [f32; MAX_SPECIES]
+}
+
+impl NetworkRates {
+    fn new(network: &ReactionNetwork) -> Self {
+        let mut packed = NetworkRates { counts: [network.reactions.len() as u32, 0, 0, 0], reactions: [PackedMassAction::default(); MAX_REACTIONS] };
+        for (reaction, slot) in network.reactions.iter().zip(packed.reactions.iter_mut()) {
+            assert!(reaction.reactants.len() <= MAX_REACTANTS, "Too many reactants, see ReactionNetwork::parse()\n");
+            slot.rate[0] = reaction.rate;
+            for (i, (species, n)) in reaction.reactants.iter().enumerate() {
+                slot.reactants[i] = *species as u32;
+                slot.orders[i] = *n;
+            }
+            slot.net = reaction.net();
+        }
+        packed
+    }
+
+    fn flatten_u8(&self) -> &[u8] {
+        let ptr = self as *const _ as *const u8;
+        unsafe {
+            std::slice::from_raw_parts(ptr, std::mem::size_of::<NetworkRates>())
+        }
+    }
+}
+
+impl GpuBackend {
+    pub fn new(dims: &Dims3, gfx_ctx: &GraphicsContext, resources: &Resources, compute: &Compute, bridge: &Bridge) -> Self {
+        let primary = SpeciesBuffers::new(&gfx_ctx.device, &compute.sim_bg_layout, resources.species_planes(0, dims),
+            Uniforms::simulation(dims, 0.0, bridge.rand_seed, true, &BoundaryConditions::default(), &Diffusion::default()));
+
+        GpuBackend {
+            device: gfx_ctx.device.clone(),
+            queue: gfx_ctx.queue.clone(),
+            dims: *dims,
+
+            species: vec![primary],
+            sim_bg_layout: compute.sim_bg_layout.clone(),
+            reaction_bg_layout: compute.reaction_bg_layout.clone(),
+            reaction_bg: None,
+            voxels: resources.all_planes(),
+            plane_stride: resources.plane_stride(),
+            network_bg_layout: compute.network_bg_layout.clone(),
+            network_bg: None,
+
+            init_p: compute.init_p.clone(),
+            laplacian_p: compute.laplacian_p.clone(),
+            implicit_rhs_p: compute.implicit_rhs_p.clone(),
+            implicit_sweep_p: compute.implicit_sweep_p.clone(),
+            multigrid_p: compute.multigrid.clone(),
+            reaction_p: compute.reaction_p.clone(),
+            network_p: compute.network_p.clone(),
+            cahn_hilliard_p: compute.cahn_hilliard_p.clone(),
+            vesicle_p: compute.vesicle.clone(),
+            noise_p: compute.noise_p.clone(),
+            init_dispatch: bridge.init_dispatch,
+            laplacian_dispatch: bridge.laplacian_dispatch,
+
+            read_ping: true,
+            seed: bridge.rand_seed,
+            registry: SpeciesRegistry::default(),
+            bounds: BoundaryConditions::default(),
+            diffusion: Diffusion::default(),
+            integrator: Integrator::default(),
+            reaction: None,
+            network: None,
+            cahn_hilliard: None,
+            vesicle: None,
+            vesicle_targets: None,
+            noise: None,
+            step_index: (0, 0),
+            vesicle_buffers: None,
+            history: None
+        }
+    }
+
+    /// Diffusion of a species, see SpeciesRegistry::diffusion()
+    fn species_diffusion(&self, species: usize) -> Diffusion {
+        self.registry.diffusion(species, &self.diffusion, self.reaction.as_ref())
+    }
+
+    /// Boundary faces of a species, see SpeciesRegistry::boundaries()
+    fn species_bounds(&self, species: usize) -> BoundaryConditions {
+        self.registry.boundaries(species, &self.bounds)
+    }
+
+    /// Γ of the thermal noise, see world/noise.rs
+    fn noise_mobility(&self) -> f32 {
+        match (&self.cahn_hilliard, &self.vesicle) {
+            (Some(model), _) => model.mobility,
+            (None, Some(model)) => model.mobility,
+            (None, None) => self.species_diffusion(0).coefficient
+        }
+    }
+
+    /// Writes a species' uniforms then encodes and submits a single dispatch over its bind group
+    /// write_buffer is applied before the submit that follows it, so consecutive calls see their own uniforms
+    fn dispatch(&self, label: &str, pipeline: &ComputePipeline, dispatch: DispatchDims, species: usize, timestep: f32) {
+        self.dispatch_with(label, pipeline, dispatch, species, self.uniforms_for(species, timestep));
+    }
+
+    fn uniforms_for(&self, species: usize, timestep: f32) -> Uniforms {
+        Uniforms::simulation(&self.dims, timestep, self.seed, self.read_ping, &self.species_bounds(species), &self.species_diffusion(species))
+    }
+
+    fn dispatch_with(&self, label: &str, pipeline: &ComputePipeline, dispatch: DispatchDims, species: usize, uniforms: Uniforms) {
+        self.queue.write_buffer(&self.species[species].uniforms, 0, uniforms.flatten_u8());
+        self.submit(label, pipeline, &self.species[species].bg, dispatch);
+    }
+
+    fn submit(&self, label: &str, pipeline: &ComputePipeline, bg: &BindGroup, dispatch: DispatchDims) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some(label)
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some(label),
+                timestamp_writes: None
+            });
+            compute_pass.set_pipeline(pipeline);
+            compute_pass.set_bind_group(0, bg, &[]);
+            let [x, y, z] = dispatch;
+            compute_pass.dispatch_workgroups(x, y, z); // group size is 8 * 4 * 8 <= 256 (256, 256, 64 respective limits)
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Explicit: flags[0] == 1: laplacian reads ping, writes pong (and vice versa), read_ping flips once all species have diffused
+    /// Implicit: rhs writes the other buffer, then the sweeps (or V-cycles) relax the current one in place, so read_ping stays put
+    /// Multigrid steps block on a residual readback per V-cycle
+    fn diffuse(&mut self, species: usize, timestep: f32) {
+        if let Some(model) = self.cahn_hilliard && species == 0 {
+            self.cahn_hilliard_step(&model, timestep);
+            return;
+        }
+        if let Some(model) = self.vesicle && species == 0 {
+            self.vesicle_step(&model, timestep);
+            return;
+        }
+        match self.integrator {
+            Integrator::Explicit => {
+                self.dispatch("Laplacian", &self.laplacian_p, self.laplacian_dispatch, species, timestep);
+            },
+            Integrator::Implicit { theta, solver: ImplicitSolver::GaussSeidel { iterations } } => {
+                self.dispatch_with("Implicit rhs", &self.implicit_rhs_p, self.laplacian_dispatch, species, self.uniforms_for(species, timestep).with_implicit(theta, 0));
+                let colours = integrator::sweep_colours(&self.dims, &self.species_bounds(species));
+                for _ in 0..iterations {
+                    for colour in 0..colours {
+                        self.dispatch_with("Implicit sweep", &self.implicit_sweep_p, self.laplacian_dispatch, species, self.uniforms_for(species, timestep).with_implicit(theta, colour));
+                    }
+                }
+            },
+            Integrator::Implicit { theta, solver: ImplicitSolver::Multigrid(config) } => {
+                self.dispatch_with("Implicit rhs", &self.implicit_rhs_p, self.laplacian_dispatch, species, self.uniforms_for(species, timestep).with_implicit(theta, 0));
+                let diffusion = self.species_diffusion(species);
+                let problem = Helmholtz::implicit_diffusion(theta, timestep, diffusion.coefficient);
+                let (read_ping, bounds) = (self.read_ping, self.species_bounds(species));
+                let history = self.multigrid(species).solve(read_ping, &diffusion.spacing, &bounds, &problem, &config);
+                self.history = Some(history);
+            }
+        }
+    }
+
+    /// Species 0's Cahn-Hilliard step, as cpu/cahn_hilliard.rs: the rhs pass, (1 - a L) w = rhs in place of φ,
+    /// then w copied into the other buffer and (1 - b L) φ' = w solved into whichever buffer step()'s parity flip makes current
+    fn cahn_hilliard_step(&mut self, model: &CahnHilliard, timestep: f32) {
+        let bounds = self.species_bounds(0).zero_flux();
+        let spacing = self.diffusion.spacing;
+        let uniforms = Uniforms::simulation(&self.dims, timestep, self.seed, self.read_ping, &bounds, &Diffusion::new(model.mobility, spacing))
+            .with_cahn_hilliard(model.well_depth, model.stabiliser(timestep));
+        self.dispatch_with("Cahn-Hilliard rhs", &self.cahn_hilliard_p, self.laplacian_dispatch, 0, uniforms);
+
+        let ([first, second], config) = (model.factors(timestep), model.solver());
+        let (read_ping, flip) = (self.read_ping, self.integrator.is_explicit());
+        self.multigrid(0).solve(read_ping, &spacing, &bounds, &first, &config);
+        let (current, other) = self.species[0].buffers(read_ping);
+        self.copy(current, other);
+        let history = self.multigrid(0).solve(read_ping != flip, &spacing, &bounds, &second, &config);
+        self.history = Some(history);
+    }
+
+    /// Species 0's vesicle step, as cpu/vesicle.rs: φ' lands in the other buffer, and is copied back when step() won't flip
+    fn vesicle_step(&mut self, model: &Vesicle, timestep: f32) {
+        let (read_ping, bounds, spacing, mut targets) = (self.read_ping, self.species_bounds(0), self.diffusion.spacing, self.vesicle_targets);
+        let buffers = self.vesicle_buffers.get_or_insert_with(|| GpuVesicle::new(
+            &self.dims, &self.device, &self.queue, &self.vesicle_p, &self.species[0].ping, &self.species[0].pong));
+        buffers.step(read_ping, timestep, &bounds, &spacing, model, &mut targets);
+        self.vesicle_targets = targets;
+        if !self.integrator.is_explicit() {
+            let (current, other) = self.species[0].buffers(read_ping);
+            self.copy(other, current);
+        }
+    }
+
+    /// Binds species 0 and 1's planes and the reaction's rates for reaction.wgsl
+    fn bind_reaction(&mut self, reaction: &Reaction) {
+        assert!(self.species.len() >= 2, "A reaction needs two species, see SpeciesRegistry::with_reaction()\n");
+        let rates = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Reaction rates buffer"),
+            contents: ReactionRates::new(reaction).flatten_u8(),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let [u, v] = [&self.species[0], &self.species[1]];
+        let bg = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Reaction bind group"),
+            layout: &self.reaction_bg_layout,
+            entries: &[
+                storage_entry(0, &u.uniforms),
+                u.ping.entry(1),
+                u.pong.entry(2),
+                v.ping.entry(3),
+                v.pong.entry(4),
+                storage_entry(5, &rates)]
+        });
+        self.reaction_bg = Some((bg, rates));
+    }
+
+    /// Binds the whole voxel buffers and the network's packed reactions for network.wgsl
+    /// The binding spans every plane, so it must fit max_storage_buffer_binding_size, see State::set_network()
+    fn bind_network(&mut self, network: &ReactionNetwork) {
+        assert!(network.species_count() == self.species.len(), "Network is not indexed as the registry, see ReactionNetwork::resolve()\n");
+        let rates = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Network rates buffer"),
+            contents: NetworkRates::new(network).flatten_u8(),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let (ping, pong) = &self.voxels;
+        let bg = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Network bind group"),
+            layout: &self.network_bg_layout,
+            entries: &[
+                storage_entry(0, &self.species[0].uniforms),
+                ping.entry(1),
+                pong.entry(2),
+                storage_entry(3, &rates)]
+        });
+        self.network_bg = Some((bg, rates));
+    }
+
+    /// A species' multigrid level buffers, allocated on first use
+    fn multigrid(&mut self, species: usize) -> &GpuMultigrid {
+        let buffers = &mut self.species[species];
+        buffers.multigrid.get_or_insert_with(|| GpuMultigrid::new(
+            &self.dims, &self.device, &self.queue, &self.multigrid_p, &buffers.ping, &buffers.pong))
+    }
+
+    fn copy(&self, source: &FieldSlice, destination: &FieldSlice) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Copy field")
+        });
+        encoder.copy_buffer_to_buffer(&source.buffer, source.offset, &destination.buffer, destination.offset, source.size);
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+impl SimulationBackend for GpuBackend {
+    fn dims(&self) -> Dims3 {
+        self.dims
+    }
+
+    /// init.wgsl writes grid_a (ping) of every species seeded at random, counted by species,
+    /// constant species, reacting species, Cahn-Hilliard's and the vesicle's φ are seeded on the CPU and uploaded to ping
+    fn init(&mut self, seed: u32) {
+        self.seed = seed;
+        self.read_ping = true;
+        self.vesicle_targets = None;
+        for species in 0..self.species.len() {
+            match self.registry.get(species).map(|s| s.initial).unwrap_or(InitialCondition::Random) {
+                InitialCondition::Random => {
+                    self.dispatch_with("Init", &self.init_p, self.init_dispatch, species, self.uniforms_for(species, 0.0).with_init(species));
+                },
+                initial => Resources::write_slice(&self.queue, &self.species[species].ping, &initial.field(&self.dims, seed, species))
+            }
+        }
+        if let Some(reaction) = &self.reaction {
+            for (species, field) in reaction.initial_fields(&self.dims, seed).iter().enumerate() {
+                Resources::write_slice(&self.queue, &self.species[species].ping, field);
+            }
+        }
+        if let Some(model) = &self.cahn_hilliard {
+            Resources::write_slice(&self.queue, &self.species[0].ping, &model.initial_field(&self.dims, seed));
+        }
+        else if let Some(model) = &self.vesicle {
+            Resources::write_slice(&self.queue, &self.species[0].ping, &model.initial_field(&self.dims, &self.diffusion.spacing));
+        }
+    }
+
+    /// Every species diffuses, then reaction.wgsl updates both current buffers in place, network.wgsl every species'
+    /// and noise.wgsl kicks species 0's
+    fn step(&mut self, timestep: f32) {
+        for species in 0..self.species.len() {
+            self.diffuse(species, timestep);
+        }
+        if self.integrator.is_explicit() { self.read_ping = !self.read_ping; }
+
+        if let Some((bg, _)) = &self.reaction_bg {
+            self.queue.write_buffer(&self.species[0].uniforms, 0, self.uniforms_for(0, timestep).flatten_u8());
+            self.submit("Reaction", &self.reaction_p, bg, self.laplacian_dispatch);
+        }
+
+        if let Some((bg, _)) = &self.network_bg {
+            let uniforms = self.uniforms_for(0, timestep).with_network(self.species.len(), self.plane_stride);
+            self.queue.write_buffer(&self.species[0].uniforms, 0, uniforms.flatten_u8());
+            self.submit("Network", &self.network_p, bg, self.laplacian_dispatch);
+        }
+
+        if let Some((noise, seed)) = &self.noise {
+            let amplitudes = noise.amplitudes(self.noise_mobility(), timestep, &self.diffusion.spacing);
+            let (step, substep) = self.step_index;
+            let uniforms = Uniforms::simulation(&self.dims, timestep, *seed, self.read_ping, &self.species_bounds(0), &self.species_diffusion(0))
+                .with_noise(step, substep, noise.conserved, &amplitudes);
+            self.dispatch_with("Noise", &self.noise_p, self.laplacian_dispatch, 0, uniforms);
+        }
+        self.step_index.1 = self.step_index.1.wrapping_add(1);
+    }
+
+    fn read_species(&mut self, species: usize) -> VoxelField {
+        let (current, _) = self.species[species].buffers(self.read_ping);
+        VoxelField::new(self.dims, Resources::read_slice(&self.device, &self.queue, current))
+    }
+
+    fn write_species(&mut self, species: usize, field: &[f32]) {
+        assert!(field.len() == (self.dims[0] * self.dims[1] * self.dims[2]) as usize, "Field length does not match dims\n");
+        let (current, _) = self.species[species].buffers(self.read_ping);
+        Resources::write_slice(&self.queue, current, field);
+        if species == 0 { self.vesicle_targets = None; }
+    }
+
+    fn species_count(&self) -> usize {
+        self.species.len()
+    }
+
+    fn restore_field(&mut self, field: &[f32], read_ping: bool) {
+        self.read_ping = read_ping;
+        self.write_species(0, field);
+    }
+
+    fn read_ping(&self) -> bool {
+        self.read_ping
+    }
+
+    fn set_boundaries(&mut self, bounds: &BoundaryConditions) {
+        self.bounds = *bounds;
+    }
+
+    fn set_diffusion(&mut self, diffusion: &Diffusion) {
+        self.diffusion = *diffusion;
+    }
+
+    fn set_integrator(&mut self, integrator: &Integrator) {
+        self.integrator = *integrator;
+        self.history = None;
+    }
+
+    /// Rebuilds every species' bind group over resources' planes, which set_species_count() may have replaced
+    /// Multigrid and vesicle buffers bound the old planes, so they are dropped and allocated again by their next step
+    fn set_species(&mut self, registry: &SpeciesRegistry, resources: &Resources) {
+        assert!(resources.species_count() >= registry.len(), "Resources hold fewer species planes than the registry\n");
+        self.registry = registry.clone();
+        self.species = (0..registry.len())
+            .map(|species| SpeciesBuffers::new(&self.device, &self.sim_bg_layout, resources.species_planes(species, &self.dims), self.uniforms_for(species, 0.0)))
+            .collect();
+        self.vesicle_buffers = None;
+        self.history = None;
+        self.reaction_bg = None;
+        if let Some(reaction) = self.reaction {
+            self.bind_reaction(&reaction);
+        }
+        self.voxels = resources.all_planes();
+        self.plane_stride = resources.plane_stride();
+        self.network_bg = None;
+        self.network = self.network.take().and_then(|network| network.resolve(registry).ok());
+        if let Some(network) = self.network.clone() {
+            self.bind_network(&network);
+        }
+    }
+
+    /// Binds species 0 and 1 to the reaction, or unbinds them with None
+    fn set_reaction(&mut self, reaction: Option<&Reaction>) {
+        self.reaction = reaction.copied();
+        self.reaction_bg = None;
+        if let Some(reaction) = reaction {
+            self.bind_reaction(reaction);
+        }
+    }
+
+    /// Binds every species' planes to the network, or unbinds them with None
+    fn set_network(&mut self, network: Option<&ReactionNetwork>) {
+        self.network = network.cloned();
+        self.network_bg = None;
+        if let Some(network) = network {
+            self.bind_network(network);
+        }
+    }
+
+    fn set_cahn_hilliard(&mut self, model: Option<&CahnHilliard>) {
+        self.cahn_hilliard = model.copied();
+        self.history = None;
+    }
+
+    fn set_vesicle(&mut self, model: Option<&Vesicle>) {
+        self.vesicle = model.copied();
+        self.vesicle_targets = None;
+    }
+
+    fn set_noise(&mut self, noise: Option<&ThermalNoise>, seed: u32) {
+        self.noise = noise.map(|noise| (*noise, seed));
+    }
+
+    fn set_step_index(&mut self, step: u64, substep: u32) {
+        self.step_index = (step, substep);
+    }
+
+    fn solver_history(&self) -> Option<&ConvergenceHistory> {
+        self.history.as_ref()
+    }
+
+    /// Every species already lives in Resources' planes, Compute::bind_species() points the raymarch pass at it
+    fn sync_for_render(&mut self, _resources: &Resources, _gfx_ctx: &GraphicsContext, _species: usize) -> bool {
+        self.read_ping
+    }
+}
@@ -1,26 +1,66 @@
-use wgpu::{BindGroup, PipelineCompilationOptions, BindGroupEntry, BindGroupLayout, BufferBinding, ComputePipeline, PipelineLayout, ShaderModule, ShaderStages, TextureFormat};
+use wgpu::{BindGroup, Device, PipelineCompilationOptions, BindGroupEntry, BindGroupLayout, BufferBinding, ComputePipeline, PipelineLayout, ShaderModule, ShaderStages, TextureFormat};
 use std::num::NonZero;
 use crate::{world::voxel_grid::Dims3, backend_admin::gpu::{
     enums::{Access, OffsetBehaviour}, 
     builders::BindGroupLayoutBuilder,
     gfx_context::GraphicsContext,
+    multigrid::MultigridPipelines,
+    vesicle::VesiclePipelines,
+    particles::ParticlePipelines,
     resources::{Uniforms, Resources}}};
 
+/// Philox and its distributions, prepended to the shaders that draw random numbers (init, noise and particles), see world/rng.rs
+const RNG_WGSL: &str = include_str!("../../shaders/rng.wgsl");
 
 
 /// Responsible for Compute pipeline, including
-/// init, raymarch and laplacian
+/// init, raymarch, laplacian, the implicit rhs / sweep passes, the multigrid passes, the reaction pass, the reaction network pass,
+/// the Cahn-Hilliard rhs, the vesicle passes, the thermal noise pass and the particle passes
+/// init, laplacian, implicit, Cahn-Hilliard and noise only touch the voxel buffers, so they use sim_bg_layout (no storage texture)
+/// and are bound by the GPU SimulationBackend, see gpu/backend.rs
+/// multigrid has its own layout for the level buffers, see gpu/multigrid.rs,
+/// reaction binds both species' ping/pong pairs and their rates (reaction_bg_layout),
+/// the reaction network the whole voxel buffers, every species' plane, and its packed reactions (network_bg_layout),
+/// and vesicle has its own layout for its potential, partials and membrane buffers, see gpu/vesicle.rs,
+/// as particles has for its particle, cell and neighbour buffers, see gpu/particles.rs
 pub struct Compute{
     init_shader: ShaderModule,
     laplacian_shader: ShaderModule,
+    implicit_shader: ShaderModule,
+    multigrid_shader: ShaderModule,
+    reaction_shader: ShaderModule,
+    network_shader: ShaderModule,
+    cahn_hilliard_shader: ShaderModule,
+    vesicle_shader: ShaderModule,
+    noise_shader: ShaderModule,
+    particle_shader: ShaderModule,
     raymarch_shader: ShaderModule,
 
     bg_layout: BindGroupLayout,
     pub bg: BindGroup,
+    displayed: usize, // species whose planes bg binds
+    pub sim_bg_layout: BindGroupLayout,
+    pub reaction_bg_layout: BindGroupLayout,
+    pub network_bg_layout: BindGroupLayout,
 
     p_layout: PipelineLayout,
+    sim_p_layout: PipelineLayout,
+    mg_p_layout: PipelineLayout,
+    reaction_p_layout: PipelineLayout,
+    network_p_layout: PipelineLayout,
+    vesicle_p_layout: PipelineLayout,
+    particle_p_layout: PipelineLayout,
     pub init_p: ComputePipeline,
     pub laplacian_p: ComputePipeline,
+    pub implicit_rhs_p: ComputePipeline,
+    pub implicit_sweep_p: ComputePipeline,
+    pub multigrid: MultigridPipelines,
+    pub reaction_p: ComputePipeline,
+    pub network_p: ComputePipeline,
+    pub cahn_hilliard_p: ComputePipeline,
+    pub vesicle: VesiclePipelines,
+    pub noise_p: ComputePipeline,
+    pub particles: ParticlePipelines,
     pub raymarch_p: ComputePipeline
     
 }
@@ -30,12 +70,44 @@ impl Compute {
         // Load shader module
         let init = gfx_ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Init"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/init.wgsl").into())
+            source: wgpu::ShaderSource::Wgsl(format!("{}{}", RNG_WGSL, include_str!("../../shaders/init.wgsl")).into())
             });
         let laplacian = gfx_ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Laplacian"),
             source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/laplacian.wgsl").into())
             });
+        let implicit = gfx_ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Implicit"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/implicit.wgsl").into())
+            });
+        let multigrid = gfx_ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Multigrid"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/multigrid.wgsl").into())
+            });
+        let reaction = gfx_ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Reaction"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/reaction.wgsl").into())
+            });
+        let network = gfx_ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Network"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/network.wgsl").into())
+            });
+        let cahn_hilliard = gfx_ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Cahn-Hilliard"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/cahn_hilliard.wgsl").into())
+            });
+        let vesicle = gfx_ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Vesicle"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/vesicle.wgsl").into())
+            });
+        let noise = gfx_ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Noise"),
+            source: wgpu::ShaderSource::Wgsl(format!("{}{}", RNG_WGSL, include_str!("../../shaders/noise.wgsl")).into())
+            });
+        let particles = gfx_ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particles"),
+            source: wgpu::ShaderSource::Wgsl(format!("{}{}", RNG_WGSL, include_str!("../../shaders/particles.wgsl")).into())
+            });
         let raymarch = gfx_ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Raymarch"),
             source: wgpu::ShaderSource::Wgsl(include_str!("../../shaders/raymarch.wgsl").into())
@@ -61,40 +133,75 @@ impl Compute {
             wgpu::TextureViewDimension::D2)
             .build(&gfx_ctx.device);
 
-        let bind_group_descriptor = &wgpu::BindGroupDescriptor { //TODO: CONSIDER MAKING A BUILD GROUP DESCRIPTOR BUILDER
-        label: Some("Bind group descriptor"),
-        layout: &bind_group_layout,
-        entries: &[
-        BindGroupEntry {
-            binding: 0,
-            resource: wgpu::BindingResource::Buffer(BufferBinding { 
-                buffer: &resources.uniforms, 
-                offset: 0, 
-                size: NonZero::new((std::mem::size_of::<Uniforms>()) as u64)
-            }),
-        },
-        BindGroupEntry {
-            binding: 1,
-            resource: wgpu::BindingResource::Buffer(BufferBinding{ 
-                buffer:  &resources.ping_voxel_buffer,
-                offset: 0,
-                size: NonZero::new((std::mem::size_of::<f32>() as u32 * dims[0] * dims[1] * dims[2]) as u64)
-        })
-        },
-        BindGroupEntry {
-            binding: 2,
-            resource: wgpu::BindingResource::Buffer(BufferBinding{ 
-                buffer:  &resources.pong_voxel_buffer,
-                offset: 0,
-                size: NonZero::new((std::mem::size_of::<f32>() as u32 * dims[0] * dims[1] * dims[2]) as u64)
-        })},
-        BindGroupEntry {
-            binding: 3,
-            resource: wgpu::BindingResource::TextureView(&resources.texture_view)}
-        ]
-        };
+        let bind_group = Self::raymarch_bind_group(&gfx_ctx.device, &bind_group_layout, resources, dims, 0);
 
-        let bind_group = gfx_ctx.device.create_bind_group(bind_group_descriptor); 
+        // Uniforms, ping and pong only
+        let sim_bind_group_layout = BindGroupLayoutBuilder::new("Simulation Bind Group".to_string())
+            .with_uniform_buffer(
+                ShaderStages::COMPUTE, 
+                OffsetBehaviour::Static)
+            .with_storage_buffer(
+                ShaderStages::COMPUTE, 
+                OffsetBehaviour::Static, 
+                Access::ReadWrite)
+            .with_storage_buffer(
+                ShaderStages::COMPUTE,
+                OffsetBehaviour::Static,
+                Access::ReadWrite)
+            .build(&gfx_ctx.device);
+
+        // Uniforms per level and colour (dynamic offset), u, f, r, coarse u, coarse f and per-workgroup partial sums
+        let mg_bind_group_layout = BindGroupLayoutBuilder::new("Multigrid Bind Group".to_string())
+            .with_uniform_buffer(
+                ShaderStages::COMPUTE,
+                OffsetBehaviour::Dynamic)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .build(&gfx_ctx.device);
+
+        // Uniforms, u ping and pong, v ping and pong, reaction rates
+        let reaction_bind_group_layout = BindGroupLayoutBuilder::new("Reaction Bind Group".to_string())
+            .with_uniform_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_uniform_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static)
+            .build(&gfx_ctx.device);
+
+        // Uniforms, whole ping and pong buffers (every species' plane), packed reactions
+        let network_bind_group_layout = BindGroupLayoutBuilder::new("Network Bind Group".to_string())
+            .with_uniform_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_uniform_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static)
+            .build(&gfx_ctx.device);
+
+        // Uniforms, ping, pong, potential, per-workgroup partial sums, membrane
+        let vesicle_bind_group_layout = BindGroupLayoutBuilder::new("Vesicle Bind Group".to_string())
+            .with_uniform_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_uniform_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static)
+            .build(&gfx_ctx.device);
+
+        // Grid, particles, sorted particles, particle cells, cell counts, cell starts, scan chunk sums, neighbours
+        let particle_bind_group_layout = BindGroupLayoutBuilder::new("Particle Bind Group".to_string())
+            .with_uniform_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .with_storage_buffer(ShaderStages::COMPUTE, OffsetBehaviour::Static, Access::ReadWrite)
+            .build(&gfx_ctx.device);
 
          // COMPUTE PIPELINE SETUP //
         let pipeline_layout = gfx_ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -104,12 +211,54 @@ impl Compute {
             push_constant_ranges: &[]
         });
 
+        let sim_pipeline_layout = gfx_ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Simulation Pipeline Layout"),
+            bind_group_layouts: &[&sim_bind_group_layout
+            ],
+            push_constant_ranges: &[]
+        });
+
+        let mg_pipeline_layout = gfx_ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Multigrid Pipeline Layout"),
+            bind_group_layouts: &[&mg_bind_group_layout
+            ],
+            push_constant_ranges: &[]
+        });
+
+        let reaction_pipeline_layout = gfx_ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Reaction Pipeline Layout"),
+            bind_group_layouts: &[&reaction_bind_group_layout
+            ],
+            push_constant_ranges: &[]
+        });
+
+        let network_pipeline_layout = gfx_ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Network Pipeline Layout"),
+            bind_group_layouts: &[&network_bind_group_layout
+            ],
+            push_constant_ranges: &[]
+        });
+
+        let vesicle_pipeline_layout = gfx_ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Vesicle Pipeline Layout"),
+            bind_group_layouts: &[&vesicle_bind_group_layout
+            ],
+            push_constant_ranges: &[]
+        });
+
+        let particle_pipeline_layout = gfx_ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Pipeline Layout"),
+            bind_group_layouts: &[&particle_bind_group_layout
+            ],
+            push_constant_ranges: &[]
+        });
+
         // Pipelines
 
         // Entry Points
         let init_pipeline = gfx_ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
             label: Some("Init"),
-            layout: Some(&pipeline_layout),
+            layout: Some(&sim_pipeline_layout),
             module: &init,
             entry_point: Some("init"),
             cache: None,
@@ -121,7 +270,7 @@ impl Compute {
 
         let laplacian_pipeline = gfx_ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
             label: Some("Laplacian"),   
-            layout: Some(&pipeline_layout),
+            layout: Some(&sim_pipeline_layout),
             module: &laplacian,
             entry_point: Some("laplacian"),
             cache: None,
@@ -130,7 +279,148 @@ impl Compute {
                 zero_initialize_workgroup_memory: true 
             }
         });
+
+        // implicit diffusion, see world/integrator.rs
+        let implicit_rhs_pipeline = gfx_ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Implicit rhs"),
+            layout: Some(&sim_pipeline_layout),
+            module: &implicit,
+            entry_point: Some("rhs"),
+            cache: None,
+            compilation_options: PipelineCompilationOptions{
+                constants: &[],
+                zero_initialize_workgroup_memory: true
+            }
+        });
+
+        let implicit_sweep_pipeline = gfx_ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Implicit sweep"),
+            layout: Some(&sim_pipeline_layout),
+            module: &implicit,
+            entry_point: Some("sweep"),
+            cache: None,
+            compilation_options: PipelineCompilationOptions{
+                constants: &[],
+                zero_initialize_workgroup_memory: true
+            }
+        });
          
+        // geometric multigrid, see world/multigrid.rs
+        let multigrid_pipeline = |entry_point: &str| gfx_ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&mg_pipeline_layout),
+            module: &multigrid,
+            entry_point: Some(entry_point),
+            cache: None,
+            compilation_options: PipelineCompilationOptions{
+                constants: &[],
+                zero_initialize_workgroup_memory: true
+            }
+        });
+        let multigrid_pipelines = MultigridPipelines {
+            bg_layout: mg_bind_group_layout.clone(),
+            relax_p: multigrid_pipeline("relax"),
+            residual_p: multigrid_pipeline("residual"),
+            norm_p: multigrid_pipeline("norm"),
+            restrict_p: multigrid_pipeline("restrict_residual"),
+            prolong_p: multigrid_pipeline("prolong")
+        };
+
+        // two-species reaction, see world/reaction.rs
+        let reaction_pipeline = gfx_ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Reaction"),
+            layout: Some(&reaction_pipeline_layout),
+            module: &reaction,
+            entry_point: Some("react"),
+            cache: None,
+            compilation_options: PipelineCompilationOptions{
+                constants: &[],
+                zero_initialize_workgroup_memory: true
+            }
+        });
+
+        // mass-action reaction network, see world/network.rs
+        let network_pipeline = gfx_ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Network"),
+            layout: Some(&network_pipeline_layout),
+            module: &network,
+            entry_point: Some("react_network"),
+            cache: None,
+            compilation_options: PipelineCompilationOptions{
+                constants: &[],
+                zero_initialize_workgroup_memory: true
+            }
+        });
+
+        // Cahn-Hilliard right hand side, see world/cahn_hilliard.rs
+        let cahn_hilliard_pipeline = gfx_ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Cahn-Hilliard rhs"),
+            layout: Some(&sim_pipeline_layout),
+            module: &cahn_hilliard,
+            entry_point: Some("rhs"),
+            cache: None,
+            compilation_options: PipelineCompilationOptions{
+                constants: &[],
+                zero_initialize_workgroup_memory: true
+            }
+        });
+
+        // phase-field vesicle, see world/vesicle.rs
+        let vesicle_pipeline = |entry_point: &str| gfx_ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&vesicle_pipeline_layout),
+            module: &vesicle,
+            entry_point: Some(entry_point),
+            cache: None,
+            compilation_options: PipelineCompilationOptions{
+                constants: &[],
+                zero_initialize_workgroup_memory: true
+            }
+        });
+        let vesicle_pipelines = VesiclePipelines {
+            bg_layout: vesicle_bind_group_layout.clone(),
+            curvature_p: vesicle_pipeline("curvature"),
+            bending_p: vesicle_pipeline("bending"),
+            relax_p: vesicle_pipeline("relax")
+        };
+
+        // thermal noise, see world/noise.rs
+        let noise_pipeline = gfx_ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Noise"),
+            layout: Some(&sim_pipeline_layout),
+            module: &noise,
+            entry_point: Some("noise"),
+            cache: None,
+            compilation_options: PipelineCompilationOptions{
+                constants: &[],
+                zero_initialize_workgroup_memory: true
+            }
+        });
+
+        // particles and their spatial hash, see gpu/particles.rs
+        let particle_pipeline = |entry_point: &str| gfx_ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&particle_pipeline_layout),
+            module: &particles,
+            entry_point: Some(entry_point),
+            cache: None,
+            compilation_options: PipelineCompilationOptions{
+                constants: &[],
+                zero_initialize_workgroup_memory: true
+            }
+        });
+        let particle_pipelines = ParticlePipelines {
+            bg_layout: particle_bind_group_layout.clone(),
+            advance_p: particle_pipeline("advance"),
+            clear_cells_p: particle_pipeline("clear_cells"),
+            count_cells_p: particle_pipeline("count_cells"),
+            scan_chunks_p: particle_pipeline("scan_chunks"),
+            scan_sums_p: particle_pipeline("scan_sums"),
+            add_offsets_p: particle_pipeline("add_offsets"),
+            scatter_p: particle_pipeline("scatter"),
+            count_neighbours_p: particle_pipeline("count_neighbours")
+        };
+
         let raymarch_pipeline = gfx_ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
             label: Some("Raymarch"),
             layout: Some(&pipeline_layout),
@@ -146,23 +436,63 @@ impl Compute {
             Compute {
                 init_shader: init,
                 laplacian_shader: laplacian,
+                implicit_shader: implicit,
+                multigrid_shader: multigrid,
+                reaction_shader: reaction,
+                network_shader: network,
+                cahn_hilliard_shader: cahn_hilliard,
+                vesicle_shader: vesicle,
+                noise_shader: noise,
+                particle_shader: particles,
                 raymarch_shader: raymarch,
 
                 bg_layout: bind_group_layout,
                 bg: bind_group,
+                displayed: 0,
+                sim_bg_layout: sim_bind_group_layout,
+                reaction_bg_layout: reaction_bind_group_layout,
+                network_bg_layout: network_bind_group_layout,
 
                 p_layout: pipeline_layout,
+                sim_p_layout: sim_pipeline_layout,
+                mg_p_layout: mg_pipeline_layout,
+                reaction_p_layout: reaction_pipeline_layout,
+                network_p_layout: network_pipeline_layout,
+                vesicle_p_layout: vesicle_pipeline_layout,
+                particle_p_layout: particle_pipeline_layout,
                 init_p: init_pipeline,
                 laplacian_p: laplacian_pipeline,
+                implicit_rhs_p: implicit_rhs_pipeline,
+                implicit_sweep_p: implicit_sweep_pipeline,
+                multigrid: multigrid_pipelines,
+                reaction_p: reaction_pipeline,
+                network_p: network_pipeline,
+                cahn_hilliard_p: cahn_hilliard_pipeline,
+                vesicle: vesicle_pipelines,
+                noise_p: noise_pipeline,
+                particles: particle_pipelines,
                 raymarch_p: raymarch_pipeline
             }
 
     }
 
     pub fn on_resize(&mut self, dims: &Dims3, gfx_ctx: &GraphicsContext, rsrcs: &Resources) {
-        let bind_group_descriptor = &wgpu::BindGroupDescriptor {
+        self.bg = Self::raymarch_bind_group(&gfx_ctx.device, &self.bg_layout, rsrcs, dims, self.displayed);
+    }
+
+    /// Points the raymarch pass at a species' planes of the voxel buffers, see SimulationBackend::sync_for_render()
+    /// Also needed after Resources::set_species_count() replaces the buffers
+    pub fn bind_species(&mut self, dims: &Dims3, gfx_ctx: &GraphicsContext, rsrcs: &Resources, species: usize) {
+        self.displayed = species;
+        self.on_resize(dims, gfx_ctx, rsrcs);
+    }
+
+    /// Uniforms, the species' ping and pong planes and the storage texture
+    fn raymarch_bind_group(device: &Device, layout: &BindGroupLayout, rsrcs: &Resources, dims: &Dims3, species: usize) -> BindGroup {
+        let (ping, pong) = rsrcs.species_planes(species, dims);
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Bind group descriptor"),
-            layout: &self.bg_layout,
+            layout: layout,
             entries: &[BindGroupEntry {
                 binding: 0,
                 resource: wgpu::BindingResource::Buffer(BufferBinding { 
@@ -171,30 +501,14 @@ impl Compute {
                     size: NonZero::new((std::mem::size_of::<Uniforms>()) as u64)
                 }),
             },
-            BindGroupEntry {
-                binding: 1,
-                resource: wgpu::BindingResource::Buffer(BufferBinding{ // actual voxel grid storage buffer @ binding 1
-                    buffer:  &rsrcs.ping_voxel_buffer,
-                    offset: 0,
-                    size: NonZero::new((std::mem::size_of::<f32>() as u32 * dims[0] * dims[1] * dims[2]) as u64)
-            })
-            },
-            BindGroupEntry {
-                binding: 2,
-                resource: wgpu::BindingResource::Buffer(BufferBinding{ // actual voxel grid storage buffer @ binding 1
-                    buffer:  &rsrcs.pong_voxel_buffer,
-                    offset: 0,
-                    size: NonZero::new((std::mem::size_of::<f32>() as u32 * dims[0] * dims[1] * dims[2]) as u64)
-            })
-            },
+            ping.entry(1), // voxel grid storage buffers @ bindings 1 and 2
+            pong.entry(2),
             BindGroupEntry {
                 binding: 3,
                 resource: wgpu::BindingResource::TextureView(&rsrcs.texture_view)
             }
             ]
-        };
-        self.bg = gfx_ctx.device.create_bind_group(bind_group_descriptor);
+        })
     }
 
 }
-
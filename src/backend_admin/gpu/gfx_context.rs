@@ -2,23 +2,50 @@ use std::{sync::Arc};
 use winit::{
     dpi::PhysicalSize,
     window::Window};
-use wgpu::{Adapter, Device, Instance, Queue, Surface, SurfaceConfiguration};
+use wgpu::{Adapter, Device, Instance, Queue, Surface, SurfaceConfiguration, SurfaceTexture, Texture, TextureView};
 use anyhow::Result;
 use std::error::Error;
 
+/// Offscreen colour target format used in place of a surface when headless
+const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
 pub struct GraphicsContext {
-    pub window: Arc<Window>,
+    pub window: Option<Arc<Window>>, // None when headless
     pub size: PhysicalSize<u32>,
     instance: Instance,
     adapter: Adapter,
 
-    pub surface: Surface<'static>,
-    pub surface_config: SurfaceConfiguration,
+    pub surface: Option<Surface<'static>>, // None when headless, see offscreen
+    pub surface_config: SurfaceConfiguration, // when headless, describes the offscreen target instead
     pub surface_configured: bool,
+    pub offscreen: Option<Texture>, // render target stand-in for the surface when headless
 
     pub device: Device,
     pub queue: Queue,
-    
+
+}
+
+/// The colour target State::render() draws into on a given frame
+/// Either the window's surface texture or the headless offscreen texture
+pub enum Frame {
+    Surface(SurfaceTexture),
+    Offscreen(TextureView)
+}
+
+impl Frame {
+    pub fn view(&self) -> TextureView {
+        match self {
+            Frame::Surface(surface_texture) => surface_texture.texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            Frame::Offscreen(view) => view.clone()
+        }
+    }
+
+    /// Presents surface textures, no-op for offscreen (contents stay in GraphicsContext::offscreen)
+    pub fn present(self) {
+        if let Frame::Surface(surface_texture) = self {
+            surface_texture.present();
+        }
+    }
 }
 
 impl GraphicsContext {
@@ -38,13 +65,7 @@ impl GraphicsContext {
             force_fallback_adapter: false
         }).await?;
 
-        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor{
-            label: None,
-            required_features: wgpu::Features::default(), 
-            required_limits: wgpu::Limits::defaults(),
-            trace: wgpu::Trace::Off,
-            memory_hints: Default::default(),
-        }).await?;
+        let (device, queue) = Self::request_device(&adapter).await?;
 
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps.formats.iter()
@@ -67,14 +88,15 @@ impl GraphicsContext {
 
         Ok (
             GraphicsContext {
-                window: win,
+                window: Some(win),
                 size: size,
                 instance: instance,
                 adapter: adapter,
 
-                surface: surface,
+                surface: Some(surface),
                 surface_config: surface_config,
                 surface_configured: true,
+                offscreen: None,
 
                 device: device,
                 queue: queue,
@@ -82,15 +104,126 @@ impl GraphicsContext {
         )
     }
 
+    /// Windowless context for batch runs on headless machines (CI, servers)
+    /// Accepts any adapter, falling back to a software adapter if no hardware adapter is found
+    /// Frames are rendered into GraphicsContext::offscreen in place of a surface
+    pub async fn new_headless(size: PhysicalSize<u32>) -> Result<Self, Box<dyn Error>> {
+        assert!(size.width > 0 && size.height > 0);
+
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+
+        let adapter = match instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface: None,
+            force_fallback_adapter: false
+        }).await {
+            Ok(adapter) => adapter,
+            Err(_) => {
+                println!("No hardware adapter found, requesting fallback adapter\n");
+                instance.request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::default(),
+                    compatible_surface: None,
+                    force_fallback_adapter: true
+                }).await?
+            }
+        };
+
+        let (device, queue) = Self::request_device(&adapter).await?;
+
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: OFFSCREEN_FORMAT,
+            width: size.width,
+            height: size.height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        let offscreen = Self::create_offscreen(&device, &surface_config);
+
+        Ok (
+            GraphicsContext {
+                window: None,
+                size: size,
+                instance: instance,
+                adapter: adapter,
+
+                surface: None,
+                surface_config: surface_config,
+                surface_configured: true,
+                offscreen: Some(offscreen),
+
+                device: device,
+                queue: queue,
+            }
+        )
+    }
+
+    async fn request_device(adapter: &Adapter) -> Result<(Device, Queue), Box<dyn Error>> {
+        Ok(adapter.request_device(&wgpu::DeviceDescriptor{
+            label: None,
+            required_features: wgpu::Features::default(),
+            required_limits: wgpu::Limits::defaults(),
+            trace: wgpu::Trace::Off,
+            memory_hints: Default::default(),
+        }).await?)
+    }
+
+    fn create_offscreen(device: &Device, config: &SurfaceConfiguration) -> Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Target"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[]
+        })
+    }
+
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.adapter.get_info()
+    }
+
+    /// Gets this frame's colour target: the next surface texture, or the offscreen texture when headless
+    pub fn acquire_frame(&self) -> Result<Frame, wgpu::SurfaceError> {
+        match (&self.surface, &self.offscreen) {
+            (Some(surface), _) => Ok(Frame::Surface(surface.get_current_texture()?)),
+            (None, Some(offscreen)) => Ok(Frame::Offscreen(offscreen.create_view(&wgpu::TextureViewDescriptor::default()))),
+            (None, None) => panic!("GraphicsContext has neither a surface nor an offscreen target\n")
+        }
+    }
+
     pub fn update_surface_config(&mut self) -> PhysicalSize<u32> {
-        let surface_caps = self.surface.get_capabilities(&self.adapter);
+        let (surface, window) = match (&self.surface, &self.window) {
+            (Some(surface), Some(window)) => (surface, window),
+            _ => {
+                // headless: no surface to query, rebuild the offscreen target at the current size
+                self.surface_config.width = self.size.width;
+                self.surface_config.height = self.size.height;
+                self.offscreen = Some(Self::create_offscreen(&self.device, &self.surface_config));
+                self.surface_configured = true;
+                return self.size;
+            }
+        };
+
+        let surface_caps = surface.get_capabilities(&self.adapter);
 
         let surface_format = surface_caps.formats.iter()
             .find(|f| f.is_srgb())
             .copied()
             .unwrap_or(surface_caps.formats[0]);
 
-        let size = self.window.inner_size();
+        let size = window.inner_size();
 
         self.surface_config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -102,12 +235,12 @@ impl GraphicsContext {
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
-        self.surface.configure(&self.device, &self.surface_config);
+        surface.configure(&self.device, &self.surface_config);
+
 
-        
         self.surface_configured = true;
 
         size
     }
 
-}
\ No newline at end of file
+}
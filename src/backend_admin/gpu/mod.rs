@@ -3,4 +3,8 @@ pub mod builders;
 pub mod resources;
 pub mod gfx_context;
 pub mod compute;
-pub mod render;
\ No newline at end of file
+pub mod render;
+pub mod backend;
+pub mod multigrid;
+pub mod vesicle;
+pub mod particles;
\ No newline at end of file
@@ -0,0 +1,255 @@
+use wgpu::{BindGroup, BindGroupEntry, BindGroupLayout, Buffer, BufferBinding, BufferUsages, ComputePass, ComputePipeline, Device, Queue};
+use std::num::NonZero;
+use crate::{
+    backend_admin::{bridge::{self, DispatchDims}, gpu::resources::{FieldSlice, Resources}},
+    world::{
+        boundary::BoundaryConditions,
+        integrator::{MAX_SWEEP_COLOURS, sweep_colours},
+        multigrid::{self, ConvergenceHistory, Helmholtz, Level, MultigridConfig},
+        voxel_grid::{Dims3, P3}}
+};
+
+/// The multigrid.wgsl entry points, built once by Compute and cloned into each GpuMultigrid
+#[derive(Clone)]
+pub struct MultigridPipelines {
+    pub bg_layout: BindGroupLayout, // uniforms (dynamic offset), u, f, r, coarse u, coarse f, partials
+    pub relax_p: ComputePipeline,
+    pub residual_p: ComputePipeline,
+    pub norm_p: ComputePipeline,
+    pub restrict_p: ComputePipeline,
+    pub prolong_p: ComputePipeline
+}
+
+/// One slot of the uniforms buffer per level and sweep colour, bound by dynamic offset
+/// so a whole V-cycle is encoded into a single compute pass
+const SLOT_STRIDE: u64 = 256; // wgpu's default min_uniform_buffer_offset_alignment
+
+/// GPU counterpart of cpu::multigrid::solve, see world/multigrid.rs
+/// The finest level is a species' ping/pong planes: u is the current field, f the other buffer
+/// (the implicit rhs pass writes it), so solving leaves the result where step() expects it
+/// Coarse levels own their u, f and r, allocated once for the grid's dims
+pub struct GpuMultigrid {
+    device: Device,
+    queue: Queue,
+    pipelines: MultigridPipelines,
+
+    levels: Vec<Level>,
+    dispatches: Vec<DispatchDims>,
+    uniforms: Buffer,
+    partials: Buffer, // one sum of squares per finest level workgroup
+    partial_count: u32,
+    bgs: Vec<BindGroup> // [0] u = ping, f = pong, [1] u = pong, f = ping, then one per coarser level
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct MultigridUniforms {
+    dims: [u32; 4], // i, j, k, ij plane stride for k
+    coarse_dims: [u32; 4], // zeroed on the coarsest level
+    ratio: [u32; 4],
+    weights: [f32; 4], // Helmholtz::weights() for this level's spacing
+    dirichlet: [f32; 4], // Level::dirichlet_weights(), [3] unused
+    flags: [u32; 4], // [0] sweep colour
+    bc_kind_lo: [u32; 4],
+    bc_kind_hi: [u32; 4],
+    bc_value_lo: [f32; 4],
+    bc_value_hi: [f32; 4]
+}
+
+impl MultigridUniforms {
+    fn flatten_u8(&self) -> &[u8] {
+        let ptr = self as *const _ as *const u8;
+        unsafe {
+            std::slice::from_raw_parts(ptr, std::mem::size_of::<MultigridUniforms>())
+        }
+    }
+}
+
+impl GpuMultigrid {
+    pub fn new(dims: &Dims3, device: &Device, queue: &Queue, pipelines: &MultigridPipelines, ping: &FieldSlice, pong: &FieldSlice) -> Self {
+        let levels = multigrid::hierarchy(dims);
+        let dispatches: Vec<DispatchDims> = levels.iter().map(|l| bridge::voxel_dispatch(&l.dims)).collect();
+        let partial_count = dispatches[0].iter().product::<u32>();
+
+        let storage = |label: &str, len: usize, usage: BufferUsages| device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: (std::mem::size_of::<f32>() * len) as u64,
+            usage: BufferUsages::STORAGE | usage,
+            mapped_at_creation: false
+        });
+        // coarse levels' u and f, residuals on every level
+        let us: Vec<Buffer> = levels[1..].iter().map(|l| storage("Multigrid u", l.len(), BufferUsages::empty())).collect();
+        let fs: Vec<Buffer> = levels[1..].iter().map(|l| storage("Multigrid f", l.len(), BufferUsages::empty())).collect();
+        let rs: Vec<Buffer> = levels.iter().map(|l| storage("Multigrid r", l.len(), BufferUsages::empty())).collect();
+        let partials = storage("Multigrid partials", partial_count as usize, BufferUsages::COPY_SRC);
+        // stand-ins for the coarse bindings of the coarsest level, never touched
+        let (no_u, no_f) = (storage("Multigrid no coarse u", 1, BufferUsages::empty()), storage("Multigrid no coarse f", 1, BufferUsages::empty()));
+
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Multigrid uniform buffer"),
+            size: SLOT_STRIDE * MAX_SWEEP_COLOURS as u64 * levels.len() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let bind = |u: &FieldSlice, f: &FieldSlice, level: usize| {
+            let (coarse_u, coarse_f) = if level + 1 < levels.len() { (&us[level], &fs[level]) } else { (&no_u, &no_f) };
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Multigrid bind group"),
+                layout: &pipelines.bg_layout,
+                entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(BufferBinding {
+                        buffer: &uniforms,
+                        offset: 0,
+                        size: NonZero::new(std::mem::size_of::<MultigridUniforms>() as u64)
+                    }),
+                },
+                u.entry(1),
+                f.entry(2),
+                storage_entry(3, &rs[level]),
+                storage_entry(4, coarse_u),
+                storage_entry(5, coarse_f),
+                storage_entry(6, &partials)]
+            })
+        };
+        let mut bgs = vec![bind(ping, pong, 0), bind(pong, ping, 0)];
+        bgs.extend((1..levels.len()).map(|level| bind(&FieldSlice::whole(&us[level - 1]), &FieldSlice::whole(&fs[level - 1]), level)));
+
+        GpuMultigrid {
+            device: device.clone(),
+            queue: queue.clone(),
+            pipelines: pipelines.clone(),
+
+            levels: levels,
+            dispatches: dispatches,
+            uniforms: uniforms,
+            partials: partials,
+            partial_count: partial_count,
+            bgs: bgs
+        }
+    }
+
+    /// Solves (α - β L) u = f by V-cycles, u being the current field (ping when read_ping) and f the other buffer
+    /// Blocks on one small readback per cycle for the residual norm
+    pub fn solve(&self, read_ping: bool, spacing: &P3, bounds: &BoundaryConditions, problem: &Helmholtz, config: &MultigridConfig) -> ConvergenceHistory {
+        self.write_uniforms(spacing, bounds, problem);
+        let finest = if read_ping { 0 } else { 1 };
+        let coarse_bounds = multigrid::coarse_bounds(bounds);
+        let colours: Vec<u32> = self.levels.iter().enumerate()
+            .map(|(index, level)| sweep_colours(&level.dims, if index == 0 { bounds } else { &coarse_bounds }))
+            .collect();
+
+        self.submit("Multigrid norm", |pass| self.encode(pass, &self.pipelines.norm_p, 0, 0, finest, self.dispatches[0]));
+        let f_norm = self.sum_partials().sqrt();
+        let scale = if f_norm > 0.0 { f_norm } else { 1.0 }; // f = 0: absolute residual
+
+        let mut history = ConvergenceHistory::default();
+        self.submit("Multigrid residual", |pass| self.encode(pass, &self.pipelines.residual_p, 0, 0, finest, self.dispatches[0]));
+        history.residuals.push(self.sum_partials().sqrt() / scale);
+        for _ in 0..config.max_cycles {
+            if history.residuals.last().is_some_and(|r| *r <= config.tolerance as f64) { break; }
+            self.submit("Multigrid V-cycle", |pass| {
+                self.v_cycle(pass, 0, finest, config, &colours);
+                self.encode(pass, &self.pipelines.residual_p, 0, 0, finest, self.dispatches[0]);
+            });
+            history.residuals.push(self.sum_partials().sqrt() / scale);
+        }
+        history.converged = history.residuals.last().is_some_and(|r| *r <= config.tolerance as f64);
+        history
+    }
+
+    /// Same order of passes as cpu::multigrid's Solver::v_cycle
+    /// colours per level, see integrator::sweep_colours
+    fn v_cycle(&self, pass: &mut ComputePass, level: usize, finest: usize, config: &MultigridConfig, colours: &[u32]) {
+        if level + 1 == self.levels.len() {
+            self.relax(pass, level, finest, config.coarse_sweeps, colours[level]);
+            return;
+        }
+        self.relax(pass, level, finest, config.pre_smooth, colours[level]);
+        self.encode(pass, &self.pipelines.residual_p, level, 0, finest, self.dispatches[level]);
+        self.encode(pass, &self.pipelines.restrict_p, level, 0, finest, self.dispatches[level + 1]);
+        self.v_cycle(pass, level + 1, finest, config, colours);
+        self.encode(pass, &self.pipelines.prolong_p, level, 0, finest, self.dispatches[level]);
+        self.relax(pass, level, finest, config.post_smooth, colours[level]);
+    }
+
+    fn relax(&self, pass: &mut ComputePass, level: usize, finest: usize, sweeps: u32, colours: u32) {
+        for _ in 0..sweeps {
+            for colour in 0..colours as usize {
+                self.encode(pass, &self.pipelines.relax_p, level, colour, finest, self.dispatches[level]);
+            }
+        }
+    }
+
+    fn encode(&self, pass: &mut ComputePass, pipeline: &ComputePipeline, level: usize, colour: usize, finest: usize, dispatch: DispatchDims) {
+        let bg = if level == 0 { &self.bgs[finest] } else { &self.bgs[level + 1] };
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, bg, &[(((MAX_SWEEP_COLOURS as usize * level) + colour) as u64 * SLOT_STRIDE) as u32]);
+        let [x, y, z] = dispatch;
+        pass.dispatch_workgroups(x, y, z);
+    }
+
+    fn submit(&self, label: &str, encode: impl FnOnce(&mut ComputePass)) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some(label)
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some(label),
+                timestamp_writes: None
+            });
+            encode(&mut compute_pass);
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Sum of the last residual / norm pass's per-workgroup partials, in f64 as cpu::multigrid::norm
+    fn sum_partials(&self) -> f64 {
+        Resources::read_buffer(&self.device, &self.queue, &self.partials, &[self.partial_count, 1, 1])
+            .iter().map(|v| *v as f64).sum()
+    }
+
+    /// Every level and colour's uniforms, written once per solve since bounds and the problem can change between steps
+    fn write_uniforms(&self, spacing: &P3, bounds: &BoundaryConditions, problem: &Helmholtz) {
+        let coarse_bounds = multigrid::coarse_bounds(bounds);
+        let mut bytes = vec![0u8; (SLOT_STRIDE as usize) * MAX_SWEEP_COLOURS as usize * self.levels.len()];
+        for (index, level) in self.levels.iter().enumerate() {
+            let level_bounds = if index == 0 { bounds } else { &coarse_bounds };
+            let (coarse_dims, ratio) = match self.levels.get(index + 1) {
+                Some(coarse) => {
+                    let [rx, ry, rz] = multigrid::ratios(level, coarse);
+                    ([coarse.dims[0], coarse.dims[1], coarse.dims[2], coarse.dims[0] * coarse.dims[1]], [rx, ry, rz, 0])
+                },
+                None => ([0, 0, 0, 0], [1, 1, 1, 0])
+            };
+            let [wx, wy, wz] = level.dirichlet_weights();
+            for colour in 0..MAX_SWEEP_COLOURS {
+                let uniforms = MultigridUniforms {
+                    dims: [level.dims[0], level.dims[1], level.dims[2], level.dims[0] * level.dims[1]],
+                    coarse_dims: coarse_dims,
+                    ratio: ratio,
+                    weights: problem.weights(&level.spacing(spacing)),
+                    dirichlet: [wx, wy, wz, 0.0],
+                    flags: [colour, 0, 0, 0],
+                    bc_kind_lo: level_bounds.kinds(0),
+                    bc_kind_hi: level_bounds.kinds(1),
+                    bc_value_lo: level_bounds.values(0),
+                    bc_value_hi: level_bounds.values(1)
+                };
+                let offset = ((MAX_SWEEP_COLOURS as usize * index) + colour as usize) * SLOT_STRIDE as usize;
+                bytes[offset..offset + std::mem::size_of::<MultigridUniforms>()].copy_from_slice(uniforms.flatten_u8());
+            }
+        }
+        self.queue.write_buffer(&self.uniforms, 0, &bytes);
+    }
+}
+
+/// A whole storage buffer at binding
+pub(crate) fn storage_entry(binding: u32, buffer: &Buffer) -> BindGroupEntry<'_> {
+    BindGroupEntry {
+        binding: binding,
+        resource: wgpu::BindingResource::Buffer(BufferBinding { buffer: buffer, offset: 0, size: None })
+    }
+}
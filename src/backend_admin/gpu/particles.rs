@@ -0,0 +1,315 @@
+use wgpu::{BindGroup, BindGroupLayout, Buffer, BufferUsages, ComputePass, ComputePipeline, Device, Queue};
+use crate::{
+    backend_admin::gpu::{multigrid::storage_entry, resources::Resources},
+    world::{
+        boundary::{Boundary, BoundaryConditions},
+        brownian_motion::Particles,
+        voxel_grid::{Dims3, P3}}
+};
+
+/// The particles.wgsl entry points, built once by Compute and cloned into each GpuParticles
+#[derive(Clone)]
+pub struct ParticlePipelines {
+    pub bg_layout: BindGroupLayout, // grid, particles, sorted, particle cells, cell counts, cell starts, chunk sums, neighbours
+    pub advance_p: ComputePipeline,
+    pub clear_cells_p: ComputePipeline,
+    pub count_cells_p: ComputePipeline,
+    pub scan_chunks_p: ComputePipeline,
+    pub scan_sums_p: ComputePipeline,
+    pub add_offsets_p: ComputePipeline,
+    pub scatter_p: ComputePipeline,
+    pub count_neighbours_p: ComputePipeline
+}
+
+/// Workgroup sizes and the scan's chunk count, as particles.wgsl
+const PARTICLE_GROUP: u32 = 64;
+const CELL_GROUP: u32 = 256;
+const SCAN_CHUNKS: u32 = 65536;
+const MAX_GROUPS: u32 = 65535; // per dispatch dimension, wider dispatches go 2D
+
+/// Particles in GPU storage buffers, stepped and sorted into cells by particles.wgsl, see world/brownian_motion.rs
+/// The spatial hash is a uniform grid aligned with the voxel grid, one cell per voxel, rebuilt by a counting sort on sort()
+/// into a copy of the particles grouped by cell, which neighbour lookups walk (cpu::particles is the reference)
+/// Particles stay in the order they were uploaded, their ids keying their draws, so steps match Particles::step()
+/// but for the rounding of the normals
+pub struct GpuParticles {
+    device: Device,
+    queue: Queue,
+    pipelines: ParticlePipelines,
+
+    dims: Dims3,
+    count: u32,
+    grid: Buffer,
+    particles: Buffer,
+    sorted: Buffer,
+    neighbours: Buffer,
+    cell_start: Buffer,
+    bg: BindGroup
+}
+
+/// particles.wgsl's Grid
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Grid {
+    dims: [u32; 4], // cells per axis, particle count
+    extent: [f32; 4], // box nm, cutoff nm
+    spacing: [f32; 4], // voxel nm, dt µs
+    seed: [u32; 4], // seed, 0, step lo, step hi
+    periodic: [u32; 4] // per axis, cells per scan chunk
+}
+
+impl Grid {
+    fn flatten_u8(&self) -> &[u8] {
+        let ptr = self as *const _ as *const u8;
+        unsafe {
+            std::slice::from_raw_parts(ptr, std::mem::size_of::<Grid>())
+        }
+    }
+}
+
+/// particles.wgsl's Particle
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct GpuParticle {
+    position: [f32; 3],
+    kind: u32,
+    velocity: [f32; 3],
+    diffusivity: f32,
+    image: [i32; 3],
+    id: u32
+}
+
+impl GpuParticle {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let word = |i: usize| [bytes[4 * i], bytes[4 * i + 1], bytes[4 * i + 2], bytes[4 * i + 3]];
+        let float = |i: usize| f32::from_ne_bytes(word(i));
+        let int = |i: usize| i32::from_ne_bytes(word(i));
+        GpuParticle {
+            position: [float(0), float(1), float(2)],
+            kind: u32::from_ne_bytes(word(3)),
+            velocity: [float(4), float(5), float(6)],
+            diffusivity: float(7),
+            image: [int(8), int(9), int(10)],
+            id: u32::from_ne_bytes(word(11))
+        }
+    }
+}
+
+fn flatten_particles(particles: &[GpuParticle]) -> &[u8] {
+    let ptr = particles.as_ptr() as *const u8;
+    unsafe {
+        std::slice::from_raw_parts(ptr, std::mem::size_of_val(particles))
+    }
+}
+
+/// Workgroups for n invocations of size, 2D once they pass MAX_GROUPS, flattened back by particles.wgsl's linear()
+fn linear_dispatch(n: u32, size: u32) -> [u32; 3] {
+    let groups = n.div_ceil(size).max(1);
+    let x = groups.min(MAX_GROUPS);
+    [x, groups.div_ceil(x), 1]
+}
+
+impl GpuParticles {
+    /// Buffers for particles in a dims grid, uploaded from particles
+    pub fn new(dims: &Dims3, device: &Device, queue: &Queue, pipelines: &ParticlePipelines, particles: &Particles) -> Self {
+        let count = particles.len();
+        assert!(count as u64 <= (MAX_GROUPS * PARTICLE_GROUP) as u64 * MAX_GROUPS as u64, "Too many particles for one dispatch\n");
+        let cells = (dims[0] * dims[1] * dims[2]) as usize;
+
+        let storage = |label: &str, size: usize, usage: BufferUsages| device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: size.max(std::mem::size_of::<GpuParticle>()) as u64, // bindings need room for one element
+            usage: BufferUsages::STORAGE | usage,
+            mapped_at_creation: false
+        });
+        let particle_size = std::mem::size_of::<GpuParticle>() * count;
+        let word = std::mem::size_of::<u32>();
+        let grid = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle grid buffer"),
+            size: std::mem::size_of::<Grid>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+        let particle_buffer = storage("Particles", particle_size, BufferUsages::COPY_DST | BufferUsages::COPY_SRC);
+        let sorted = storage("Sorted particles", particle_size, BufferUsages::COPY_SRC);
+        let particle_cells = storage("Particle cells", word * count, BufferUsages::empty());
+        let cell_counts = storage("Particle cell counts", word * (cells + 1), BufferUsages::empty());
+        let cell_start = storage("Particle cell starts", word * (cells + 1), BufferUsages::COPY_SRC);
+        let chunk_sums = storage("Particle scan chunk sums", word * SCAN_CHUNKS as usize, BufferUsages::empty());
+        let neighbours = storage("Particle neighbours", word * count, BufferUsages::COPY_SRC);
+
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particle bind group"),
+            layout: &pipelines.bg_layout,
+            entries: &[
+            storage_entry(0, &grid),
+            storage_entry(1, &particle_buffer),
+            storage_entry(2, &sorted),
+            storage_entry(3, &particle_cells),
+            storage_entry(4, &cell_counts),
+            storage_entry(5, &cell_start),
+            storage_entry(6, &chunk_sums),
+            storage_entry(7, &neighbours)]
+        });
+
+        let gpu = GpuParticles {
+            device: device.clone(),
+            queue: queue.clone(),
+            pipelines: pipelines.clone(),
+
+            dims: *dims,
+            count: count as u32,
+            grid: grid,
+            particles: particle_buffer,
+            sorted: sorted,
+            neighbours: neighbours,
+            cell_start: cell_start,
+            bg: bg
+        };
+        gpu.upload(particles);
+        gpu
+    }
+
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Overwrites the buffers with particles, which must be as many as they were built for
+    pub fn upload(&self, particles: &Particles) {
+        assert!(particles.len() == self.len(), "Particle count does not match the buffers\n");
+        let packed: Vec<GpuParticle> = (0..particles.len()).map(|i| GpuParticle {
+            position: particles.positions[i],
+            kind: particles.kinds[i],
+            velocity: particles.velocities[i],
+            diffusivity: particles.diffusivity[i],
+            image: particles.images[i],
+            id: i as u32
+        }).collect();
+        if !packed.is_empty() { self.queue.write_buffer(&self.particles, 0, flatten_particles(&packed)); }
+    }
+
+    /// Copies positions, velocities and images back into particles, the CPU copy uploaded from
+    /// Blocks on the readback
+    pub fn download(&self, particles: &mut Particles) {
+        assert!(particles.len() == self.len(), "Particle count does not match the buffers\n");
+        for p in self.read_particles(&self.particles) {
+            let i = p.id as usize;
+            particles.positions[i] = p.position;
+            particles.velocities[i] = p.velocity;
+            particles.images[i] = p.image;
+        }
+    }
+
+    /// One step of timestep µs, as Particles::step(), which it also counts towards particles' elapsed time
+    /// spacing is the voxel spacing in nm, bounds the field's boundary conditions, which decide the walls
+    pub fn step(&self, particles: &mut Particles, timestep: f32, spacing: &P3, bounds: &BoundaryConditions, seed: u32, step: u64) {
+        self.write_grid(spacing, bounds, timestep, 0.0, seed, step);
+        self.submit("Particle step", |pass| self.encode(pass, &self.pipelines.advance_p, linear_dispatch(self.count, PARTICLE_GROUP)));
+        particles.stepped(timestep);
+    }
+
+    /// Rebuilds the spatial hash from the current positions, one counting sort
+    pub fn sort(&self, spacing: &P3, bounds: &BoundaryConditions) {
+        self.write_grid(spacing, bounds, 0.0, 0.0, 0, 0);
+        self.submit("Particle sort", |pass| self.encode_sort(pass));
+    }
+
+    /// Particles within cutoff nm of each particle, itself excluded, in Particles' order, as cpu::particles::neighbour_counts()
+    /// Sorts first, then blocks on the readback
+    pub fn neighbour_counts(&self, spacing: &P3, bounds: &BoundaryConditions, cutoff: f32) -> Vec<u32> {
+        assert!(cutoff > 0.0 && spacing.iter().all(|dx| cutoff <= *dx), "Neighbour cutoff should be > 0.0 and at most the voxel spacing\n");
+        self.write_grid(spacing, bounds, 0.0, cutoff, 0, 0);
+        self.submit("Particle neighbours", |pass| {
+            self.encode_sort(pass);
+            self.encode(pass, &self.pipelines.count_neighbours_p, linear_dispatch(self.count, PARTICLE_GROUP));
+        });
+        let sorted = self.read_particles(&self.sorted);
+        let counts = self.read_words(&self.neighbours, self.len());
+        let mut by_id = vec![0; self.len()];
+        for (p, count) in sorted.iter().zip(counts) {
+            by_id[p.id as usize] = count;
+        }
+        by_id
+    }
+
+    /// The last sort's cell starts, cells + 1 entries, as cpu::particles::CellList's
+    pub fn cell_start(&self) -> Vec<u32> {
+        self.read_words(&self.cell_start, (self.dims[0] * self.dims[1] * self.dims[2]) as usize + 1)
+    }
+
+    /// The last sort's particle indices, cell by cell
+    pub fn sorted_order(&self) -> Vec<u32> {
+        self.read_particles(&self.sorted).iter().map(|p| p.id).collect()
+    }
+
+    fn write_grid(&self, spacing: &P3, bounds: &BoundaryConditions, timestep: f32, cutoff: f32, seed: u32, step: u64) {
+        let cells = self.dims[0] * self.dims[1] * self.dims[2];
+        let periodic = |axis: usize| (bounds.faces[axis][0] == Boundary::Periodic) as u32;
+        let grid = Grid {
+            dims: [self.dims[0], self.dims[1], self.dims[2], self.count],
+            extent: [self.dims[0] as f32 * spacing[0], self.dims[1] as f32 * spacing[1], self.dims[2] as f32 * spacing[2], cutoff],
+            spacing: [spacing[0], spacing[1], spacing[2], timestep],
+            seed: [seed, 0, step as u32, (step >> 32) as u32],
+            periodic: [periodic(0), periodic(1), periodic(2), (cells + 1).div_ceil(SCAN_CHUNKS)]
+        };
+        self.queue.write_buffer(&self.grid, 0, grid.flatten_u8());
+    }
+
+    /// The counting sort's seven dispatches, see particles.wgsl
+    fn encode_sort(&self, pass: &mut ComputePass) {
+        let cells = self.dims[0] * self.dims[1] * self.dims[2] + 1;
+        let per_particle = linear_dispatch(self.count, PARTICLE_GROUP);
+        let per_cell = linear_dispatch(cells, CELL_GROUP);
+        self.encode(pass, &self.pipelines.clear_cells_p, per_cell);
+        self.encode(pass, &self.pipelines.count_cells_p, per_particle);
+        self.encode(pass, &self.pipelines.scan_chunks_p, linear_dispatch(SCAN_CHUNKS, CELL_GROUP));
+        self.encode(pass, &self.pipelines.scan_sums_p, [1, 1, 1]);
+        self.encode(pass, &self.pipelines.add_offsets_p, per_cell);
+        self.encode(pass, &self.pipelines.clear_cells_p, per_cell);
+        self.encode(pass, &self.pipelines.scatter_p, per_particle);
+    }
+
+    fn encode(&self, pass: &mut ComputePass, pipeline: &ComputePipeline, dispatch: [u32; 3]) {
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &self.bg, &[]);
+        let [x, y, z] = dispatch;
+        pass.dispatch_workgroups(x, y, z);
+    }
+
+    fn submit(&self, label: &str, encode: impl FnOnce(&mut ComputePass)) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some(label)
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some(label),
+                timestamp_writes: None
+            });
+            encode(&mut compute_pass);
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    fn read_particles(&self, buffer: &Buffer) -> Vec<GpuParticle> {
+        self.read_bytes(buffer, std::mem::size_of::<GpuParticle>() * self.len())
+            .chunks_exact(std::mem::size_of::<GpuParticle>())
+            .map(GpuParticle::from_bytes)
+            .collect()
+    }
+
+    fn read_words(&self, buffer: &Buffer, len: usize) -> Vec<u32> {
+        self.read_bytes(buffer, std::mem::size_of::<u32>() * len)
+            .chunks_exact(std::mem::size_of::<u32>())
+            .map(|bytes| u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect()
+    }
+
+    /// The first size bytes of buffer, see Resources::read_bytes()
+    fn read_bytes(&self, buffer: &Buffer, size: usize) -> Vec<u8> {
+        Resources::read_bytes(&self.device, &self.queue, buffer, 0, size as u64)
+    }
+}
@@ -1,16 +1,21 @@
 use crate::{backend_admin::{
     bridge::Bridge, gpu::gfx_context::GraphicsContext},
-    world::{voxel_grid::Dims3, world::{BoundingBox, World}
+    world::{boundary::BoundaryConditions, diffusion::Diffusion, field::VoxelField, voxel_grid::Dims3, world::{BoundingBox, World}
     }};
-use wgpu::{Buffer, BufferUsages, Extent3d, Sampler, Texture, TextureDescriptor, TextureUsages, TextureView, TextureViewDescriptor};
+use wgpu::{BindGroupEntry, Buffer, BufferBinding, BufferUsages, Device, Queue, Extent3d, Sampler, Texture, TextureDescriptor, TextureUsages, TextureView, TextureViewDescriptor};
 use wgpu::util::DeviceExt;
 use winit::dpi::PhysicalSize;
+use std::{error::Error, num::NonZero};
 
 
+/// The voxel buffers are planar: one plane per species of World::species, species 0's at offset 0 where the raymarch pass reads it
+/// Planes are plane_stride apart, a field rounded up to the device's storage offset alignment, so each binds on its own
 pub struct Resources {
     pub sampler: Sampler,
     pub ping_voxel_buffer: Buffer,
     pub pong_voxel_buffer: Buffer,
+    species_count: usize,
+    plane_stride: u64,
     storage_texture: Texture,
     pub texture_view: TextureView,
     pub uniforms: Buffer
@@ -23,19 +28,10 @@ impl Resources {
              PhysicalSize::new(gfx_ctx.surface_config.width, gfx_ctx.surface_config.height) }
              else { gfx_ctx.update_surface_config() };
 
-        let ping_voxels = gfx_ctx.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Compute store a"),
-            size:  (std::mem::size_of::<f32>() as u32 * dims[0] * dims[1] * dims[2]) as u64,
-            usage: BufferUsages::STORAGE,
-            mapped_at_creation: false 
-        });
-
-        let pong_voxels = gfx_ctx.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Compute store b"),
-            size:  (std::mem::size_of::<f32>() as u32 * dims[0] * dims[1] * dims[2]) as u64,
-            usage: BufferUsages::STORAGE,
-            mapped_at_creation: false
-        }); 
+        let species_count = world.species.len();
+        let plane_stride = Self::plane_size(&gfx_ctx.device, dims);
+        let (ping_voxels, pong_voxels) = Self::voxel_buffers(&gfx_ctx.device, plane_stride * species_count as u64)
+            .expect("Voxel buffers exceed the device's limits");
 
 
         let uniforms = Uniforms {
@@ -49,7 +45,12 @@ impl Resources {
             right: [world.camera.r[0], world.camera.r[1], world.camera.r[2], 0.0 as f32],
             timestep: [0.0 as f32, 0.0 as f32, 0.0 as f32, 0.0 as f32],
             seed: [bridge.rand_seed, 0, 0, 0],
-            flags: [1, 0, 0, 0]
+            flags: [1, 0, 0, 0],
+            bc_kind_lo: [0, 0, 0, 0], // boundaries only matter to the laplacian pass, see Uniforms::simulation()
+            bc_kind_hi: [0, 0, 0, 0],
+            bc_value_lo: [0.0, 0.0, 0.0, 0.0],
+            bc_value_hi: [0.0, 0.0, 0.0, 0.0],
+            diffusion: [0.0, 0.0, 0.0, 0.0]
         };
         
         let uniforms = gfx_ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -105,6 +106,8 @@ impl Resources {
             sampler: sampler,
             ping_voxel_buffer: ping_voxels,
             pong_voxel_buffer: pong_voxels,
+            species_count: species_count,
+            plane_stride: plane_stride,
             storage_texture: storage_texture,
             texture_view: texture_view,
             uniforms: uniforms
@@ -152,7 +155,12 @@ impl Resources {
             right: [world.camera.r[0], world.camera.r[1], world.camera.r[2], 0.0 as f32],
             timestep: [0.0 as f32, 0.0 as f32, 0.0 as f32, 0.0 as f32],
             seed: [bridge.rand_seed, 0, 0, 0],
-            flags: [1, 0, 0, 0]
+            flags: [1, 0, 0, 0],
+            bc_kind_lo: [0, 0, 0, 0],
+            bc_kind_hi: [0, 0, 0, 0],
+            bc_value_lo: [0.0, 0.0, 0.0, 0.0],
+            bc_value_hi: [0.0, 0.0, 0.0, 0.0],
+            diffusion: [0.0, 0.0, 0.0, 0.0]
         };
 
         self.uniforms = gfx_ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -165,10 +173,149 @@ impl Resources {
 
     }
 
+    /// Bytes of one field, in u64 so grids of 2^30 voxels and more reach voxel_buffers()' limit check instead of wrapping
+    fn field_size(dims: &Dims3) -> u64 {
+        std::mem::size_of::<f32>() as u64 * dims[0] as u64 * dims[1] as u64 * dims[2] as u64
+    }
+
+    /// Bytes between species planes: one field, rounded up so every plane is a valid storage binding offset
+    fn plane_size(device: &Device, dims: &Dims3) -> u64 {
+        let field = Self::field_size(dims);
+        field.next_multiple_of(device.limits().min_storage_buffer_offset_alignment as u64)
+    }
+
+    /// Planar ping/pong pair of size bytes each, zeroed
+    fn voxel_buffers(device: &Device, size: u64) -> Result<(Buffer, Buffer), Box<dyn Error>> {
+        Self::check_voxel_buffers(device, size)?;
+        let buffer = |label: &str| device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: size,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST, // COPY for SimulationBackend field reads/writes
+            mapped_at_creation: false
+        });
+        Ok((buffer("Compute store a"), buffer("Compute store b")))
+    }
+
+    fn check_voxel_buffers(device: &Device, size: u64) -> Result<(), Box<dyn Error>> {
+        if size > device.limits().max_buffer_size {
+            return Err(format!("{} bytes of voxel buffers exceed the device's limit of {}, try fewer species or a smaller grid", size, device.limits().max_buffer_size).into());
+        }
+        Ok(())
+    }
+
+    /// Whether set_species_count() could hold count species on this device, without reallocating anything
+    pub fn check_species_count(&self, gfx_ctx: &GraphicsContext, count: usize) -> Result<(), Box<dyn Error>> {
+        Self::check_voxel_buffers(&gfx_ctx.device, self.plane_stride * count as u64)
+    }
+
+    pub fn species_count(&self) -> usize {
+        self.species_count
+    }
+
+    /// Bytes from one species' plane to the next, for passes that bind the whole buffers (the reaction network)
+    pub fn plane_stride(&self) -> u64 {
+        self.plane_stride
+    }
+
+    /// A species' (ping, pong) planes
+    pub fn species_planes(&self, species: usize, dims: &Dims3) -> (FieldSlice, FieldSlice) {
+        assert!(species < self.species_count, "Species {} has no voxel planes, see Resources::set_species_count\n", species);
+        let size = Self::field_size(dims);
+        let plane = |buffer: &Buffer| FieldSlice::new(buffer, species as u64 * self.plane_stride, size);
+        (plane(&self.ping_voxel_buffer), plane(&self.pong_voxel_buffer))
+    }
+
+    /// The whole (ping, pong) buffers, every species' plane, as the reaction network pass binds them
+    pub fn all_planes(&self) -> (FieldSlice, FieldSlice) {
+        (FieldSlice::whole(&self.ping_voxel_buffer), FieldSlice::whole(&self.pong_voxel_buffer))
+    }
+
+    /// Reallocates the voxel buffers for count species, keeping the planes both layouts share and zeroing new ones
+    /// Anything bound to the old buffers must be rebound: Compute::on_resize() and SimulationBackend::set_species()
+    pub fn set_species_count(&mut self, gfx_ctx: &GraphicsContext, count: usize) -> Result<(), Box<dyn Error>> {
+        if count == self.species_count { return Ok(()); }
+        let (ping, pong) = Self::voxel_buffers(&gfx_ctx.device, self.plane_stride * count as u64)?;
+        let mut encoder = gfx_ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Copy species planes")
+        });
+        let kept = self.plane_stride * count.min(self.species_count) as u64;
+        encoder.copy_buffer_to_buffer(&self.ping_voxel_buffer, 0, &ping, 0, kept);
+        encoder.copy_buffer_to_buffer(&self.pong_voxel_buffer, 0, &pong, 0, kept);
+        gfx_ctx.queue.submit(std::iter::once(encoder.finish()));
+
+        self.ping_voxel_buffer = ping;
+        self.pong_voxel_buffer = pong;
+        self.species_count = count;
+        Ok(())
+    }
+
+    /// GPU -> CPU readback of the current voxel field
+    /// read_ping selects ping or pong exactly as the raymarch pass does (State::read_ping)
+    pub fn read_voxels(&self, gfx_ctx: &GraphicsContext, species: usize, read_ping: bool, dims: &Dims3) -> VoxelField {
+        let (ping, pong) = self.species_planes(species, dims);
+        VoxelField::new(*dims, Self::read_slice(&gfx_ctx.device, &gfx_ctx.queue, if read_ping { &ping } else { &pong }))
+    }
+
+    /// CPU -> GPU upload of a caller-provided field into a species' ping plane
+    /// After this the raymarch and laplacian passes must read ping (read_ping true)
+    pub fn write_voxels(&self, gfx_ctx: &GraphicsContext, species: usize, field: &[f32], dims: &Dims3) {
+        assert!(field.len() == (dims[0] * dims[1] * dims[2]) as usize, "Field length does not match dims\n");
+        Self::write_slice(&gfx_ctx.queue, &self.species_planes(species, dims).0, field);
+    }
+
+    /// Queues a write of field into a species plane, applied before the next submit
+    pub fn write_slice(queue: &Queue, slice: &FieldSlice, field: &[f32]) {
+        let bytes: Vec<u8> = field.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        queue.write_buffer(&slice.buffer, slice.offset, &bytes);
+    }
+
+    /// Copies a voxel buffer into a mappable staging buffer and blocks until it is mapped and read
+    /// Takes raw handles so GpuBackend can use it without owning Resources
+    pub fn read_buffer(device: &Device, queue: &Queue, buffer: &Buffer, dims: &Dims3) -> Vec<f32> {
+        let size = Self::field_size(dims);
+        Self::read_slice(device, queue, &FieldSlice::new(buffer, 0, size))
+    }
+
+    /// read_buffer() of a species plane
+    pub fn read_slice(device: &Device, queue: &Queue, slice: &FieldSlice) -> Vec<f32> {
+        Self::read_bytes(device, queue, &slice.buffer, slice.offset, slice.size)
+            .chunks_exact(std::mem::size_of::<f32>())
+            .map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect()
+    }
+
+    /// size bytes of buffer from offset, copied into a mappable staging buffer and read back blocking
+    /// Untyped, for buffers that don't hold f32 voxels (GpuParticles' particles and cell lists)
+    pub fn read_bytes(device: &Device, queue: &Queue, buffer: &Buffer, offset: u64, size: u64) -> Vec<u8> {
+        if size == 0 { return Vec::new(); }
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback staging buffer"),
+            size: size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback")
+        });
+        encoder.copy_buffer_to_buffer(buffer, offset, &staging, 0, size);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = staging.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| {
+            result.expect("Failed to map readback staging buffer\n");
+        });
+        device.poll(wgpu::PollType::Wait).expect("Device lost during readback\n");
+
+        let bytes = slice.get_mapped_range().to_vec();
+        staging.unmap();
+        bytes
+    }
+
     pub fn uniforms_refresh(&mut self, 
         gfx_ctx: &GraphicsContext, read_ping: &bool, 
         duration: f32, bbox: BoundingBox, dims: &Dims3, 
-        world: &World) {
+        world: &World, bridge: &Bridge) {
         if gfx_ctx.surface_configured == true {
 
             let uniforms = Uniforms {
@@ -181,18 +328,18 @@ impl Resources {
                 up: [world.camera.u[0], world.camera.u[1], world.camera.u[2], 0.0 as f32],
                 right: [world.camera.r[0], world.camera.r[1], world.camera.r[2], world.right_sf],
                 timestep: [duration, 0.0, 0.0, 0.0],
-                seed: [0, 0, 0, 0 ], // could later reintroduce seed here for hot sim resizing 
-                flags: [*read_ping as u32, 0, 0, 0]
+                seed: [bridge.rand_seed, 0, 0, 0], // kept current so the raymarch uniforms never disagree with the run's seed
+                flags: [*read_ping as u32, 0, 0, 0],
+                bc_kind_lo: [0, 0, 0, 0],
+                bc_kind_hi: [0, 0, 0, 0],
+                bc_value_lo: [0.0, 0.0, 0.0, 0.0],
+                bc_value_hi: [0.0, 0.0, 0.0, 0.0],
+                diffusion: [0.0, 0.0, 0.0, 0.0]
             };
 
             let data = uniforms.flatten_u8();
 
-            self.uniforms = gfx_ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Uniform buffer"),
-            contents: data,
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-            });
-
+            // write into the buffer Compute::bg already binds (a new buffer here would never reach the shaders)
             gfx_ctx.queue.write_buffer(&self.uniforms, 0, data);
         }
         else { panic!("Tried to update uniforms with outdated graphics context\n") }
@@ -203,6 +350,38 @@ impl Resources {
 
 
 
+/// A byte range of a storage buffer holding one field, e.g. a species' plane of the voxel buffers
+/// Clones share the buffer (wgpu handles are reference counted)
+#[derive(Clone)]
+pub struct FieldSlice {
+    pub buffer: Buffer,
+    pub offset: u64,
+    pub size: u64
+}
+
+impl FieldSlice {
+    pub fn new(buffer: &Buffer, offset: u64, size: u64) -> Self {
+        FieldSlice {
+            buffer: buffer.clone(),
+            offset: offset,
+            size: size
+        }
+    }
+
+    /// The whole of buffer
+    pub fn whole(buffer: &Buffer) -> Self {
+        Self::new(buffer, 0, buffer.size())
+    }
+
+    /// The range at binding, shaders index it from 0 whatever its offset
+    pub fn entry(&self, binding: u32) -> BindGroupEntry<'_> {
+        BindGroupEntry {
+            binding: binding,
+            resource: wgpu::BindingResource::Buffer(BufferBinding { buffer: &self.buffer, offset: self.offset, size: NonZero::new(self.size) })
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct Uniforms {
@@ -216,13 +395,80 @@ pub struct Uniforms {
     centre: [f32; 4],
     up: [f32; 4], // [2]< padding
     right: [f32; 4], // [2]< padding
-    timestep: [f32; 4], // only [0]
-    seed: [u32; 4], // only [0]
-    flags: [u32; 4]
+    timestep: [f32; 4], // [0] dt, [1] theta for the implicit passes, or well depth and [2] stabiliser for Cahn-Hilliard
+    seed: [u32; 4], // [0], and [1..3] the noise pass's counter
+    flags: [u32; 4], // [0] read_ping, [1] implicit sweep colour, or species and [2] plane stride for the network pass
+    bc_kind_lo: [u32; 4], // Boundary::kind() per axis at index 0, [3] unused
+    bc_kind_hi: [u32; 4], // ... and at index dims - 1
+    bc_value_lo: [f32; 4], // Dirichlet values, same layout
+    bc_value_hi: [f32; 4],
+    diffusion: [f32; 4] // Diffusion::stencil_weights(), D then 1/dx^2 per axis
 
 }
 
 impl Uniforms {
+    /// Uniforms for the init and laplacian passes, which only read dims, timestep, seed, flags, boundaries and diffusion
+    /// Camera fields are left zeroed
+    pub fn simulation(dims: &Dims3, timestep: f32, seed: u32, read_ping: bool, bounds: &BoundaryConditions, diffusion: &Diffusion) -> Self {
+        Uniforms {
+            window_dims: [0, 0, 0, 0],
+            dims: [dims[0], dims[1], dims[2], dims[0] * dims[1]],
+            bounding_box: [0, 0, 0, 0],
+            cam_pos: [0.0, 0.0, 0.0, 0.0],
+            forward: [0.0, 0.0, 0.0, 0.0],
+            centre: [0.0, 0.0, 0.0, 0.0],
+            up: [0.0, 0.0, 0.0, 0.0],
+            right: [0.0, 0.0, 0.0, 0.0],
+            timestep: [timestep, 0.0, 0.0, 0.0],
+            seed: [seed, 0, 0, 0],
+            flags: [read_ping as u32, 0, 0, 0],
+            bc_kind_lo: bounds.kinds(0),
+            bc_kind_hi: bounds.kinds(1),
+            bc_value_lo: bounds.values(0),
+            bc_value_hi: bounds.values(1),
+            diffusion: diffusion.stencil_weights()
+        }
+    }
+
+    /// Simulation uniforms for the implicit passes: theta and which colour (0 red, 1 black, 2 see integrator::sweep_colour) a sweep updates
+    pub fn with_implicit(mut self, theta: f32, colour: u32) -> Self {
+        self.timestep[1] = theta;
+        self.flags[1] = colour;
+        self
+    }
+
+    /// Simulation uniforms for the Cahn-Hilliard rhs pass, whose diffusion carries the mobility in place of D
+    pub fn with_cahn_hilliard(mut self, well_depth: f32, stabiliser: f32) -> Self {
+        self.timestep[1] = well_depth;
+        self.timestep[2] = stabiliser;
+        self
+    }
+
+    /// Simulation uniforms for the init pass: the species whose draws it counts, see InitialCondition::field()
+    pub fn with_init(mut self, species: usize) -> Self {
+        self.seed[1] = species as u32;
+        self
+    }
+
+    /// Simulation uniforms for the reaction network pass: how many species planes it updates, plane_stride apart in f32s
+    pub fn with_network(mut self, species_count: usize, plane_stride: u64) -> Self {
+        self.flags[1] = species_count as u32;
+        self.flags[2] = (plane_stride / std::mem::size_of::<f32>() as u64) as u32;
+        self
+    }
+
+    /// Simulation uniforms for the noise pass: the counter the step's normals are drawn at and their amplitudes
+    pub fn with_noise(mut self, step: u64, substep: u32, conserved: bool, amplitudes: &[f32; 3]) -> Self {
+        self.seed[1] = substep;
+        self.seed[2] = step as u32;
+        self.seed[3] = (step >> 32) as u32;
+        self.flags[1] = conserved as u32;
+        self.timestep[1] = amplitudes[0];
+        self.timestep[2] = amplitudes[1];
+        self.timestep[3] = amplitudes[2];
+        self
+    }
+
     pub fn flatten_u8(&self) -> &[u8] {
         let ptr = self as *const _ as *const u8;
 
@@ -0,0 +1,173 @@
+use wgpu::{BindGroup, BindGroupEntry, BindGroupLayout, Buffer, BufferBinding, BufferUsages, ComputePass, ComputePipeline, Device, Queue};
+use std::num::NonZero;
+use crate::{
+    backend_admin::{
+        bridge::{self, DispatchDims},
+        gpu::{multigrid::storage_entry, resources::{FieldSlice, Resources, Uniforms}}},
+    world::{
+        boundary::BoundaryConditions,
+        diffusion::Diffusion,
+        vesicle::{Vesicle, VesicleSums},
+        voxel_grid::{Dims3, P3}}
+};
+
+/// The vesicle.wgsl entry points, built once by Compute and cloned into each GpuVesicle
+#[derive(Clone)]
+pub struct VesiclePipelines {
+    pub bg_layout: BindGroupLayout, // uniforms, ping, pong, potential, partials, membrane
+    pub curvature_p: ComputePipeline,
+    pub bending_p: ComputePipeline,
+    pub relax_p: ComputePipeline
+}
+
+/// Sums per workgroup in partials, see vesicle.wgsl
+const PARTIAL_STRIDE: usize = 8;
+
+/// GPU counterpart of cpu::vesicle::vesicle_step, see world/vesicle.rs
+/// Binds the simulation's ping/pong pair directly, the flags pick which is current as in every other pass
+/// Owns the potential buffer and the per-workgroup partials, allocated once for the grid's dims
+pub struct GpuVesicle {
+    device: Device,
+    queue: Queue,
+    pipelines: VesiclePipelines,
+
+    dims: Dims3,
+    dispatch: DispatchDims,
+    uniforms: Buffer,
+    membrane: Buffer,
+    partials: Buffer,
+    partial_count: u32,
+    bg: BindGroup
+}
+
+/// vesicle.wgsl's Membrane
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Membrane {
+    shape: [f32; 4], // rigidity, width, spontaneous curvature, 0
+    multipliers: [f32; 4] // λ_V, λ_A, 0, 0
+}
+
+impl Membrane {
+    fn new(model: &Vesicle, multipliers: &[f32; 2]) -> Self {
+        Membrane {
+            shape: [model.rigidity, model.width, model.spontaneous_curvature, 0.0],
+            multipliers: [multipliers[0], multipliers[1], 0.0, 0.0]
+        }
+    }
+
+    fn flatten_u8(&self) -> &[u8] {
+        let ptr = self as *const _ as *const u8;
+        unsafe {
+            std::slice::from_raw_parts(ptr, std::mem::size_of::<Membrane>())
+        }
+    }
+}
+
+impl GpuVesicle {
+    pub fn new(dims: &Dims3, device: &Device, queue: &Queue, pipelines: &VesiclePipelines, ping: &FieldSlice, pong: &FieldSlice) -> Self {
+        let dispatch = bridge::voxel_dispatch(dims);
+        let partial_count = dispatch.iter().product::<u32>();
+
+        let storage = |label: &str, len: usize, usage: BufferUsages| device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: (std::mem::size_of::<f32>() * len) as u64,
+            usage: BufferUsages::STORAGE | usage,
+            mapped_at_creation: false
+        });
+        let uniform = |label: &str, size: usize| device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: size as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+        let potential = storage("Vesicle potential", (dims[0] * dims[1] * dims[2]) as usize, BufferUsages::empty());
+        let partials = storage("Vesicle partials", PARTIAL_STRIDE * partial_count as usize, BufferUsages::COPY_SRC);
+        let uniforms = uniform("Vesicle uniform buffer", std::mem::size_of::<Uniforms>());
+        let membrane = uniform("Vesicle membrane buffer", std::mem::size_of::<Membrane>());
+
+        let bg = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Vesicle bind group"),
+            layout: &pipelines.bg_layout,
+            entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(BufferBinding {
+                    buffer: &uniforms,
+                    offset: 0,
+                    size: NonZero::new(std::mem::size_of::<Uniforms>() as u64)
+                }),
+            },
+            ping.entry(1),
+            pong.entry(2),
+            storage_entry(3, &potential),
+            storage_entry(4, &partials),
+            storage_entry(5, &membrane)]
+        });
+
+        GpuVesicle {
+            device: device.clone(),
+            queue: queue.clone(),
+            pipelines: pipelines.clone(),
+
+            dims: *dims,
+            dispatch: dispatch,
+            uniforms: uniforms,
+            membrane: membrane,
+            partials: partials,
+            partial_count: partial_count,
+            bg: bg
+        }
+    }
+
+    /// One step from the current field (ping when read_ping), leaving φ' in the other buffer
+    /// targets as cpu::vesicle::vesicle_step, returns the multipliers the step used
+    /// Blocks on one readback of the partials, between the bending and relax passes
+    pub fn step(&self, read_ping: bool, timestep: f32, bounds: &BoundaryConditions, spacing: &P3, model: &Vesicle, targets: &mut Option<[f64; 2]>) -> [f32; 2] {
+        let uniforms = Uniforms::simulation(&self.dims, timestep, 0, read_ping, &bounds.zero_flux(), &Diffusion::new(model.mobility, *spacing));
+        self.queue.write_buffer(&self.uniforms, 0, uniforms.flatten_u8());
+        self.queue.write_buffer(&self.membrane, 0, Membrane::new(model, &[0.0, 0.0]).flatten_u8());
+        self.submit("Vesicle potential", |pass| {
+            self.encode(pass, &self.pipelines.curvature_p);
+            self.encode(pass, &self.pipelines.bending_p);
+        });
+
+        let sums = self.sum_partials();
+        let targets = *targets.get_or_insert([sums.volume, sums.area]);
+        let multipliers = model.multipliers(&sums, &targets, (self.dims[0] * self.dims[1] * self.dims[2]) as usize, timestep);
+        self.queue.write_buffer(&self.membrane, 0, Membrane::new(model, &multipliers).flatten_u8());
+        self.submit("Vesicle relax", |pass| self.encode(pass, &self.pipelines.relax_p));
+        multipliers
+    }
+
+    fn encode(&self, pass: &mut ComputePass, pipeline: &ComputePipeline) {
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &self.bg, &[]);
+        let [x, y, z] = self.dispatch;
+        pass.dispatch_workgroups(x, y, z);
+    }
+
+    fn submit(&self, label: &str, encode: impl FnOnce(&mut ComputePass)) {
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some(label)
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some(label),
+                timestamp_writes: None
+            });
+            encode(&mut compute_pass);
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// The bending pass's per-workgroup partials, totalled in f64 as the CPU sums
+    fn sum_partials(&self) -> VesicleSums {
+        let partials = Resources::read_buffer(&self.device, &self.queue, &self.partials, &[PARTIAL_STRIDE as u32 * self.partial_count, 1, 1]);
+        let mut sums = VesicleSums::default();
+        for group in partials.chunks(PARTIAL_STRIDE) {
+            sums.add(group);
+        }
+        sums
+    }
+}
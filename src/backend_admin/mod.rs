@@ -1,4 +1,7 @@
 pub mod app_dispatcher;
 pub mod state;
+pub mod simulation;
+pub mod stepper;
 pub mod gpu;
+pub mod cpu;
 pub mod bridge;
\ No newline at end of file
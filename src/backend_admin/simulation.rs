@@ -0,0 +1,149 @@
+use crate::{
+    backend_admin::gpu::{gfx_context::GraphicsContext, resources::Resources},
+    world::{boundary::BoundaryConditions, cahn_hilliard::CahnHilliard, diffusion::Diffusion, field::VoxelField, integrator::Integrator, multigrid::ConvergenceHistory, network::ReactionNetwork, noise::ThermalNoise, rdme::Rdme, reaction::Reaction, species::SpeciesRegistry, stability::StabilityLimit, vesicle::Vesicle, voxel_grid::Dims3}
+};
+
+/// Everything the app loop needs from a diffusion simulation, whatever runs it
+/// Implemented by GpuBackend (wgpu compute passes, see gpu/backend.rs)
+/// and CpuBackend (multi-threaded laplacian, see cpu/backend.rs),
+/// and over whole molecules by RdmeBackend (stochastic hops and reactions, see cpu/rdme_backend.rs)
+/// Fields are flat f32 voxel arrays indexed x + y * dims[0] + z * dims[0] * dims[1]
+/// Every backend holds the species of a SpeciesRegistry (see world/species.rs), species 0 alone until set_species(),
+/// all sharing one ping/pong parity
+pub trait SimulationBackend {
+    fn dims(&self) -> Dims3;
+
+    /// Seeds every species from its InitialCondition, species 0 and 1 from Reaction::initial_fields() when reacting
+    /// With Cahn-Hilliard set, species 0 is seeded from CahnHilliard::initial_field() instead,
+    /// with a vesicle from Vesicle::initial_field()
+    fn init(&mut self, seed: u32);
+
+    /// Advances the field by one step of length timestep, explicit Euler or implicit as set by set_integrator(),
+    /// then by one explicit reaction step when set_reaction() was given one, and one network step when set_network() was
+    /// With Cahn-Hilliard set, species 0 takes a Cahn-Hilliard step in place of diffusing, with a vesicle a vesicle step
+    /// With noise set, species 0 then takes its thermal kick, counted by the step index (whose sub-step each step() advances)
+    fn step(&mut self, timestep: f32);
+
+    /// Copies the current field out of the backend (GPU: blocking readback)
+    fn read_field(&mut self) -> VoxelField {
+        self.read_species(0)
+    }
+
+    /// Copies a species' current field out of the backend
+    fn read_species(&mut self, species: usize) -> VoxelField;
+
+    /// Overwrites a species' current field, after restore_field() has set the parity
+    fn write_species(&mut self, species: usize, field: &[f32]);
+
+    /// Species in the registry last given to set_species()
+    fn species_count(&self) -> usize;
+
+    /// Stores registry's species from the next step on, each diffusing with its own D and boundaries where it has them
+    /// Fields are kept by index, species past the old count start zeroed until init() or write_species()
+    /// A network is re-indexed by species name, and dropped if the registry lacks any of its species
+    /// GPU: the fields live in resources' voxel planes, so call after Resources::set_species_count()
+    fn set_species(&mut self, registry: &SpeciesRegistry, resources: &Resources);
+
+    /// Overwrites the current field, length must match dims
+    fn write_field(&mut self, field: &[f32]) {
+        self.restore_field(field, true);
+    }
+
+    /// Overwrites ping (read_ping true) or pong (false) and makes it the current field
+    /// Lets checkpoints restore ping/pong parity exactly
+    fn restore_field(&mut self, field: &[f32], read_ping: bool);
+
+    /// True when ping holds the current field
+    fn read_ping(&self) -> bool;
+
+    /// Boundary conditions used by every following step() for species without their own, can change between steps
+    fn set_boundaries(&mut self, bounds: &BoundaryConditions);
+
+    /// Diffusion coefficient and voxel spacing used by every following step(), D for species without their own
+    /// Callers keep timesteps under Diffusion::stable_timestep(), see world::stability for sub-stepping
+    fn set_diffusion(&mut self, diffusion: &Diffusion);
+
+    /// Time integration used by every following step(), explicit unless set
+    fn set_integrator(&mut self, integrator: &Integrator);
+
+    /// The reaction coupling species 0 and 1 from the next step on, or none with None
+    /// The registry must hold a species 1 (see SpeciesRegistry::with_reaction()) for as long as a reaction is set
+    fn set_reaction(&mut self, reaction: Option<&Reaction>);
+
+    /// The mass-action network every species reacts by from the next step on, or none with None, see world/network.rs
+    /// network must be indexed as the registry last given to set_species(), see ReactionNetwork::resolve()
+    fn set_network(&mut self, network: Option<&ReactionNetwork>);
+
+    /// Cahn-Hilliard dynamics for species 0 from the next step on, or plain diffusion again with None
+    fn set_cahn_hilliard(&mut self, model: Option<&CahnHilliard>);
+
+    /// Phase-field vesicle dynamics for species 0 from the next step on, or plain diffusion again with None
+    /// Cahn-Hilliard takes precedence while both are set
+    /// The constraint targets are taken from the field at the next step, and again after init(), restore_field() or write_species(0, ..)
+    fn set_vesicle(&mut self, model: Option<&Vesicle>);
+
+    /// Thermal noise for species 0 from the next step on, or none with None, see world/noise.rs
+    /// seed keys its random numbers, Bridge::rand_seed in the app
+    fn set_noise(&mut self, noise: Option<&ThermalNoise>, seed: u32);
+
+    /// Index the next step()'s noise is drawn at: the clock's step and the sub-step within it
+    /// Each step() advances substep by one, so callers set it once per clock step
+    fn set_step_index(&mut self, step: u64, substep: u32);
+
+    /// The largest step() this backend would take with these species and diffusion whatever the integrator,
+    /// None unless it has one of its own, added to the operators' limits so longer steps are sub-stepped, see world::stability
+    /// Takes the settings rather than reading its own so they can be checked before they are set
+    fn stability_limit(&self, _species: &SpeciesRegistry, _diffusion: &Diffusion) -> Option<StabilityLimit> {
+        None
+    }
+
+    /// Notices about settings the backend ignores, queued by its setters since the last call, for the caller to print once
+    fn take_notices(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Residual history of the last step's multigrid solve, None until an implicit multigrid or Cahn-Hilliard step has run
+    fn solver_history(&self) -> Option<&ConvergenceHistory>;
+
+    /// Makes a species' current field visible to the raymarch pass through its planes of resources' voxel buffers,
+    /// those Compute::bind_species() binds
+    /// Returns the read_ping flag the raymarch pass should use
+    fn sync_for_render(&mut self, resources: &Resources, gfx_ctx: &GraphicsContext, species: usize) -> bool;
+}
+
+/// Which SimulationBackend to build at startup
+/// Auto picks the CPU backend when the only adapter is a software one
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum BackendKind {
+    Gpu,
+    Cpu,
+    Rdme(Rdme), // molecule counts on the CPU, see world/rdme.rs
+    #[default]
+    Auto
+}
+
+impl BackendKind {
+    /// Parses `--backend gpu|cpu|rdme[:<Ω>]|auto` from the command line, Auto without one
+    /// Errs on a kind it doesn't know or a malformed Ω, for the caller to fall back to Auto
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        match args.iter().position(|a| a == "--backend").and_then(|pos| args.get(pos + 1)) {
+            Some(kind) if kind == "gpu" => Ok(BackendKind::Gpu),
+            Some(kind) if kind == "cpu" => Ok(BackendKind::Cpu),
+            Some(kind) if kind == "auto" => Ok(BackendKind::Auto),
+            Some(kind) if kind.starts_with("rdme") => Rdme::parse(kind).map(BackendKind::Rdme),
+            Some(kind) => Err(format!("Unknown backend '{}'", kind)),
+            None => Ok(BackendKind::Auto)
+        }
+    }
+
+    /// Resolves Auto against the adapter the GraphicsContext ended up with
+    pub fn resolve(self, gfx_ctx: &GraphicsContext) -> Self {
+        match self {
+            BackendKind::Auto => {
+                if gfx_ctx.adapter_info().device_type == wgpu::DeviceType::Cpu { BackendKind::Cpu }
+                else { BackendKind::Gpu }
+            },
+            kind => kind
+        }
+    }
+}
@@ -1,16 +1,37 @@
 use winit::{dpi::{PhysicalPosition, PhysicalSize}, window::Window};
-use std::{sync::Arc};
+use std::{path::Path, sync::Arc};
 use crate::{
     backend_admin::{
         bridge::Bridge, 
+        cpu::{backend::CpuBackend, rdme_backend::RdmeBackend},
         gpu::{
-            compute::Compute, gfx_context::GraphicsContext, render::Render, resources::Resources}}, 
+            backend::GpuBackend, compute::Compute, gfx_context::GraphicsContext, particles::GpuParticles, render::Render, resources::Resources},
+        simulation::{BackendKind, SimulationBackend},
+        stepper}, 
     world::{
+        boundary::BoundaryConditions,
+        brownian_motion::Particles,
+        cahn_hilliard::CahnHilliard,
+        units::{self, PhysicalUnits},
+        checkpoint::Checkpoint,
+        field::VoxelField,
+        integrator::Integrator,
+        network::ReactionNetwork,
+        noise::ThermalNoise,
+        rdme::Rdme,
+        reaction::{self, Reaction},
+        species::SpeciesRegistry,
+        stability::{self, StabilityLimit, TimestepController},
+        vesicle::Vesicle,
         voxel_grid::Dims3, 
+        vtk::{self, VtkGeometry},
         world::{World}}
     };
 use std::error::Error;
 
+const CHECKPOINT_PATH: &str = "bocs.ckpt"; // F5 saves, F9 restores
+const READOUT_INTERVAL: f32 = 0.25; // seconds between title bar readouts
+
 pub struct State {
     pub gfx_ctx: GraphicsContext,
     pub world: World,
@@ -18,11 +39,16 @@ pub struct State {
     resources: Resources,
     compute: Compute,
     render: Render,
+    backend: Box<dyn SimulationBackend>, // steps the field, GPU or CPU
+    gpu_particles: Option<GpuParticles>, // steps world.particles when the backend is on the GPU, see set_particles()
+    particles_on_gpu: bool, // whether the backend resolved to the GPU
 
     dims: Dims3,
-    init_complete: bool,
-    read_ping: bool,
-    time: std::time::Instant,
+    init_complete: bool, // false until the first render() runs the init pass, or a field is seeded
+    read_ping: bool, // as reported by backend.sync_for_render() each frame
+    displayed: usize, // species the raymarch pass draws, V cycles it
+    time: std::time::Instant, // wall time of the last frame, feeds world.clock
+    readout_time: std::time::Instant, // last title bar readout
 
     pub mouse_pressed: bool,
     pub mouse_pressed_pos: Option<PhysicalPosition<f64>>,
@@ -30,11 +56,21 @@ pub struct State {
 
 impl State {
     
-    pub async fn new(window: Arc<Window>) -> Result<Self, Box<dyn Error>> {
-        let mut gfx_ctx: GraphicsContext = GraphicsContext::new(window).await?;
-        let dims: Dims3 = [200, 200, 200];
+    pub async fn new(window: Arc<Window>, backend: BackendKind) -> Result<Self, Box<dyn Error>> {
+        let gfx_ctx: GraphicsContext = GraphicsContext::new(window).await?;
+        Self::from_context(gfx_ctx, [200, 200, 200], backend)
+    }
+
+    /// Windowless State for offscreen simulation (batch jobs, CI)
+    /// render() draws into gfx_ctx.offscreen instead of a surface
+    pub async fn new_headless(size: PhysicalSize<u32>, dims: Dims3, backend: BackendKind) -> Result<Self, Box<dyn Error>> {
+        let gfx_ctx: GraphicsContext = GraphicsContext::new_headless(size).await?;
+        Self::from_context(gfx_ctx, dims, backend)
+    }
+
+    fn from_context(mut gfx_ctx: GraphicsContext, dims: Dims3, backend: BackendKind) -> Result<Self, Box<dyn Error>> {
         // World contains voxel_grid and camera
-        let world = World::new(dims, &gfx_ctx);
+        let world = World::new(dims, &gfx_ctx.size);
 
         // Bridge holds rand seed and maintains dispatch dims for raymarch and laplacian
         let bridge = Bridge::new(&world.voxel_grid, &gfx_ctx);
@@ -44,6 +80,15 @@ impl State {
         let compute = Compute::new(&dims, &resources, &gfx_ctx);
         
         let render = Render::new(&resources, &gfx_ctx);
+
+        // Simulation runs wherever the machine allows, raymarch and render always run on gfx_ctx
+        let backend = backend.resolve(&gfx_ctx);
+        let particles_on_gpu = backend == BackendKind::Gpu;
+        let backend: Box<dyn SimulationBackend> = match backend {
+            BackendKind::Cpu => Box::new(CpuBackend::new(dims)),
+            BackendKind::Rdme(model) => Box::new(RdmeBackend::new(dims, model)),
+            _ => Box::new(GpuBackend::new(&dims, &gfx_ctx, &resources, &compute, &bridge))
+        };
         
         Ok (
             Self { 
@@ -53,11 +98,16 @@ impl State {
                 resources: resources,
                 compute: compute,
                 render: render,
+                backend: backend,
+                gpu_particles: None,
+                particles_on_gpu: particles_on_gpu,
 
                 init_complete: false,
                 read_ping: true,
+                displayed: 0,
                 dims: dims,
                 time: std::time::Instant::now(),
+                readout_time: std::time::Instant::now(),
 
                 mouse_pressed: false,
                 mouse_pressed_pos: None
@@ -84,70 +134,60 @@ impl State {
 
     
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let surface_texture = self.gfx_ctx.surface.get_current_texture()?;
+        let frame = self.gfx_ctx.acquire_frame()?; // surface texture, or offscreen texture when headless
         // this defines how the texture is interpreted (sampled) to produce the actual pixel outputs to the surface
         // texel -> pixel
-        let surface_texture_view = surface_texture.texture.create_view(&wgpu::TextureViewDescriptor::default()); // both associated with surface
+        let surface_texture_view = frame.view();
 
-        self.world.generate_bb_projection(&self.gfx_ctx); 
+        self.world.generate_bb_projection(&self.gfx_ctx.size); 
 
         let mut encoder = self.gfx_ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Command Encoder")
         });
 
-        // UPDATE TIMESTEP //
+        // NOTICES QUEUED BY THE BACKEND'S SETTERS, PRINTED ONCE //
+        stepper::print_notices(self.backend.as_mut());
+
+        // ADVANCE SIM CLOCK: FIXED dt, WALL TIME ONLY DECIDES HOW MANY STEPS //
         let now = std::time::Instant::now();
-        let duration = ((now - self.time).as_secs_f32()).min(0.1666666); // stability bound for 3D euler integration
-        // let fps = 1.0 / duration;
-        //println!("fps: {}\n", fps);
+        let elapsed = self.world.units.sim_us((now - self.time).as_secs_f32());
         self.time = now;
 
-        // Ping pong flag
-        self.read_ping = if self.init_complete{ !self.read_ping }
-        else { self.read_ping };
-
-        // UPDATE AND WRITE NEW UNIFORMS BUFFER TO QUEUE
-        self.resources.uniforms_refresh(&self.gfx_ctx, &self.read_ping, duration, self.world.bbox, &self.dims, &self.world);
-
+        // INIT (RAND SEED) ON FIRST FRAME UNLESS A FIELD WAS SEEDED, STEP THEREAFTER
+        // EACH dt STEP IS SPLIT INTO SUB-STEPS WHEN IT EXCEEDS AN OPERATOR'S STABILITY LIMIT, SEE stepper::advance()
         if !self.init_complete {
-        {   
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor{
-                label: Some("Init"),
-                timestamp_writes: None
-                });
-
-            // RAND SEED INIT
-            compute_pass.set_pipeline(&self.compute.init_p);
-            compute_pass.set_bind_group(0, &self.compute.bg, &[]); 
-            let [x, y, z] = self.bridge.init_dispatch;
-            compute_pass.dispatch_workgroups(x, y, z);  // group size is 8 * 4 * 8 <= 256 (256, 256, 64 respective limits)
+            self.backend.init(self.bridge.rand_seed);
             self.init_complete = true;
-
-            // RAYMARCH
-            compute_pass.set_pipeline(&self.compute.raymarch_p);
-            compute_pass.set_bind_group(0, &self.compute.bg, &[]); 
-            let [x, y, z] = self.bridge.raymarch_dispatch;
-            compute_pass.dispatch_workgroups(x, y, z); 
         }
+        else if let Err(e) = stepper::advance(&mut self.world, self.backend.as_mut(), self.gpu_particles.as_ref(), self.bridge.rand_seed, elapsed) {
+            // a change since set_timestep() made dt too many sub-steps, hold the clock until it is fixed
+            if !self.world.clock.paused {
+                println!("{}, pausing\n", e);
+                self.world.clock.toggle_pause();
+            }
+        }
+
+        // PHYSICAL READOUT IN THE TITLE BAR
+        if let Some(window) = &self.gfx_ctx.window && (now - self.readout_time).as_secs_f32() > READOUT_INTERVAL {
+            window.set_title(&format!("📦 {}", self.readout()));
+            self.readout_time = now;
         }
-        else {
-            {
+        // MAKE THE BACKEND'S FIELD (OR THE DISPLAYED SPECIES) VISIBLE TO RAYMARCH
+        self.read_ping = self.backend.sync_for_render(&self.resources, &self.gfx_ctx, self.displayed);
+
+        // UPDATE AND WRITE NEW UNIFORMS BUFFER TO QUEUE
+        self.resources.uniforms_refresh(&self.gfx_ctx, &self.read_ping, self.world.clock.dt, self.world.bbox, &self.dims, &self.world, &self.bridge);
+
+        {
             let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor{
-                label: Some("Laplacian"),
+                label: Some("Raymarch"),
                 timestamp_writes: None
                 });
-    
 
-            compute_pass.set_pipeline(&self.compute.laplacian_p);
-            compute_pass.set_bind_group(0, &self.compute.bg, &[]); 
-             let [x, y, z] = self.bridge.laplacian_dispatch;
-            compute_pass.dispatch_workgroups(x, y, z);  // group size is 8 * 4 * 8 <= 256 (256, 256, 64 respective limits)
-            // Raymarch
             compute_pass.set_pipeline(&self.compute.raymarch_p);
             compute_pass.set_bind_group(0, &self.compute.bg, &[]); 
             let [x, y, z] = self.bridge.raymarch_dispatch; 
             compute_pass.dispatch_workgroups(x, y, z);
-            }
         }
         
         {
@@ -179,17 +219,430 @@ impl State {
         
         // submit will accept anything that implements IntoIter
         self.gfx_ctx.queue.submit(std::iter::once(encoder.finish())); // allowing encoder call here
-        surface_texture.present();
+        frame.present();
     
         Ok(())
 
     }
 
-    pub fn handle_key(&self, event_loop: &winit::event_loop::ActiveEventLoop, code: winit::keyboard::KeyCode, is_pressed: bool) {
+    /// Seeds the simulation with a caller-provided field in place of the init pass
+    /// Call before the first render(), field must match the VoxelGrid's dims
+    pub fn seed_field(&mut self, field: &[f32]) {
+        assert!(field.len() == (self.dims[0] * self.dims[1] * self.dims[2]) as usize, "Field length does not match VoxelGrid dims\n");
+        self.backend.write_field(field);
+        self.init_complete = true;
+    }
+
+    /// Seeds the simulation by evaluating f(x, y, z) at every voxel, see seed_field()
+    pub fn seed_field_with(&mut self, f: impl Fn(u32, u32, u32) -> f32) {
+        let field = VoxelField::from_fn(self.dims, f);
+        self.seed_field(&field.data);
+    }
+
+    /// Copy of the current concentration field
+    /// Asks the backend rather than Resources, so it is current even between CPU steps
+    pub fn read_field(&mut self) -> VoxelField {
+        self.backend.read_field()
+    }
+
+    /// Raw GPU readback of the voxel plane the raymarch pass last read (State::read_ping)
+    pub fn read_rendered_field(&self) -> VoxelField {
+        self.resources.read_voxels(&self.gfx_ctx, self.displayed, self.read_ping, &self.dims)
+    }
+
+    /// Sum of all timesteps taken so far, µs
+    pub fn sim_time(&self) -> f64 {
+        self.world.clock.sim_time()
+    }
+
+    /// Sim time, dt, integrator (and implicit solver), box size and D in physical units, then the reaction, network and particles if any,
+    /// e.g. "t = 1.250 ms (step 12500) | dt = 100.000 ns, explicit Euler | 200.00 nm x 200.00 nm x 200.00 nm | D = 1 µm²/s"
+    /// or "... | dt = 10.000 µs, backward Euler (multigrid, 3 V-cycles, |r|/|f| = 2.1e-5) | ..."
+    /// or "... | D = 1 µm²/s | Cahn-Hilliard M = 1, κ = 2, A = 1 | conserved noise kT = 0.01 | Gray-Scott F = 0.037, k = 0.06 | showing v of 2 species"
+    pub fn readout(&self) -> String {
+        let [x, y, z] = self.world.units.box_nm(&self.dims);
+        let dt = match self.world.stability.last().and_then(|plan| plan.limited_by.map(|limit| (plan, limit))) {
+            Some((plan, limit)) => format!("{} ({} x {}, {}-limited)",
+                units::format_time_us(plan.requested as f64), plan.substeps, units::format_time_us(plan.sub_dt as f64), limit.operator),
+            None => units::format_time_us(self.world.clock.dt as f64)
+        };
+        let integrator = match (self.world.integrator.solver(), self.backend.solver_history()) {
+            (Some(solver), Some(history)) => format!("{} ({}, {})", self.world.integrator.name(), solver.name(), history.summary()),
+            (Some(solver), None) => format!("{} ({})", self.world.integrator.name(), solver.name()),
+            (None, _) => self.world.integrator.name().to_string()
+        };
+        let reaction = match &self.world.reaction {
+            Some(reaction) => format!(" | {}", reaction.name()),
+            None => String::new()
+        };
+        let network = match &self.world.network {
+            Some(network) => format!(" | {}", network.name()),
+            None => String::new()
+        };
+        let species = match self.world.species.len() {
+            1 => String::new(),
+            count => format!(" | showing {} of {} species", self.world.species.name(self.displayed), count)
+        };
+        let phase = match (&self.world.cahn_hilliard, &self.world.vesicle) {
+            (Some(model), _) => format!(" | {}", model.name()),
+            (None, Some(model)) => format!(" | {}", model.name()),
+            (None, None) => String::new()
+        };
+        let noise = match &self.world.noise {
+            Some(noise) => format!(" | {}", noise.name()),
+            None => String::new()
+        };
+        let particles = match &self.world.particles {
+            Some(particles) => format!(" | {}", particles.name()),
+            None => String::new()
+        };
+        format!("t = {} (step {}{}) | dt = {}, {} | {} x {} x {} | D = {} µm²/s{}{}{}{}{}{}",
+            units::format_time_us(self.world.clock.sim_time()),
+            self.world.clock.step_count(),
+            if self.world.clock.paused { ", paused" } else { "" },
+            dt,
+            integrator,
+            units::format_length_nm(x as f64), units::format_length_nm(y as f64), units::format_length_nm(z as f64),
+            self.world.units.diffusion_um2_per_s,
+            phase,
+            noise,
+            reaction,
+            network,
+            particles,
+            species)
+    }
+
+    /// Writes the current field as .vti or .vtk (by extension), placed in the VoxelGrid's world coordinates in nm
+    pub fn export_vtk(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let geometry = VtkGeometry::from_voxel_grid(&self.world.voxel_grid, &self.world.units.voxel_nm);
+        vtk::write_vtk(path, &self.backend.read_field(), &geometry)
+    }
+
+    /// Changes the boundary conditions from the next step on, e.g. between frames
+    pub fn set_boundaries(&mut self, bounds: BoundaryConditions) {
+        self.world.boundaries = bounds;
+        self.backend.set_boundaries(&bounds);
+    }
+
+    /// Changes voxel size, D and time scale from the next step on
+    /// The clock's dt is kept, world.stability sub-steps it if it is no longer stable
+    pub fn set_units(&mut self, units: PhysicalUnits) {
+        self.world.units = units;
+        self.backend.set_diffusion(&units.diffusion());
+    }
+
+    /// Switches between explicit and implicit diffusion from the next step on
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.world.integrator = integrator;
+        self.backend.set_integrator(&integrator);
+    }
+
+    /// Replaces the species the backend stores, reallocating Resources' planes, see SpeciesRegistry
+    /// Fields are kept by index, added species start at zero, call before the first render() so init seeds them
+    /// While reacting, a species 1 is kept for the reaction (SpeciesRegistry::with_reaction()),
+    /// and the network, if any, is re-indexed by name, so every species it names must stay
+    pub fn set_species(&mut self, registry: SpeciesRegistry) -> Result<(), Box<dyn Error>> {
+        let registry = registry.with_reaction(self.world.reaction.is_some());
+        let network = match &self.world.network {
+            Some(network) => {
+                self.check_network_binding(registry.len())?;
+                Some(network.resolve(&registry)?)
+            },
+            None => None
+        };
+        self.resources.set_species_count(&self.gfx_ctx, registry.len())?;
+        self.displayed = self.displayed.min(registry.len() - 1);
+        self.compute.bind_species(&self.dims, &self.gfx_ctx, &self.resources, self.displayed);
+        self.backend.set_species(&registry, &self.resources);
+        self.backend.set_network(network.as_ref());
+        self.world.species = registry;
+        self.world.network = network;
+        Ok(())
+    }
+
+    /// Makes every species react by network from the next step on, or stops the network with None
+    /// network must be indexed as world.species, load_network() extends the registry as a file declares
+    pub fn set_network(&mut self, network: Option<ReactionNetwork>) -> Result<(), Box<dyn Error>> {
+        let network = match network {
+            Some(network) => {
+                self.check_network_binding(self.world.species.len())?;
+                Some(network.resolve(&self.world.species)?)
+            },
+            None => None
+        };
+        self.backend.set_network(network.as_ref());
+        self.world.network = network;
+        Ok(())
+    }
+
+    /// Parses a network file against world.species, adds the species it declares, then sets it
+    /// Call before the first render() so init seeds the added species, see ReactionNetwork::parse()
+    pub fn load_network(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let (network, registry) = ReactionNetwork::load(path, &self.world.species)?;
+        self.set_network(None)?;
+        self.set_species(registry)?;
+        self.set_network(Some(network))
+    }
+
+    /// The GPU network pass binds every species' plane at once, which must fit one storage binding
+    fn check_network_binding(&self, species: usize) -> Result<(), Box<dyn Error>> {
+        let size = self.resources.plane_stride() * species as u64;
+        let limit = self.gfx_ctx.device.limits().max_storage_buffer_binding_size as u64;
+        if size > limit {
+            return Err(format!("A reaction network over {} species needs a {} byte binding, the device allows {}, try fewer species or a smaller grid", species, size, limit).into());
+        }
+        Ok(())
+    }
+
+    /// Adds (or with None removes) the reaction coupling species 0 and 1, and a species v to couple to when there is no species 1
+    /// Call before the first render() so init seeds both species, a v added mid-run starts at zero
+    pub fn set_reaction(&mut self, reaction: Option<Reaction>) -> Result<(), Box<dyn Error>> {
+        let registry = self.world.species.clone().with_reaction(reaction.is_some());
+        if reaction.is_none() { self.backend.set_reaction(None); }
+        self.world.reaction = reaction;
+        self.set_species(registry)?;
+        self.backend.set_reaction(reaction.as_ref());
+        Ok(())
+    }
+
+    /// Cahn-Hilliard phase separation of species 0 in place of its diffusion from the next step on, or diffusion again with None
+    /// Call before the first render() so init seeds φ about the model's mean composition
+    /// Replaces the vesicle model, if any
+    pub fn set_cahn_hilliard(&mut self, model: Option<CahnHilliard>) {
+        if model.is_some() && self.world.vesicle.is_some() { self.set_vesicle(None); }
+        self.world.cahn_hilliard = model;
+        self.backend.set_cahn_hilliard(model.as_ref());
+    }
+
+    /// Phase-field vesicle dynamics of species 0 in place of its diffusion from the next step on, or diffusion again with None
+    /// Replaces the Cahn-Hilliard model, if any. Call before the first render() so init seeds the initial ellipsoid
+    pub fn set_vesicle(&mut self, model: Option<Vesicle>) {
+        if model.is_some() && self.world.cahn_hilliard.is_some() { self.set_cahn_hilliard(None); }
+        self.world.vesicle = model;
+        self.backend.set_vesicle(model.as_ref());
+    }
+
+    /// Thermal noise on species 0 from the next step on, or deterministic again with None
+    /// Keyed on the run's rand seed and counted by the clock's step, so runs with the same seed reproduce exactly
+    pub fn set_noise(&mut self, noise: Option<ThermalNoise>) {
+        self.world.noise = noise;
+        self.backend.set_noise(noise.as_ref(), self.bridge.rand_seed);
+    }
+
+    /// Brownian particles from the next step on, or none with None, see world/brownian_motion.rs
+    /// Stepped once per clock step at the full dt, keyed on the run's rand seed like the noise
+    /// With the GPU backend they are uploaded to GpuParticles and stepped there, world.particles lagging until sync_particles()
+    pub fn set_particles(&mut self, particles: Option<Particles>) {
+        self.gpu_particles = match &particles {
+            Some(p) if self.particles_on_gpu => Some(GpuParticles::new(&self.dims, &self.gfx_ctx.device, &self.gfx_ctx.queue, &self.compute.particles, p)),
+            _ => None
+        };
+        self.world.particles = particles;
+    }
+
+    /// Brings world.particles up to date with GpuParticles, nothing to do when they step on the CPU
+    pub fn sync_particles(&mut self) {
+        if let (Some(gpu), Some(particles)) = (&self.gpu_particles, &mut self.world.particles) {
+            gpu.download(particles);
+        }
+    }
+
+    /// count particles scattered uniformly through the box, each with D µm²/s, keyed on the run's rand seed
+    pub fn scatter_particles(&mut self, count: usize, diffusivity: f32) {
+        let extent = self.world.units.box_nm(&self.dims);
+        self.set_particles(Some(Particles::scatter(count, diffusivity, &extent, self.bridge.rand_seed)));
+    }
+
+    /// Particles within cutoff nm of each particle, in their order, None without any
+    /// From the spatial hash, one cell per voxel, so cutoff is at most the voxel spacing: GpuParticles' counting sort
+    /// with the GPU backend, cpu::particles' otherwise
+    pub fn particle_neighbours(&mut self, cutoff: f32) -> Option<Vec<u32>> {
+        match &self.gpu_particles {
+            Some(gpu) => Some(gpu.neighbour_counts(&self.world.units.voxel_nm, &self.world.boundaries, cutoff)),
+            None => stepper::particle_neighbours(&self.world, cutoff)
+        }
+    }
+
+    /// Prints what the run ends on, see stepper::report(), with the particles' neighbours within cutoff nm if given
+    pub fn report(&mut self, cutoff: Option<f32>, rdme: Option<&Rdme>) {
+        self.sync_particles();
+        let neighbours = cutoff.and_then(|cutoff| self.particle_neighbours(cutoff).map(|counts| (cutoff, counts)));
+        stepper::report(&self.world, self.backend.as_mut(), neighbours, rdme);
+    }
+
+    /// Sets the fixed step length in µs
+    /// Steps beyond the stability limits are split into sub-steps rather than rejected, up to stability::MAX_SUBSTEPS
+    pub fn set_timestep(&mut self, dt: f32) -> Result<(), Box<dyn Error>> {
+        Self::check_timestep(dt, &self.stability_limits())?;
+        self.world.clock.dt = dt;
+        Ok(())
+    }
+
+    /// Whether dt is positive and can be sub-stepped within limits, see TimestepController::plan()
+    fn check_timestep(dt: f32, limits: &[StabilityLimit]) -> Result<(), Box<dyn Error>> {
+        if !(dt > 0.0 && dt.is_finite()) {
+            return Err(format!("Timestep {} µs should be finite and > 0.0", dt).into());
+        }
+        TimestepController::default().plan(dt, limits)?;
+        Ok(())
+    }
+
+    /// Limits of every operator the run has on and the backend's own, see stepper::stability_limits()
+    fn stability_limits(&self) -> Vec<StabilityLimit> {
+        stepper::stability_limits(&self.world, self.backend.as_ref())
+    }
+
+    /// Writes field, seed, ping/pong parity, sim time, camera basis, boundaries, diffusion, dt, step mode and integrator to path,
+    /// plus every other species and its field, the reaction, the network, the Cahn-Hilliard or vesicle model, the noise and the particles if set
+    pub fn save_checkpoint(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        self.sync_particles();
+        stepper::checkpoint(&self.world, self.backend.as_mut(), self.bridge.rand_seed).save(path)
+    }
+
+    /// Checks a checkpoint against this State before restore_checkpoint() sets any of it:
+    /// its dims against the VoxelGrid's, its species against the device's buffer limits and the network it resolves,
+    /// and the timestep it resumes with against the stability limits of its own settings and the backend's
+    fn check_checkpoint(&self, checkpoint: &Checkpoint) -> Result<(), Box<dyn Error>> {
+        if checkpoint.field.dims != self.dims {
+            return Err(format!("Checkpoint dims {:?} do not match VoxelGrid dims {:?}", checkpoint.field.dims, self.dims).into());
+        }
+        let species = checkpoint.species.clone().with_reaction(checkpoint.reaction.is_some());
+        if species.len() != checkpoint.species_fields.len() + 1 {
+            return Err(format!("Checkpoint holds {} fields for {} species", checkpoint.species_fields.len() + 1, species.len()).into());
+        }
+        self.resources.check_species_count(&self.gfx_ctx, species.len())?;
+        let network = match &checkpoint.network {
+            Some(network) => {
+                self.check_network_binding(species.len())?;
+                Some(network.resolve(&species)?)
+            },
+            None => None
+        };
+        let dt = checkpoint.clock.map_or(self.world.clock.dt, |(dt, _)| dt);
+        let units = PhysicalUnits::from_diffusion(&checkpoint.diffusion, self.world.units.time_scale);
+        let mut limits = stability::stability_limits(&units, &checkpoint.integrator, &species, checkpoint.reaction.as_ref(), network.as_ref(), checkpoint.cahn_hilliard.as_ref(), checkpoint.vesicle.as_ref());
+        limits.extend(self.backend.stability_limit(&species, &checkpoint.diffusion));
+        Self::check_timestep(dt, &limits)
+    }
+
+    /// Resumes from a checkpoint written by save_checkpoint(), in place of the init pass
+    /// Every section is checked by check_checkpoint() first, so a checkpoint this run can't hold changes nothing
+    pub fn restore_checkpoint(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let checkpoint = Checkpoint::load(path)?;
+        self.check_checkpoint(&checkpoint)?;
+
+        self.set_network(None)?;
+        self.set_reaction(None)?;
+        self.set_species(checkpoint.species.clone())?;
+        self.set_reaction(checkpoint.reaction)?;
+        self.set_network(checkpoint.network.clone())?;
+        self.set_cahn_hilliard(checkpoint.cahn_hilliard);
+        self.set_vesicle(checkpoint.vesicle);
+        self.backend.restore_field(&checkpoint.field.data, checkpoint.read_ping);
+        for (species, field) in checkpoint.species_fields.iter().enumerate() {
+            self.backend.write_species(species + 1, &field.data);
+        }
+        self.bridge.rand_seed = checkpoint.rand_seed;
+        self.set_noise(checkpoint.noise); // keyed on the restored seed
+        self.world.clock.restore(checkpoint.sim_time, checkpoint.step_count);
+        checkpoint.camera.apply_to(&mut self.world.camera);
+        self.set_boundaries(checkpoint.boundaries);
+        self.set_integrator(checkpoint.integrator);
+        self.set_particles(checkpoint.particles.clone());
+        self.set_units(PhysicalUnits::from_diffusion(&checkpoint.diffusion, self.world.units.time_scale));
+        if let Some((dt, mode)) = checkpoint.clock {
+            self.set_timestep(dt)?; // against the restored limits
+            self.world.clock.mode = mode;
+        }
+        self.init_complete = true;
+        Ok(())
+    }
+
+    pub fn handle_key(&mut self, event_loop: &winit::event_loop::ActiveEventLoop, code: winit::keyboard::KeyCode, is_pressed: bool) {
         match (code, is_pressed) {
             (winit::keyboard::KeyCode::Escape, true) => {
                 event_loop.exit()
             },
+            (winit::keyboard::KeyCode::F5, true) => {
+                match self.save_checkpoint(Path::new(CHECKPOINT_PATH)) {
+                    Ok(_) => println!("Saved checkpoint to {}\n", CHECKPOINT_PATH),
+                    Err(e) => println!("Unable to save checkpoint: {}\n", e)
+                }
+            },
+            (winit::keyboard::KeyCode::Space, true) => {
+                self.world.clock.toggle_pause();
+            },
+            (winit::keyboard::KeyCode::Period, true) => {
+                self.world.clock.single_step(); // only while paused
+            },
+            (winit::keyboard::KeyCode::KeyB, true) => {
+                let bounds = self.world.boundaries.cycle();
+                self.set_boundaries(bounds);
+                println!("Boundaries: {:?}\n", bounds.faces[0][0]);
+            },
+            (winit::keyboard::KeyCode::KeyI, true) => {
+                let integrator = self.world.integrator.cycle();
+                self.set_integrator(integrator);
+                println!("Integrator: {}\n", integrator.name());
+            },
+            (winit::keyboard::KeyCode::KeyM, true) => {
+                let integrator = self.world.integrator.toggle_solver();
+                self.set_integrator(integrator);
+                if let Some(solver) = integrator.solver() { println!("Implicit solver: {}\n", solver.name()); }
+            },
+            (winit::keyboard::KeyCode::KeyR, true) => {
+                // re-seeds both species, Gray-Scott's v = 0 is a steady state
+                let reaction = match self.world.reaction {
+                    Some(_) => None,
+                    None => Some(Reaction::gray_scott(reaction::GRAY_SCOTT_FEED, reaction::GRAY_SCOTT_KILL))
+                };
+                match self.set_reaction(reaction) {
+                    Ok(_) => println!("Reaction: {}\n", reaction.map(|r| r.name()).unwrap_or("none".to_string())),
+                    Err(e) => println!("Unable to set reaction: {}\n", e)
+                }
+                self.init_complete = false;
+            },
+            (winit::keyboard::KeyCode::KeyC, true) => {
+                // re-seeds about the mean composition, the init pass's noise in [0, 1) would not separate
+                let model = match self.world.cahn_hilliard {
+                    Some(_) => None,
+                    None => Some(CahnHilliard::default())
+                };
+                self.set_cahn_hilliard(model);
+                self.init_complete = false;
+                println!("Cahn-Hilliard: {}\n", model.map(|m| m.name()).unwrap_or("off".to_string()));
+            },
+            (winit::keyboard::KeyCode::KeyH, true) => {
+                // re-seeds the initial ellipsoid, noise has no membrane to relax
+                let model = match self.world.vesicle {
+                    Some(_) => None,
+                    None => Some(Vesicle::default())
+                };
+                self.set_vesicle(model);
+                self.init_complete = false;
+                println!("Vesicle: {}\n", model.map(|m| m.name()).unwrap_or("off".to_string()));
+            },
+            (winit::keyboard::KeyCode::KeyN, true) => {
+                let noise = match self.world.noise {
+                    Some(_) => None,
+                    None => Some(ThermalNoise::default())
+                };
+                self.set_noise(noise);
+                println!("Noise: {}\n", noise.map(|n| n.name()).unwrap_or("off".to_string()));
+            },
+            (winit::keyboard::KeyCode::KeyV, true) => {
+                if self.backend.species_count() > 1 {
+                    self.displayed = (self.displayed + 1) % self.backend.species_count();
+                    self.compute.bind_species(&self.dims, &self.gfx_ctx, &self.resources, self.displayed);
+                    println!("Showing species {}\n", self.world.species.name(self.displayed));
+                }
+            },
+            (winit::keyboard::KeyCode::F9, true) => {
+                match self.restore_checkpoint(Path::new(CHECKPOINT_PATH)) {
+                    Ok(_) => println!("Restored checkpoint from {}\n", CHECKPOINT_PATH),
+                    Err(e) => println!("Unable to restore checkpoint: {}\n", e)
+                }
+            },
             _ => {}
         }
     
@@ -0,0 +1,126 @@
+use crate::{
+    backend_admin::{
+        cpu::particles::{CellList, neighbour_counts},
+        gpu::particles::GpuParticles,
+        simulation::SimulationBackend},
+    world::{
+        checkpoint::{CameraBasis, Checkpoint},
+        field::VoxelField,
+        rdme::Rdme,
+        species::SpeciesRegistry,
+        stability::{self, StabilityLimit},
+        vesicle::Vesicle,
+        world::World}
+    };
+
+// Stepping and readings over a World and the SimulationBackend holding its fields,
+// shared by State (render() each frame) and the headless CPU fallback in main.rs, which has no State
+
+/// Limits of every operator world has on, see stability::stability_limits(), and the backend's own
+pub fn stability_limits(world: &World, backend: &dyn SimulationBackend) -> Vec<StabilityLimit> {
+    let mut limits = stability::stability_limits(&world.units, &world.integrator, &world.species, world.reaction.as_ref(), world.network.as_ref(), world.cahn_hilliard.as_ref(), world.vesicle.as_ref());
+    limits.extend(backend.stability_limit(&world.species, &world.units.diffusion()));
+    limits
+}
+
+/// Prints the notices the backend's setters queued since the last call, once each
+pub fn print_notices(backend: &mut dyn SimulationBackend) {
+    for notice in backend.take_notices() { println!("{}\n", notice); }
+}
+
+/// Advances world.clock by elapsed µs (a StepMode::PerFrame clock ignores it)
+/// Each clock step is split into world.stability's sub-steps of the backend, then steps world.particles,
+/// on gpu_particles when they live on the GPU, with seed's noise
+/// Err when dt takes more sub-steps than stability::MAX_SUBSTEPS, the clock is left as it was
+pub fn advance(world: &mut World, backend: &mut dyn SimulationBackend, gpu_particles: Option<&GpuParticles>, seed: u32, elapsed: f32) -> Result<(), String> {
+    let plan = world.stability.plan(world.clock.dt, &stability_limits(world, backend))?;
+    let first_step = world.clock.step_count();
+    let steps = world.clock.advance(elapsed);
+    if steps == 0 { return Ok(()); }
+    if let Some(report) = world.stability.take_report() {
+        println!("{}\n", report);
+    }
+    let extent = world.units.box_nm(&world.voxel_grid.dims);
+    for step in 0..steps {
+        backend.set_step_index(first_step + step as u64, 0); // the noise's counter, see world/noise.rs
+        for _ in 0..plan.substeps {
+            backend.step(plan.sub_dt);
+        }
+        if let Some(particles) = &mut world.particles {
+            match gpu_particles {
+                Some(gpu) => gpu.step(particles, world.clock.dt, &world.units.voxel_nm, &world.boundaries, seed, first_step + step as u64),
+                None => particles.step(world.clock.dt, &extent, &world.boundaries, seed, first_step + step as u64)
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Everything a run resumes from, world's settings with the backend's fields and parity
+/// world.particles must be up to date, see State::sync_particles()
+pub fn checkpoint(world: &World, backend: &mut dyn SimulationBackend, seed: u32) -> Checkpoint {
+    Checkpoint {
+        field: backend.read_field(),
+        rand_seed: seed,
+        read_ping: backend.read_ping(),
+        sim_time: world.clock.sim_time(),
+        step_count: world.clock.step_count(),
+        camera: CameraBasis::from_camera(&world.camera),
+        boundaries: world.boundaries,
+        diffusion: world.units.diffusion(),
+        species: world.species.clone(),
+        species_fields: (1..world.species.len()).map(|species| backend.read_species(species)).collect(),
+        reaction: world.reaction,
+        network: world.network.clone(),
+        cahn_hilliard: world.cahn_hilliard,
+        vesicle: world.vesicle,
+        noise: world.noise,
+        clock: Some((world.clock.dt, world.clock.mode)),
+        integrator: world.integrator,
+        particles: world.particles.clone()
+    }
+}
+
+/// Particles within cutoff nm of each particle, in their order, from cpu::particles' spatial hash, None without any
+pub fn particle_neighbours(world: &World, cutoff: f32) -> Option<Vec<u32>> {
+    let particles = world.particles.as_ref()?;
+    let (spacing, dims) = (world.units.voxel_nm, world.voxel_grid.dims);
+    let list = CellList::new(particles, &spacing, &dims);
+    Some(neighbour_counts(particles, &list, &spacing, &dims, &world.boundaries, cutoff))
+}
+
+/// Prints what a run ends on, whichever it has: the last multigrid solve, the Cahn-Hilliard free energy, the vesicle's shape,
+/// the particles' MSD and their neighbours within a cutoff in nm, counted by the caller, and the molecules of an RDME run
+/// world.particles must be up to date, see State::sync_particles()
+pub fn report(world: &World, backend: &mut dyn SimulationBackend, neighbours: Option<(f32, Vec<u32>)>, rdme: Option<&Rdme>) {
+    if let Some(history) = backend.solver_history() { println!("Last multigrid solve: {}\n", history.summary()); }
+    if let Some(model) = &world.cahn_hilliard {
+        let field = backend.read_field();
+        println!("Cahn-Hilliard free energy: {:.6e}, mean φ = {}\n", model.free_energy(&field, &world.units.voxel_nm, &world.boundaries), field.mean());
+    }
+    if let Some(model) = &world.vesicle {
+        let (volume, area) = model.measure(&backend.read_field(), &world.units.voxel_nm, &world.boundaries.zero_flux());
+        println!("Vesicle volume: {:.6e} nm³, area: {:.6e} nm², reduced volume: {:.4}\n", volume, area, Vesicle::reduced_volume(volume, area));
+    }
+    if let Some(particles) = &world.particles {
+        let extent = world.units.box_nm(&world.voxel_grid.dims);
+        let (free, error) = particles.free_msd();
+        println!("Particle MSD: {:.6e} nm², free Brownian 6⟨D⟩t = {:.6e} ± {:.1e} nm²\n", particles.msd(&extent), free, error);
+        if let Some((cutoff, counts)) = neighbours {
+            let mean = counts.iter().map(|n| *n as f64).sum::<f64>() / counts.len().max(1) as f64;
+            println!("Particle neighbours within {} nm: mean {:.4}, uniform density (count - 1) 4/3 πr³ / V = {:.4}\n", cutoff, mean, particles.uniform_neighbours(cutoff, &extent));
+        }
+    }
+    if let Some(model) = rdme {
+        let fields: Vec<VoxelField> = (0..world.species.len()).map(|s| backend.read_species(s)).collect();
+        print_molecules(model, &world.species, &fields);
+    }
+}
+
+/// Molecules of every species, summed over the voxels
+fn print_molecules(model: &Rdme, species: &SpeciesRegistry, fields: &[VoxelField]) {
+    let totals = species.iter().zip(fields)
+        .map(|(s, field)| format!("{} {}", s.name, field.data.iter().map(|c| model.count(*c) as u64).sum::<u64>()))
+        .collect::<Vec<String>>();
+    println!("Molecules, {}: {}\n", model.name(), totals.join(", "));
+}
@@ -1,26 +1,406 @@
 use winit::{
+    dpi::PhysicalSize,
     event_loop::{
-        ControlFlow, 
+        ControlFlow,
         EventLoop,
-    }, 
+    },
 };
 mod backend_admin;
 mod world;
-use std::error::Error;
-use crate::backend_admin::{state::State, app_dispatcher::App} ;
+use std::{error::Error, path::{Path, PathBuf}};
+use crate::backend_admin::{
+    state::State,
+    app_dispatcher::App,
+    cpu::{backend::CpuBackend, rdme_backend::RdmeBackend},
+    simulation::{BackendKind, SimulationBackend},
+    stepper};
+use crate::world::{
+    boundary::BoundaryConditions,
+    brownian_motion::Particles,
+    cahn_hilliard::CahnHilliard,
+    checkpoint::Checkpoint,
+    clock::{SimClock, StepMode, DEFAULT_TIMESTEP},
+    integrator::Integrator,
+    network::ReactionNetwork,
+    noise::ThermalNoise,
+    reaction::Reaction,
+    species::SpeciesRegistry,
+    vesicle::Vesicle,
+    field::VoxelField,
+    npy::{self, AxisOrder},
+    units::{self, PhysicalUnits},
+    voxel_grid::VoxelGrid,
+    vtk::{self, PvdSeries, VtkGeometry},
+    world::World};
+
+const HEADLESS_FRAMES: u32 = 100;
+const SERIES_EVERY: u32 = 10; // frames between .pvd series steps, unless --every is passed
 
 /// Entry into app \n
 /// See winit and wgpu docs for more information \n
+/// Pass `--headless [frames]` to run offscreen without a window (e.g. CI, servers) \n
+/// Pass `--backend gpu|cpu|auto` to choose where the simulation runs (default auto), or `--backend rdme[:<Ω>]` for whole molecules hopping and reacting stochastically on the CPU, Ω per unit concentration (default 100, see world/rdme.rs) \n
+/// With `--headless`, `--restore <path>` resumes from a checkpoint and `--checkpoint <path>` saves one at the end \n
+/// With `--headless`, `--init <path.npy>` seeds the field from a 3D .npy array and `--export <path>` writes the final field as .npy, .vti or .vtk \n
+/// With `--headless`, `--bounds <spec>` sets boundary conditions, e.g. `xyz=periodic` or `x=periodic,z-=dirichlet:1.0` (B cycles them in the app) \n
+/// With `--headless`, `--diffusion <µm²/s>`, `--spacing <nm>` or `--spacing <dx,dy,dz>` (nm) and `--time-scale <µs per second>` set physical units (default 1.0 each) \n
+/// With `--headless`, `--dt <µs>` sets the fixed timestep (default a restored checkpoint's, else 0.1 µs or the stable limit if lower; larger steps are split into stable sub-steps, at most 1000) and `--steps-per-frame <n>` how many steps each frame runs (default a restored checkpoint's, else 1) \n
+/// With `--headless`, `--integrator explicit|backward-euler|crank-nicolson[:iterations|:mg[:tolerance]]` picks the diffusion scheme and implicit solver (default a restored checkpoint's, else explicit, I cycles schemes and M toggles Gauss-Seidel / multigrid in the app) \n
+/// With `--headless`, `--reaction gray-scott[:F:k]|poly:du=<terms>;dv=<terms>` adds species v and couples it to u, `--diffusion-ratio <D_v / D_u>` sets v's diffusion (default 0.5, R toggles Gray-Scott and V the displayed species in the app) \n
+/// With `--headless`, `--species <name>[:<D>[:random|<value>[:<bounds>]]];...` adds species after u, each with its own D (µm²/s), initial condition and `--bounds` spec where given, e.g. `lipid:0.5;protein::0.1:xyz=periodic` (V cycles the displayed species in the app) \n
+/// With `--headless`, `--network <path>` reacts the species by the mass-action reactions in a text file, one per line, e.g. `A + B -> C, k=0.3`, adding the species it declares with `species <name>[:<D>...]` (see world/network.rs) \n
+/// With `--headless`, `--cahn-hilliard default|mobility=<M>,kappa=<κ>,depth=<A>,mean=<φ>` runs Cahn-Hilliard phase separation in place of diffusion and reports the final free energy (C toggles it in the app) \n
+/// With `--headless`, `--vesicle default|rigidity=<k>,width=<ε>,curvature=<C>,mobility=<γ>,volume=on|off,area=on|off` relaxes a phase-field vesicle under Helfrich bending in place of diffusion and reports its final volume, area and reduced volume (H toggles it in the app) \n
+/// With `--headless`, `--noise <kT>[:conserved|:non-conserved]` adds thermal noise to species 0 every step, conserved unless stated, reproducible for a given seed (N toggles it in the app) \n
+/// With `--headless`, `--particles <count>[:<D>]` scatters Brownian point particles through the box, each with D µm²/s (default the run's D), in place of a restored checkpoint's, and reports their mean squared displacement against 6Dt \n
+/// With `--headless`, `--neighbours <cutoff>` also reports the particles' mean count of neighbours within cutoff nm (at most the voxel spacing) from the spatial hash, against a uniform density's \n
+/// With `--headless`, `--series <path.pvd>` writes a ParaView time series, one .vti every `--every <frames>` frames (default 10) \n
 #[tokio::main] // this is for async as in backend_admin/app_with_event_handler! see here: https://rust-lang.github.io/async-book/part-guide/async-await.html
-async fn main() -> Result<(), Box<dyn Error>> { // see async  
-    // The EventLoop interfaces with the OS 
+async fn main() -> Result<(), Box<dyn Error>> { // see async
+    let args: Vec<String> = std::env::args().collect();
+    let backend = BackendKind::from_args(&args).unwrap_or_else(|e| {
+        println!("{}, defaulting to auto\n", e);
+        BackendKind::Auto
+    });
+
+    if let Some(pos) = args.iter().position(|a| a == "--headless") {
+        let frames = args.get(pos + 1)
+            .and_then(|f| f.parse::<u32>().ok())
+            .unwrap_or(HEADLESS_FRAMES);
+        let bounds = match arg_value(&args, "--bounds") {
+            Some(spec) => Some(BoundaryConditions::parse(&spec)?),
+            None => None
+        };
+        let units = UnitArgs::parse(&args)?;
+        let integrator = match arg_value(&args, "--integrator") {
+            Some(spec) => Some(Integrator::parse(&spec)?),
+            None => None
+        };
+        let reaction = match arg_value(&args, "--reaction") {
+            Some(spec) => {
+                let reaction = Reaction::parse(&spec)?;
+                match arg_value(&args, "--diffusion-ratio").map(|r| r.parse::<f32>()).transpose()? {
+                    Some(ratio) if ratio >= 0.0 => Some(reaction.with_diffusion_ratio(ratio)),
+                    Some(_) => return Err("--diffusion-ratio should be >= 0".into()),
+                    None => Some(reaction)
+                }
+            },
+            None => None
+        };
+        let species = match arg_value(&args, "--species") {
+            Some(spec) => Some(SpeciesRegistry::parse(&spec)?),
+            None => None
+        };
+        if let Some(species) = &species {
+            println!("Species: {}\n", species.iter().map(|s| s.describe()).collect::<Vec<String>>().join(", "));
+        }
+        let cahn_hilliard = match arg_value(&args, "--cahn-hilliard") {
+            Some(spec) => Some(CahnHilliard::parse(&spec)?),
+            None => None
+        };
+        let vesicle = match arg_value(&args, "--vesicle") {
+            Some(spec) => Some(Vesicle::parse(&spec)?),
+            None => None
+        };
+        if cahn_hilliard.is_some() && vesicle.is_some() { return Err("--cahn-hilliard and --vesicle are mutually exclusive".into()); }
+        let noise = match arg_value(&args, "--noise") {
+            Some(spec) => Some(ThermalNoise::parse(&spec)?),
+            None => None
+        };
+        let particles = match arg_value(&args, "--particles") {
+            Some(spec) => Some(Particles::parse(&spec)?),
+            None => None
+        };
+        let neighbours = arg_value(&args, "--neighbours").map(|r| r.parse::<f32>()).transpose()?;
+        if neighbours.is_some_and(|cutoff| !(cutoff > 0.0 && cutoff.is_finite())) { return Err("--neighbours should be finite and > 0".into()); }
+        let dt = arg_value(&args, "--dt").map(|dt| dt.parse::<f32>()).transpose()?;
+        if dt.is_some_and(|dt| !(dt > 0.0 && dt.is_finite())) { return Err("--dt should be finite and > 0".into()); }
+        let run = HeadlessRun {
+            frames: frames,
+            backend: backend,
+            restore: arg_value(&args, "--restore").map(PathBuf::from),
+            checkpoint: arg_value(&args, "--checkpoint").map(PathBuf::from),
+            init: arg_value(&args, "--init").map(PathBuf::from),
+            export: arg_value(&args, "--export").map(PathBuf::from),
+            series: arg_value(&args, "--series").map(PathBuf::from),
+            bounds: bounds,
+            units: units,
+            integrator: integrator,
+            species: species,
+            reaction: reaction,
+            network: arg_value(&args, "--network").map(PathBuf::from),
+            cahn_hilliard: cahn_hilliard,
+            vesicle: vesicle,
+            noise: noise,
+            particles: particles,
+            neighbours: neighbours,
+            dt: dt,
+            steps_per_frame: arg_value(&args, "--steps-per-frame")
+                .map(|n| n.parse::<u32>())
+                .transpose()?,
+            every: arg_value(&args, "--every")
+                .and_then(|n| n.parse::<u32>().ok())
+                .unwrap_or(SERIES_EVERY)
+                .max(1)
+        };
+        return run_headless(run).await;
+    }
+
+    // The EventLoop interfaces with the OS
     // Tracking WindowEvent and DeviceEvent events...
     let event_loop = EventLoop::<State>::with_user_event().build()?; // not an active event loop
     let proxy = event_loop.create_proxy(); // used to inject awaited requests back into App
 
-    // ControlFlow::Poll continuously runs the event loop (through Application Handler in App), even if the OS hasn't dispatched any events. 
+    // ControlFlow::Poll continuously runs the event loop (through Application Handler in App), even if the OS hasn't dispatched any events.
     event_loop.set_control_flow(ControlFlow::Poll);
 
-    Ok(event_loop.run_app(&mut App::new(move || proxy))?) // ! APP ENTRY HERE ! //
+    Ok(event_loop.run_app(&mut App::new(backend, move || proxy))?) // ! APP ENTRY HERE ! //
+
+}
+
+/// Command line options for a headless run
+struct HeadlessRun {
+    frames: u32,
+    backend: BackendKind,
+    restore: Option<PathBuf>, // checkpoint to resume from
+    checkpoint: Option<PathBuf>, // checkpoint to save at the end
+    init: Option<PathBuf>, // .npy initial condition
+    export: Option<PathBuf>, // final field as .npy (z, y, x), .vti or .vtk
+    bounds: Option<BoundaryConditions>, // overrides a restored checkpoint's boundaries
+    units: UnitArgs, // ditto for physical units
+    integrator: Option<Integrator>, // ditto for the integrator, explicit without either
+    species: Option<SpeciesRegistry>, // overrides a restored checkpoint's species
+    reaction: Option<Reaction>, // ditto for the reaction
+    network: Option<PathBuf>, // ditto for the reaction network, read against the run's species
+    cahn_hilliard: Option<CahnHilliard>, // ditto for the Cahn-Hilliard model
+    vesicle: Option<Vesicle>, // ditto for the vesicle model
+    noise: Option<ThermalNoise>, // ditto for the thermal noise
+    particles: Option<(usize, Option<f32>)>, // count and D of Brownian particles, the run's D when None, in place of a restored checkpoint's
+    neighbours: Option<f32>, // cutoff in nm to count the particles' neighbours within at the end
+    dt: Option<f32>, // fixed timestep in µs, overrides a restored checkpoint's
+    steps_per_frame: Option<u32>, // steps per frame, frames never depend on wall time headless, ditto
+    series: Option<PathBuf>, // .pvd time series
+    every: u32 // frames between series steps
+}
+
+/// No event loop, no window: drives State::render() into an offscreen texture for a fixed number of frames
+/// If no adapter can be created at all, falls back to stepping a CpuBackend without rendering (unless --backend gpu)
+async fn run_headless(run: HeadlessRun) -> Result<(), Box<dyn Error>> {
+    if run.restore.is_some() && run.init.is_some() { return Err("--restore and --init are mutually exclusive".into()); }
+
+    // a restored or seeded run takes its dims from the file
+    let resume = match &run.restore {
+        Some(path) => Some(Checkpoint::load(path)?),
+        None => None
+    };
+    let initial = match &run.init {
+        Some(path) => Some(npy::read_npy(path)?),
+        None => None
+    };
+    let dims = resume.as_ref().map(|c| c.field.dims)
+        .or(initial.as_ref().map(|f| f.dims))
+        .unwrap_or([200, 200, 200]);
+
+    // physical units come from the checkpoint, then any flags on top
+    let units = run.units.apply(match &resume {
+        Some(c) => PhysicalUnits::from_diffusion(&c.diffusion, PhysicalUnits::default().time_scale),
+        None => PhysicalUnits::default()
+    })?;
+
+    // as does the clock, a checkpoint from a windowed run stepping once per frame
+    let saved_clock = resume.as_ref().and_then(|c| c.clock);
+    if let Some(cutoff) = run.neighbours {
+        if run.particles.is_none() && resume.as_ref().is_none_or(|c| c.particles.is_none()) {
+            return Err("--neighbours needs --particles, or a restored checkpoint with particles".into());
+        }
+        let spacing = units.voxel_nm.iter().cloned().fold(f32::INFINITY, f32::min);
+        if cutoff > spacing { return Err(format!("--neighbours {} nm is beyond the voxel spacing {} nm the spatial hash's cells allow", cutoff, spacing).into()); }
+    }
+
+    let dt = run.dt.or(saved_clock.map(|(dt, _)| dt)).unwrap_or(DEFAULT_TIMESTEP.min(units.stable_timestep_us()));
+    let steps_per_frame = run.steps_per_frame.or(match saved_clock {
+        Some((_, StepMode::PerFrame(steps))) => Some(steps),
+        _ => None
+    }).unwrap_or(1);
+
+    let size = PhysicalSize::new(1280, 720);
+    let geometry = VtkGeometry::from_voxel_grid(&VoxelGrid::new_centered_at_origin(dims), &units.voxel_nm);
+    let mut series = run.series.as_ref().map(|path| PvdSeries::new(path));
+
+    let rdme = match run.backend {
+        BackendKind::Rdme(model) => Some(model),
+        _ => None
+    };
+    let sim_time = match State::new_headless(size, dims, run.backend).await {
+        Ok(mut state) => {
+            if let Some(path) = &run.restore { state.restore_checkpoint(path)?; }
+            if let Some(species) = run.species { state.set_species(species)?; }
+            if let Some(reaction) = run.reaction { state.set_reaction(Some(reaction))?; }
+            if let Some(path) = &run.network { state.load_network(path)?; }
+            if let Some(network) = &state.world.network { print_network(network, &state.world.species); }
+            if let Some(model) = run.cahn_hilliard { state.set_cahn_hilliard(Some(model)); }
+            if let Some(model) = run.vesicle { state.set_vesicle(Some(model)); }
+            if let Some(noise) = run.noise { state.set_noise(Some(noise)); }
+            if let Some(field) = &initial { state.seed_field(&field.data); }
+            if let Some(bounds) = run.bounds { state.set_boundaries(bounds); }
+            state.set_units(units);
+            if let Some((count, diffusivity)) = run.particles { state.scatter_particles(count, diffusivity.unwrap_or(units.diffusion_um2_per_s)); }
+            if let Some(integrator) = run.integrator { state.set_integrator(integrator); }
+            state.set_timestep(dt)?;
+            state.world.clock.mode = StepMode::PerFrame(steps_per_frame);
+            for frame in 0..run.frames {
+                state.render()?;
+                if let Some(series) = &mut series && frame % run.every == 0 {
+                    series.write_step(state.sim_time(), &state.read_field(), &geometry)?;
+                }
+            }
+            state.gfx_ctx.device.poll(wgpu::PollType::Wait)?; // block until all submitted passes complete
+            if let Some(path) = &run.checkpoint { state.save_checkpoint(path)?; }
+            if let Some(path) = &run.export { export_field(path, &state.read_field(), &geometry)?; }
+            state.report(run.neighbours, rdme.as_ref());
+            state.sim_time()
+        },
+        Err(e) if run.backend != BackendKind::Gpu => {
+            println!("No usable adapter ({}), simulating on CPU without rendering\n", e);
+            // no State without an adapter, so the fallback keeps its own World, stepped by stepper as State's is
+            let mut world = World::new(dims, &size);
+            world.units = units;
+            world.clock = SimClock::new(dt, StepMode::PerFrame(steps_per_frame));
+            world.reaction = run.reaction.or(resume.as_ref().and_then(|c| c.reaction));
+            let species = run.species.or(resume.as_ref().map(|c| c.species.clone())).unwrap_or_default().with_reaction(world.reaction.is_some());
+            (world.species, world.network) = match &run.network {
+                Some(path) => {
+                    let (network, species) = ReactionNetwork::load(path, &species)?;
+                    (species, Some(network))
+                },
+                None => {
+                    let network = resume.as_ref().and_then(|c| c.network.as_ref()).map(|n| n.resolve(&species)).transpose()?;
+                    (species, network)
+                }
+            };
+            if let Some(network) = &world.network { print_network(network, &world.species); }
+            let mut cpu: Box<dyn SimulationBackend> = match run.backend {
+                BackendKind::Rdme(model) => {
+                    let mut rdme = RdmeBackend::new(dims, model);
+                    rdme.set_registry(&world.species);
+                    Box::new(rdme)
+                },
+                _ => {
+                    let mut cpu = CpuBackend::new(dims);
+                    cpu.set_registry(&world.species);
+                    Box::new(cpu)
+                }
+            };
+            cpu.set_reaction(world.reaction.as_ref());
+            cpu.set_network(world.network.as_ref());
+            // a checkpoint's model only stands when no flag picked the other one
+            world.vesicle = run.vesicle.or(resume.as_ref().and_then(|c| c.vesicle).filter(|_| run.cahn_hilliard.is_none()));
+            world.cahn_hilliard = run.cahn_hilliard.or(resume.as_ref().and_then(|c| c.cahn_hilliard)).filter(|_| run.vesicle.is_none());
+            cpu.set_cahn_hilliard(world.cahn_hilliard.as_ref());
+            cpu.set_vesicle(world.vesicle.as_ref());
+            cpu.set_diffusion(&units.diffusion()); // before init, which seeds the vesicle in nm
+            let (seed, restored_bounds) = match &resume {
+                Some(c) => {
+                    cpu.restore_field(&c.field.data, c.read_ping);
+                    for (i, field) in c.species_fields.iter().enumerate().filter(|(i, _)| i + 1 < world.species.len()) {
+                        cpu.write_species(i + 1, &field.data);
+                    }
+                    world.clock.restore(c.sim_time, c.step_count);
+                    c.camera.apply_to(&mut world.camera);
+                    (c.rand_seed, c.boundaries)
+                },
+                None => {
+                    let seed = rand::random::<u32>();
+                    match &initial {
+                        Some(field) => cpu.write_field(&field.data),
+                        None => cpu.init(seed)
+                    }
+                    (seed, BoundaryConditions::default())
+                }
+            };
+            world.noise = run.noise.or(resume.as_ref().and_then(|c| c.noise));
+            cpu.set_noise(world.noise.as_ref(), seed);
+            world.boundaries = run.bounds.unwrap_or(restored_bounds);
+            cpu.set_boundaries(&world.boundaries);
+            world.integrator = run.integrator.or(resume.as_ref().map(|c| c.integrator)).unwrap_or_default();
+            cpu.set_integrator(&world.integrator);
+            world.particles = run.particles.map(|(count, diffusivity)| Particles::scatter(count, diffusivity.unwrap_or(units.diffusion_um2_per_s), &units.box_nm(&dims), seed))
+                .or(resume.as_ref().and_then(|c| c.particles.clone()));
+            for frame in 0..run.frames {
+                stepper::print_notices(cpu.as_mut());
+                stepper::advance(&mut world, cpu.as_mut(), None, seed, 0.0)?;
+                if let Some(series) = &mut series && frame % run.every == 0 {
+                    series.write_step(world.clock.sim_time(), &cpu.read_field(), &geometry)?;
+                }
+            }
+            if let Some(path) = &run.checkpoint { stepper::checkpoint(&world, cpu.as_mut(), seed).save(path)?; }
+            if let Some(path) = &run.export { export_field(path, &cpu.read_field(), &geometry)?; }
+            let neighbours = run.neighbours.and_then(|cutoff| stepper::particle_neighbours(&world, cutoff).map(|counts| (cutoff, counts)));
+            stepper::report(&world, cpu.as_mut(), neighbours, rdme.as_ref());
+            world.clock.sim_time()
+        },
+        Err(e) => return Err(e)
+    };
+
+    println!("Headless run complete: {} frames, t = {}\n", run.frames, units::format_time_us(sim_time));
+    Ok(())
+}
+
+fn print_network(network: &ReactionNetwork, species: &SpeciesRegistry) {
+    println!("Network: {} over {}\n{}", network.name(), species.iter().map(|s| s.name.as_str()).collect::<Vec<&str>>().join(", "), network.to_text());
+}
+
+/// Writes field as .npy or VTK, picked by path's extension
+fn export_field(path: &Path, field: &VoxelField, geometry: &VtkGeometry) -> Result<(), Box<dyn Error>> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("npy") => npy::write_npy(path, field, AxisOrder::ZYX),
+        _ => vtk::write_vtk(path, field, geometry)
+    }
+}
+
+/// Physical unit flags, each optional and applied over the checkpoint's or default units
+#[derive(Default)]
+struct UnitArgs {
+    diffusion_um2_per_s: Option<f32>, // --diffusion
+    voxel_nm: Option<[f32; 3]>, // --spacing, one value or dx,dy,dz
+    time_scale: Option<f32> // --time-scale, µs per wall-clock second
+}
+
+impl UnitArgs {
+    fn parse(args: &[String]) -> Result<Self, Box<dyn Error>> {
+        let voxel_nm = match arg_value(args, "--spacing") {
+            Some(dx) => {
+                let dx = dx.split(',').map(|v| v.trim().parse::<f32>()).collect::<Result<Vec<f32>, _>>()?;
+                match dx.as_slice() {
+                    [d] => Some([*d, *d, *d]),
+                    [x, y, z] => Some([*x, *y, *z]),
+                    _ => return Err("--spacing takes one value or three (dx,dy,dz)".into())
+                }
+            },
+            None => None
+        };
+        Ok(UnitArgs {
+            diffusion_um2_per_s: arg_value(args, "--diffusion").map(|d| d.parse::<f32>()).transpose()?,
+            voxel_nm: voxel_nm,
+            time_scale: arg_value(args, "--time-scale").map(|t| t.parse::<f32>()).transpose()?
+        })
+    }
+
+    fn apply(&self, base: PhysicalUnits) -> Result<PhysicalUnits, Box<dyn Error>> {
+        let units = PhysicalUnits {
+            voxel_nm: self.voxel_nm.unwrap_or(base.voxel_nm),
+            diffusion_um2_per_s: self.diffusion_um2_per_s.unwrap_or(base.diffusion_um2_per_s),
+            time_scale: self.time_scale.unwrap_or(base.time_scale)
+        };
+        let positive = |x: f32| x > 0.0 && x.is_finite();
+        if !(units.diffusion_um2_per_s >= 0.0 && units.diffusion_um2_per_s.is_finite()) || !units.voxel_nm.iter().all(|dx| positive(*dx)) || !positive(units.time_scale) {
+            return Err("--diffusion should be finite and >= 0, --spacing and --time-scale finite and > 0".into());
+        }
+        Ok(units)
+    }
+}
 
-}
\ No newline at end of file
+/// Value following flag on the command line, e.g. `--restore run.ckpt`
+fn arg_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter().position(|a| a == flag).and_then(|pos| args.get(pos + 1)).cloned()
+}
@@ -0,0 +1,87 @@
+struct Uniforms{
+    mid_window: vec4<u32>,
+    dims: vec4<u32>, // i, j, k, k stride
+    bounding_box: vec4<i32>,
+    cam_pos: vec4<f32>,
+    forward: vec4<f32>,
+    centre: vec4<f32>, // some k*forward
+    up: vec4<f32>,
+    right: vec4<f32>, // [3] horizontal scaling factor (not needed for up, 1:1)
+    timestep: vec4<f32>, // [0] dt, [1] well depth A, [2] stabiliser S
+    seed: vec4<u32>,
+    flags: vec4<u32>, // [0] reada flag 1 true, 0 false
+    bc_kind_lo: vec4<u32>, // boundary kind per axis at index 0 (0 Neumann, 1 periodic), never Dirichlet here
+    bc_kind_hi: vec4<u32>, // ... at index dims - 1
+    bc_value_lo: vec4<f32>,
+    bc_value_hi: vec4<f32>,
+    diffusion: vec4<f32> // [0] mobility M, [1..3] 1 / dx^2 per axis
+}
+// BINDINGS
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(0) @binding(1)
+var<storage, read_write> grid_a: array<f32>;
+
+@group(0) @binding(2)
+var<storage, read_write> grid_b: array<f32>;
+
+// CONSTS
+const group_x: u32 = 8;
+const group_y: u32 = 4;
+const group_z: u32 = 8;
+
+// BOUNDARY CONDITIONS, see world/boundary.rs
+const BC_PERIODIC: u32 = 1; // neighbour is the voxel on the opposite face
+
+// CAHN-HILLIARD, see world/cahn_hilliard.rs
+// (1 - dt M S L + dt M κ L²) φ' = φ + dt M L (A (φ³ - φ) - S φ)
+// rhs() writes the right hand side into the other buffer, the multigrid passes then solve the two factors of the left
+
+// flags[0] == 1: grid_a holds φ, grid_b the right hand side (and vice versa)
+fn field(idx: u32) -> f32 {
+    if uniforms.flags[0] == 1 { return grid_a[idx]; }
+    return grid_b[idx];
+}
+
+fn store_rhs(idx: u32, value: f32) {
+    if uniforms.flags[0] == 1 { grid_b[idx] = value; }
+    else { grid_a[idx] = value; }
+}
+
+// the explicit part of μ, stabilised: A (φ³ - φ) - S φ
+fn explicit_potential(idx: u32) -> f32 {
+    let phi = field(idx);
+    return (uniforms.timestep[1] * ((phi * phi * phi) - phi)) - (uniforms.timestep[2] * phi);
+}
+
+// one side of the stencil over the explicit potential as (weight * neighbour, weight)
+// a zero flux face contributes nothing, which is what keeps the mean of φ fixed
+fn side(inside: bool, idx: u32, kind: u32, wrap_idx: u32, weight: f32) -> vec2<f32> {
+    if inside { return vec2<f32>(explicit_potential(idx) * weight, weight); }
+    if kind == BC_PERIODIC { return vec2<f32>(explicit_potential(wrap_idx) * weight, weight); }
+    return vec2<f32>(0.0, 0.0);
+}
+
+// same order as implicit.wgsl's stencil()
+fn stencil(gid: vec3<u32>, idx: u32) -> vec2<f32> {
+    let dims = uniforms.dims;
+    var terms = side(gid.x > 0, idx - 1, uniforms.bc_kind_lo.x, idx + dims[0] - 1, uniforms.diffusion[1]);
+    terms += side(gid.x + 1 < dims[0], idx + 1, uniforms.bc_kind_hi.x, idx - gid.x, uniforms.diffusion[1]);
+    terms += side(gid.y > 0, idx - dims[0], uniforms.bc_kind_lo.y, idx + ((dims[1] - 1) * dims[0]), uniforms.diffusion[2]);
+    terms += side(gid.y + 1 < dims[1], idx + dims[0], uniforms.bc_kind_hi.y, idx - (gid.y * dims[0]), uniforms.diffusion[2]);
+    terms += side(gid.z > 0, idx - dims[3], uniforms.bc_kind_lo.z, idx + ((dims[2] - 1) * dims[3]), uniforms.diffusion[3]);
+    terms += side(gid.z + 1 < dims[2], idx + dims[3], uniforms.bc_kind_hi.z, idx - (gid.z * dims[3]), uniforms.diffusion[3]);
+    return terms;
+}
+
+// RIGHT HAND SIDE: φ + dt M L (A (φ³ - φ) - S φ)
+@compute @workgroup_size(group_x, group_y, group_z)
+fn rhs(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if gid.x >= uniforms.dims[0] || gid.y >= uniforms.dims[1] || gid.z >= uniforms.dims[2] { return; }
+    let idx = gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3]);
+    let terms = stencil(gid, idx);
+    let explicit_weight = uniforms.diffusion[0] * uniforms.timestep[0];
+    store_rhs(idx, field(idx) + (explicit_weight * (terms.x - (terms.y * explicit_potential(idx)))));
+}
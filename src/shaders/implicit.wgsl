@@ -0,0 +1,130 @@
+struct Uniforms{
+    mid_window: vec4<u32>,
+    dims: vec4<u32>, // i, j, k, k stride
+    bounding_box: vec4<i32>,
+    cam_pos: vec4<f32>,
+    forward: vec4<f32>,
+    centre: vec4<f32>, // some k*forward
+    up: vec4<f32>,
+    right: vec4<f32>, // [3] horizontal scaling factor (not needed for up, 1:1)
+    timestep: vec4<f32>, // [0] dt, [1] theta
+    seed: vec4<u32>,
+    flags: vec4<u32>, // [0] reada flag 1 true, 0 false, [1] sweep colour
+    bc_kind_lo: vec4<u32>, // boundary kind per axis at index 0 (0 Neumann, 1 periodic, 2 Dirichlet)
+    bc_kind_hi: vec4<u32>, // ... at index dims - 1
+    bc_value_lo: vec4<f32>, // Dirichlet values
+    bc_value_hi: vec4<f32>,
+    diffusion: vec4<f32> // [0] D, [1..3] 1 / dx^2 per axis
+}
+// BINDINGS
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(0) @binding(1)
+var<storage, read_write> grid_a: array<f32>;
+
+@group(0) @binding(2)
+var<storage, read_write> grid_b: array<f32>;
+
+// CONSTS
+const group_x: u32 = 8;
+const group_y: u32 = 4;
+const group_z: u32 = 8;
+
+// BOUNDARY CONDITIONS, see world/boundary.rs
+const BC_NEUMANN: u32 = 0; // face drops out of the stencil (zero flux)
+const BC_PERIODIC: u32 = 1; // neighbour is the voxel on the opposite face
+const BC_DIRICHLET: u32 = 2; // neighbour holds a fixed value
+
+// IMPLICIT THETA SCHEME, see world/integrator.rs
+// (1 - θ dt D L) u' = (1 + (1 - θ) dt D L) u
+// rhs() writes the right hand side into the other buffer, then sweep() relaxes u' in place on the current one,
+// red voxels ((x + y + z) even) then black (then a third colour on odd periodic axes), so every update reads only colours not being written
+
+// flags[0] == 1: grid_a holds the field, grid_b the right hand side (and vice versa)
+fn field(idx: u32) -> f32 {
+    if uniforms.flags[0] == 1 { return grid_a[idx]; }
+    return grid_b[idx];
+}
+
+fn store_field(idx: u32, value: f32) {
+    if uniforms.flags[0] == 1 { grid_a[idx] = value; }
+    else { grid_b[idx] = value; }
+}
+
+fn rhs_value(idx: u32) -> f32 {
+    if uniforms.flags[0] == 1 { return grid_b[idx]; }
+    return grid_a[idx];
+}
+
+fn store_rhs(idx: u32, value: f32) {
+    if uniforms.flags[0] == 1 { grid_b[idx] = value; }
+    else { grid_a[idx] = value; }
+}
+
+// one side of the stencil as (weight * neighbour, weight)
+// idx is only read when inside, a Neumann face contributes nothing so L stays conservative
+fn side(inside: bool, idx: u32, kind: u32, value: f32, wrap_idx: u32, weight: f32) -> vec2<f32> {
+    if inside { return vec2<f32>(field(idx) * weight, weight); }
+    switch kind {
+        case BC_PERIODIC: { return vec2<f32>(field(wrap_idx) * weight, weight); }
+        case BC_DIRICHLET: { return vec2<f32>(value * weight, weight); }
+        default: { return vec2<f32>(0.0, 0.0); } // BC_NEUMANN
+    }
+}
+
+// L u = stencil.x - stencil.y * u at the centre
+// stencil.x sums weighted neighbours, stencil.y their weights (1 / dx^2 per axis)
+fn stencil(gid: vec3<u32>, idx: u32) -> vec2<f32> {
+    let dims = uniforms.dims;
+    var terms = side(gid.x > 0, idx - 1, uniforms.bc_kind_lo.x, uniforms.bc_value_lo.x, idx + dims[0] - 1, uniforms.diffusion[1]);
+    terms += side(gid.x + 1 < dims[0], idx + 1, uniforms.bc_kind_hi.x, uniforms.bc_value_hi.x, idx - gid.x, uniforms.diffusion[1]);
+    terms += side(gid.y > 0, idx - dims[0], uniforms.bc_kind_lo.y, uniforms.bc_value_lo.y, idx + ((dims[1] - 1) * dims[0]), uniforms.diffusion[2]);
+    terms += side(gid.y + 1 < dims[1], idx + dims[0], uniforms.bc_kind_hi.y, uniforms.bc_value_hi.y, idx - (gid.y * dims[0]), uniforms.diffusion[2]);
+    terms += side(gid.z > 0, idx - dims[3], uniforms.bc_kind_lo.z, uniforms.bc_value_lo.z, idx + ((dims[2] - 1) * dims[3]), uniforms.diffusion[3]);
+    terms += side(gid.z + 1 < dims[2], idx + dims[3], uniforms.bc_kind_hi.z, uniforms.bc_value_hi.z, idx - (gid.z * dims[3]), uniforms.diffusion[3]);
+    return terms;
+}
+
+fn in_domain(gid: vec3<u32>) -> bool {
+    return gid.x < uniforms.dims[0] && gid.y < uniforms.dims[1] && gid.z < uniforms.dims[2];
+}
+
+// SWEEP COLOURS, see world/integrator.rs's sweep_colour()
+// each axis counts its parity, or 2 at the last voxel of a periodic axis of odd length (which would otherwise share
+// its colour with the voxel it wraps to), summed modulo 3 when there is such an axis and modulo 2 (red-black) otherwise
+fn sweep_colour(gid: vec3<u32>, dims: vec3<u32>) -> u32 {
+    var sum = 0u;
+    var colours = 2u;
+    for (var axis = 0u; axis < 3u; axis++) {
+        var c = gid[axis] % 2u;
+        if uniforms.bc_kind_lo[axis] == BC_PERIODIC && dims[axis] % 2u == 1u {
+            colours = 3u;
+            if gid[axis] + 1u == dims[axis] { c = 2u; }
+        }
+        sum += c;
+    }
+    return sum % colours;
+}
+
+// RIGHT HAND SIDE: u + (1 - θ) dt D L u
+@compute @workgroup_size(group_x, group_y, group_z)
+fn rhs(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if !in_domain(gid) { return; }
+    let idx = gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3]);
+    let c_i = field(idx);
+    let terms = stencil(gid, idx);
+    let explicit_weight = (1.0 - uniforms.timestep[1]) * uniforms.diffusion[0] * uniforms.timestep[0];
+    store_rhs(idx, c_i + (explicit_weight * (terms.x - (terms.y * c_i))));
+}
+
+// ONE COLOUR OF A GAUSS-SEIDEL SWEEP: u = (b + θ dt D neighbours) / (1 + θ dt D weights)
+@compute @workgroup_size(group_x, group_y, group_z)
+fn sweep(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if !in_domain(gid) || sweep_colour(gid, uniforms.dims.xyz) != uniforms.flags[1] { return; }
+    let idx = gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3]);
+    let terms = stencil(gid, idx);
+    let implicit_weight = uniforms.timestep[1] * uniforms.diffusion[0] * uniforms.timestep[0];
+    store_field(idx, (rhs_value(idx) + (implicit_weight * terms.x)) / (1.0 + (implicit_weight * terms.y)));
+}
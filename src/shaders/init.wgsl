@@ -8,8 +8,13 @@ struct Uniforms{
     up: vec4<f32>,
     right: vec4<f32>, // [3] horizontal scaling factor (not needed for up, 1:1)
     timestep: vec4<f32>, // [0] time in seconds
-    seed: vec4<f32>,
-    flags: vec4<u32> // [0] reada flag 1 true, 0 false
+    seed: vec4<u32>, // [0] rand seed, [1] species
+    flags: vec4<u32>, // [0] reada flag 1 true, 0 false
+    bc_kind_lo: vec4<u32>, // boundary kind per axis at index 0 (0 Neumann, 1 periodic, 2 Dirichlet)
+    bc_kind_hi: vec4<u32>, // ... at index dims - 1
+    bc_value_lo: vec4<f32>, // Dirichlet values
+    bc_value_hi: vec4<f32>,
+    diffusion: vec4<f32> // [0] D, [1..3] 1 / dx^2 per axis
 }
 
 // CONSTS
@@ -25,15 +30,17 @@ var<uniform> uniforms: Uniforms;
 var<storage, read_write> grid_a: array<f32>;
 
 // RANDOM INIT OF GRID_A
+// one uniform in (0, 1) per voxel, counted by its index and species under (seed, INIT_STREAM), see rng.wgsl (prepended by Compute)
+// so the field is a pure function of the seed, and InitialCondition::field draws exactly the same one
 @compute @workgroup_size(group_x, group_y, group_z)
 fn init(@builtin(global_invocation_id) gid: vec3<u32>) {
     // OOB check for when grid_n % group_n != 0 (ceiling to access all cells)
     if gid.x >= uniforms.dims[0] || gid.y >= uniforms.dims[1] || gid.z >= uniforms.dims[2] {return;}
 
-    let prn: f32 = fract(sin(uniforms.seed[0] + f32(gid.x) + f32(gid.y) + f32(gid.z)) * 523969.3496);
-
     let idx: u32 = gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3]);
 
+    let prn: f32 = uniform01(philox4x32(vec4<u32>(idx, uniforms.seed[1], 0u, 0u), vec2<u32>(uniforms.seed[0], INIT_STREAM)).x);
+
     grid_a[idx] = prn;
 }
 
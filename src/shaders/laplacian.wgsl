@@ -8,8 +8,13 @@ struct Uniforms{
     up: vec4<f32>,
     right: vec4<f32>, // [3] horizontal scaling factor (not needed for up, 1:1)
     timestep: vec4<f32>, // [0] time in seconds
-    seed: vec4<f32>,
-    flags: vec4<u32> // [0] reada flag 1 true, 0 false
+    seed: vec4<u32>,
+    flags: vec4<u32>, // [0] reada flag 1 true, 0 false
+    bc_kind_lo: vec4<u32>, // boundary kind per axis at index 0 (0 Neumann, 1 periodic, 2 Dirichlet)
+    bc_kind_hi: vec4<u32>, // ... at index dims - 1
+    bc_value_lo: vec4<f32>, // Dirichlet values
+    bc_value_hi: vec4<f32>,
+    diffusion: vec4<f32> // [0] D, [1..3] 1 / dx^2 per axis
 }
 // BINDINGS
 
@@ -37,9 +42,23 @@ const shared_z: u32 = group_z + 2;
 var<workgroup> shared_cells: array<f32, shared_x * shared_y * shared_z>;
 
 
+// BOUNDARY CONDITIONS, see world/boundary.rs
+const BC_NEUMANN: u32 = 0; // halo takes the edge voxel (zero flux)
+const BC_PERIODIC: u32 = 1; // halo takes the voxel on the opposite face
+const BC_DIRICHLET: u32 = 2; // halo holds a fixed value
+
+// halo value just outside the grid, edge is the voxel on the face, wrapped the one on the opposite face
+fn boundary_halo(kind: u32, value: f32, edge: f32, wrapped: f32) -> f32 {
+    switch kind {
+        case BC_PERIODIC: { return wrapped; }
+        case BC_DIRICHLET: { return value; }
+        default: { return edge; } // BC_NEUMANN
+    }
+}
+
 // COLLABORATIVE LOADING AND LAPLACIAN STENCIL
 // WORKGROUP DIMS + 2 = SHARED MEMORY CUBOID WITH HALO
-// TODO: ADD PERIODIC SWAP FOR NEUMANN ON KEYPRESS RUST-SIDE, LOGIC DIVERGENCE HERE
+// FACE HALOS COME FROM boundary_halo(), SO ONE PIPELINE COVERS EVERY BOUNDARY CONDITION
 @compute @workgroup_size(group_x, group_y, group_z)
 fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invocation_id) loc: vec3<u32>, @builtin(workgroup_id) gro: vec3<u32>){
     // ALL THREADS IN DOMAIN TO FETCH INNER CELLS
@@ -56,10 +75,10 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
             // FACE THREADS TO FETCH HALOS
             // X HALOS
             if gid.x == uniforms.dims[0] - 1 { // catches both short tiles and clean tiles
-                let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch x in global
+                let wrap_idx: u32 = global_y_stride + global_z_stride; // fetch x = 0 in global for periodic bound
 
-                let halo_cell = grid_a[idx];
-                shared_cells[loc.x + 2 + ((loc.y + 1) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write x + 2 in shared for Neumann bound
+                let halo_cell = boundary_halo(uniforms.bc_kind_hi.x, uniforms.bc_value_hi.x, middle_voxel, grid_a[wrap_idx]);
+                shared_cells[loc.x + 2 + ((loc.y + 1) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write x + 2 in shared for boundary
             
             }
             else if loc.x == group_x - 1  && gid.x + 1 < uniforms.dims[0] { // catches clean tiles before final x bound tile which still needs halo
@@ -69,7 +88,8 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
                 shared_cells[loc.x + 2 + ((loc.y + 1) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write x + 2 in shared
             
             }
-            else if loc.x == 0 && gid.x > 0  { // && gid.x < uniforms.dims[0] already assured
+            // lower halo checked separately: the last voxel on an axis can also be the first in its tile
+            if loc.x == 0 && gid.x > 0  { // && gid.x < uniforms.dims[0] already assured
                 let idx: u32 = gid.x - 1 + global_y_stride + global_z_stride; // fetch x - 1  in global
 
                 let halo_cell = grid_a[idx];
@@ -77,18 +97,18 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
                 
             }
             else if gid.x == 0  {
-                let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch x  in global
+                let wrap_idx: u32 = uniforms.dims[0] - 1 + global_y_stride + global_z_stride; // fetch x = dims - 1 in global for periodic bound
 
-                let halo_cell = grid_a[idx];
-                shared_cells[loc.x + ((loc.y + 1) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write y in shared for Neumann bound
+                let halo_cell = boundary_halo(uniforms.bc_kind_lo.x, uniforms.bc_value_lo.x, middle_voxel, grid_a[wrap_idx]);
+                shared_cells[loc.x + ((loc.y + 1) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write x in shared for boundary
                 }
 
                 // Y HALOS
             if gid.y == uniforms.dims[1] - 1 { // catches both short tiles and clean tiles
-                let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch y in global
+                let wrap_idx: u32 = gid.x + global_z_stride; // fetch y = 0 in global for periodic bound
 
-                let halo_cell = grid_a[idx];
-                shared_cells[loc.x + 1 + ((loc.y + 2) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write y + 2 in shared for Neumann bound
+                let halo_cell = boundary_halo(uniforms.bc_kind_hi.y, uniforms.bc_value_hi.y, middle_voxel, grid_a[wrap_idx]);
+                shared_cells[loc.x + 1 + ((loc.y + 2) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write y + 2 in shared for boundary
             
             }
             else if loc.y == group_y - 1  && gid.y + 1 < uniforms.dims[1] { // catches clean tiles before final y bound tile which still needs halo
@@ -99,7 +119,8 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
                 shared_cells[loc.x + 1 + ((loc.y + 2) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write y + 2 in shared
             
             }
-            else if loc.y == 0 && gid.y > 0  { // && gid.y < uniforms.dims[1] already assured
+            // lower halo checked separately: the last voxel on an axis can also be the first in its tile
+            if loc.y == 0 && gid.y > 0  { // && gid.y < uniforms.dims[1] already assured
                 let global_y_stride = (gid.y -1) * uniforms.dims[0]; // recompute y - 1 stride
                 let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch y - 1  in global
 
@@ -108,17 +129,17 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
                 
             }
             else if gid.y == 0  {
-                let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch y  in global
+                let wrap_idx: u32 = gid.x + ((uniforms.dims[1] - 1) * uniforms.dims[0]) + global_z_stride; // fetch y = dims - 1 in global for periodic bound
 
-                let halo_cell = grid_a[idx];
-                shared_cells[loc.x + 1 + (loc.y * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write y in shared for Neumann bound
+                let halo_cell = boundary_halo(uniforms.bc_kind_lo.y, uniforms.bc_value_lo.y, middle_voxel, grid_a[wrap_idx]);
+                shared_cells[loc.x + 1 + (loc.y * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write y in shared for boundary
                 }
                 // Z HALOS
             if gid.z == uniforms.dims[2] - 1 { // catches both short tiles and clean tiles
-                let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch z in global
+                let wrap_idx: u32 = gid.x + global_y_stride; // fetch z = 0 in global for periodic bound
 
-                let halo_cell = grid_a[idx];
-                shared_cells[loc.x + 1 + ((loc.y + 1) * shared_x) + ((loc.z + 2) * shared_x * shared_y)] = halo_cell; // write z + 2 in shared for Neumann bound
+                let halo_cell = boundary_halo(uniforms.bc_kind_hi.z, uniforms.bc_value_hi.z, middle_voxel, grid_a[wrap_idx]);
+                shared_cells[loc.x + 1 + ((loc.y + 1) * shared_x) + ((loc.z + 2) * shared_x * shared_y)] = halo_cell; // write z + 2 in shared for boundary
             
             }
             else if loc.z == group_z - 1  && gid.z + 1 < uniforms.dims[2] { // catches clean tiles before final y bound tile which still needs halo
@@ -129,7 +150,8 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
                 shared_cells[loc.x + 1 + ((loc.y + 1) * shared_x) + ((loc.z + 2) * shared_x * shared_y)] = halo_cell; // write z + 2 in shared
             
             }
-            else if loc.z == 0 && gid.z > 0 { // && gid.z < uniforms.dims[2] already assured
+            // lower halo checked separately: the last voxel on an axis can also be the first in its tile
+            if loc.z == 0 && gid.z > 0 { // && gid.z < uniforms.dims[2] already assured
                 let global_z_stride = (gid.z - 1) * uniforms.dims[3]; // recompute z + 1 stride
                 let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch z - 1  in global
 
@@ -138,10 +160,10 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
                 
             }
             else if gid.z == 0  {
-                let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch z in global
+                let wrap_idx: u32 = gid.x + global_y_stride + ((uniforms.dims[2] - 1) * uniforms.dims[3]); // fetch z = dims - 1 in global for periodic bound
 
-                let halo_cell = grid_a[idx];
-                shared_cells[loc.x + 1 + ((loc.y + 1) *shared_x) + (loc.z * shared_x * shared_y)] = halo_cell; // write z in shared for Neumann bound
+                let halo_cell = boundary_halo(uniforms.bc_kind_lo.z, uniforms.bc_value_lo.z, middle_voxel, grid_a[wrap_idx]);
+                shared_cells[loc.x + 1 + ((loc.y + 1) *shared_x) + (loc.z * shared_x * shared_y)] = halo_cell; // write z in shared for boundary
                 }
         } // READ GRID A
         else {
@@ -153,10 +175,10 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
             // FACE THREADS TO FETCH HALOS
             // X HALOS
             if gid.x == uniforms.dims[0] - 1 { // catches both short tiles and clean tiles
-                let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch x in global
+                let wrap_idx: u32 = global_y_stride + global_z_stride; // fetch x = 0 in global for periodic bound
 
-                let halo_cell = grid_b[idx];
-                shared_cells[loc.x + 2 + ((loc.y + 1) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write x + 2 in shared for Neumann bound
+                let halo_cell = boundary_halo(uniforms.bc_kind_hi.x, uniforms.bc_value_hi.x, middle_voxel, grid_b[wrap_idx]);
+                shared_cells[loc.x + 2 + ((loc.y + 1) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write x + 2 in shared for boundary
             
             }
             else if loc.x == group_x - 1  && gid.x + 1 < uniforms.dims[0] { // catches clean tiles before final x bound tile which still needs halo
@@ -166,7 +188,8 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
                 shared_cells[loc.x + 2 + ((loc.y + 1) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write x + 2 in shared
             
             }
-            else if loc.x == 0 && gid.x > 0  { // && gid.x < uniforms.dims[0] already assured
+            // lower halo checked separately: the last voxel on an axis can also be the first in its tile
+            if loc.x == 0 && gid.x > 0  { // && gid.x < uniforms.dims[0] already assured
                 let idx: u32 = gid.x - 1 + global_y_stride + global_z_stride; // fetch x - 1  in global
 
                 let halo_cell = grid_b[idx];
@@ -174,19 +197,19 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
                 
             }
             else if gid.x == 0  {
-                let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch x  in global
+                let wrap_idx: u32 = uniforms.dims[0] - 1 + global_y_stride + global_z_stride; // fetch x = dims - 1 in global for periodic bound
 
-                let halo_cell = grid_b[idx];
-                shared_cells[loc.x + ((loc.y + 1) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write y in shared for Neumann bound
+                let halo_cell = boundary_halo(uniforms.bc_kind_lo.x, uniforms.bc_value_lo.x, middle_voxel, grid_b[wrap_idx]);
+                shared_cells[loc.x + ((loc.y + 1) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write x in shared for boundary
                 }
 
 
                 // Y HALOS
             if gid.y == uniforms.dims[1] - 1 { // catches both short tiles and clean tiles
-                let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch y in global
+                let wrap_idx: u32 = gid.x + global_z_stride; // fetch y = 0 in global for periodic bound
 
-                let halo_cell = grid_b[idx];
-                shared_cells[loc.x + 1 + ((loc.y + 2) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write y + 2 in shared for Neumann bound
+                let halo_cell = boundary_halo(uniforms.bc_kind_hi.y, uniforms.bc_value_hi.y, middle_voxel, grid_b[wrap_idx]);
+                shared_cells[loc.x + 1 + ((loc.y + 2) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write y + 2 in shared for boundary
             
             }
             else if loc.y == group_y - 1  && gid.y + 1 < uniforms.dims[1] { // catches clean tiles before final y bound tile which still needs halo
@@ -197,7 +220,8 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
                 shared_cells[loc.x + 1 + ((loc.y + 2) * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write y + 2 in shared
             
             }
-            else if loc.y == 0 && gid.y > 0  { // && gid.y < uniforms.dims[1] already assured
+            // lower halo checked separately: the last voxel on an axis can also be the first in its tile
+            if loc.y == 0 && gid.y > 0  { // && gid.y < uniforms.dims[1] already assured
                 let global_y_stride = (gid.y -1) * uniforms.dims[0]; // recompute y - 1 stride
                 let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch y - 1  in global
 
@@ -206,18 +230,18 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
                 
             }
             else if gid.y == 0  {
-                let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch y  in global
+                let wrap_idx: u32 = gid.x + ((uniforms.dims[1] - 1) * uniforms.dims[0]) + global_z_stride; // fetch y = dims - 1 in global for periodic bound
 
-                let halo_cell = grid_b[idx];
-                shared_cells[loc.x + 1 + (loc.y * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write y in shared for Neumann bound
+                let halo_cell = boundary_halo(uniforms.bc_kind_lo.y, uniforms.bc_value_lo.y, middle_voxel, grid_b[wrap_idx]);
+                shared_cells[loc.x + 1 + (loc.y * shared_x) + ((loc.z + 1) * shared_x * shared_y)] = halo_cell; // write y in shared for boundary
                 }
 
                 // Z HALOS
             if gid.z == uniforms.dims[2] - 1 { // catches both short tiles and clean tiles
-                let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch z in global
+                let wrap_idx: u32 = gid.x + global_y_stride; // fetch z = 0 in global for periodic bound
 
-                let halo_cell = grid_b[idx];
-                shared_cells[loc.x + 1 + ((loc.y + 1) * shared_x) + ((loc.z + 2) * shared_x * shared_y)] = halo_cell; // write z + 2 in shared for Neumann bound
+                let halo_cell = boundary_halo(uniforms.bc_kind_hi.z, uniforms.bc_value_hi.z, middle_voxel, grid_b[wrap_idx]);
+                shared_cells[loc.x + 1 + ((loc.y + 1) * shared_x) + ((loc.z + 2) * shared_x * shared_y)] = halo_cell; // write z + 2 in shared for boundary
             
             }
             else if loc.z == group_z - 1  && gid.z + 1 < uniforms.dims[2] { // catches clean tiles before final y bound tile which still needs halo
@@ -228,7 +252,8 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
                 shared_cells[loc.x + 1 + ((loc.y + 1) * shared_x) + ((loc.z + 2) * shared_x * shared_y)] = halo_cell; // write z + 2 in shared
             
             }
-            else if loc.z == 0 && gid.z > 0 { // && gid.z < uniforms.dims[2] already assured
+            // lower halo checked separately: the last voxel on an axis can also be the first in its tile
+            if loc.z == 0 && gid.z > 0 { // && gid.z < uniforms.dims[2] already assured
                 let global_z_stride = (gid.z - 1) * uniforms.dims[3]; // recompute z + 1 stride
                 let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch z - 1  in global
 
@@ -237,10 +262,10 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
                 
             }
             else if gid.z == 0  {
-                let idx: u32 = gid.x + global_y_stride + global_z_stride; // fetch z in global
+                let wrap_idx: u32 = gid.x + global_y_stride + ((uniforms.dims[2] - 1) * uniforms.dims[3]); // fetch z = dims - 1 in global for periodic bound
 
-                let halo_cell = grid_b[idx];
-                shared_cells[loc.x + 1 + ((loc.y + 1) *shared_x) + (loc.z * shared_x * shared_y)] = halo_cell; // write z in shared for Neumann bound
+                let halo_cell = boundary_halo(uniforms.bc_kind_lo.z, uniforms.bc_value_lo.z, middle_voxel, grid_b[wrap_idx]);
+                shared_cells[loc.x + 1 + ((loc.y + 1) *shared_x) + (loc.z * shared_x * shared_y)] = halo_cell; // write z in shared for boundary
                 }
         } // READ GRID_B
     }
@@ -264,8 +289,11 @@ fn laplacian(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invoc
         let c_i_zmin = shared_cells[ idx_zmin ];
         let c_i_zplus = shared_cells[ idx_zplus ];
 
-        // LAPLACIAN x^2 == 1.0, D = 1.0
-        let next_c_i = c_i + ((1.0 * uniforms.timestep[0] / 1.0) * ((c_i_xmin + c_i_xplus + c_i_ymin + c_i_yplus + c_i_zmin + c_i_zplus) - (6.0 * c_i)));
+        // LAPLACIAN, D and 1 / dx^2 per axis from uniforms.diffusion
+        let laplacian = ((c_i_xmin + c_i_xplus - (2.0 * c_i)) * uniforms.diffusion[1])
+            + ((c_i_ymin + c_i_yplus - (2.0 * c_i)) * uniforms.diffusion[2])
+            + ((c_i_zmin + c_i_zplus - (2.0 * c_i)) * uniforms.diffusion[3]);
+        let next_c_i = c_i + ((uniforms.diffusion[0] * uniforms.timestep[0]) * laplacian);
         if uniforms.flags[0] == 1 {
             grid_b[idx] = next_c_i;
         }
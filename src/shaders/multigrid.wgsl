@@ -0,0 +1,200 @@
+struct Uniforms{
+    dims: vec4<u32>, // this level's i, j, k, k stride
+    coarse_dims: vec4<u32>, // the next coarser level's, zero on the coarsest
+    ratio: vec4<u32>, // fine voxels per coarse voxel per axis
+    weights: vec4<f32>, // [0] alpha, [1..3] beta / dx^2 per axis
+    dirichlet: vec4<f32>, // Dirichlet face weight per axis, see world/multigrid.rs
+    flags: vec4<u32>, // [0] sweep colour
+    bc_kind_lo: vec4<u32>, // boundary kind per axis at index 0 (0 Neumann, 1 periodic, 2 Dirichlet)
+    bc_kind_hi: vec4<u32>, // ... at index dims - 1
+    bc_value_lo: vec4<f32>, // Dirichlet values, zero below the finest level
+    bc_value_hi: vec4<f32>
+}
+// BINDINGS
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(0) @binding(1)
+var<storage, read_write> u: array<f32>; // solution (finest) or error (coarser)
+
+@group(0) @binding(2)
+var<storage, read_write> f: array<f32>; // right hand side
+
+@group(0) @binding(3)
+var<storage, read_write> r: array<f32>; // residual
+
+@group(0) @binding(4)
+var<storage, read_write> coarse_u: array<f32>;
+
+@group(0) @binding(5)
+var<storage, read_write> coarse_f: array<f32>;
+
+@group(0) @binding(6)
+var<storage, read_write> partials: array<f32>; // one sum of squares per workgroup
+
+// CONSTS AND SHARED MEMORY
+const group_x: u32 = 8;
+const group_y: u32 = 4;
+const group_z: u32 = 8;
+const group_size: u32 = group_x * group_y * group_z;
+
+var<workgroup> squares: array<f32, group_size>;
+
+// BOUNDARY CONDITIONS, see world/boundary.rs
+const BC_NEUMANN: u32 = 0; // face drops out of the stencil (zero flux)
+const BC_PERIODIC: u32 = 1; // neighbour is the voxel on the opposite face
+const BC_DIRICHLET: u32 = 2; // neighbour holds a fixed value
+
+// GEOMETRIC MULTIGRID FOR (α - β L) u = f, see world/multigrid.rs and backend_admin/cpu/multigrid.rs
+
+// one side of the stencil as (weight * neighbour, weight), as in implicit.wgsl
+fn side(inside: bool, idx: u32, kind: u32, value: f32, wrap_idx: u32, axis: u32) -> vec2<f32> {
+    let weight = uniforms.weights[axis + 1];
+    if inside { return vec2<f32>(u[idx] * weight, weight); }
+    switch kind {
+        case BC_PERIODIC: { return vec2<f32>(u[wrap_idx] * weight, weight); }
+        case BC_DIRICHLET: {
+            let face_weight = weight * uniforms.dirichlet[axis];
+            return vec2<f32>(value * face_weight, face_weight);
+        }
+        default: { return vec2<f32>(0.0, 0.0); } // BC_NEUMANN
+    }
+}
+
+fn stencil(gid: vec3<u32>, idx: u32) -> vec2<f32> {
+    let dims = uniforms.dims;
+    var terms = side(gid.x > 0, idx - 1, uniforms.bc_kind_lo.x, uniforms.bc_value_lo.x, idx + dims[0] - 1, 0);
+    terms += side(gid.x + 1 < dims[0], idx + 1, uniforms.bc_kind_hi.x, uniforms.bc_value_hi.x, idx - gid.x, 0);
+    terms += side(gid.y > 0, idx - dims[0], uniforms.bc_kind_lo.y, uniforms.bc_value_lo.y, idx + ((dims[1] - 1) * dims[0]), 1);
+    terms += side(gid.y + 1 < dims[1], idx + dims[0], uniforms.bc_kind_hi.y, uniforms.bc_value_hi.y, idx - (gid.y * dims[0]), 1);
+    terms += side(gid.z > 0, idx - dims[3], uniforms.bc_kind_lo.z, uniforms.bc_value_lo.z, idx + ((dims[2] - 1) * dims[3]), 2);
+    terms += side(gid.z + 1 < dims[2], idx + dims[3], uniforms.bc_kind_hi.z, uniforms.bc_value_hi.z, idx - (gid.z * dims[3]), 2);
+    return terms;
+}
+
+fn in_domain(gid: vec3<u32>, dims: vec4<u32>) -> bool {
+    return gid.x < dims[0] && gid.y < dims[1] && gid.z < dims[2];
+}
+
+// tree reduction of squares into partials, every invocation of the workgroup must arrive here
+fn reduce_squares(value: f32, local_index: u32, wid: vec3<u32>, groups: vec3<u32>) {
+    squares[local_index] = value * value;
+    workgroupBarrier();
+    for (var stride = group_size / 2; stride > 0; stride = stride / 2) {
+        if local_index < stride {
+            squares[local_index] += squares[local_index + stride];
+        }
+        workgroupBarrier();
+    }
+    if local_index == 0 {
+        partials[wid.x + (wid.y * groups.x) + (wid.z * groups.x * groups.y)] = squares[0];
+    }
+}
+
+// SWEEP COLOURS, see world/integrator.rs's sweep_colour()
+// each axis counts its parity, or 2 at the last voxel of a periodic axis of odd length (which would otherwise share
+// its colour with the voxel it wraps to), summed modulo 3 when there is such an axis and modulo 2 (red-black) otherwise
+fn sweep_colour(gid: vec3<u32>, dims: vec3<u32>) -> u32 {
+    var sum = 0u;
+    var colours = 2u;
+    for (var axis = 0u; axis < 3u; axis++) {
+        var c = gid[axis] % 2u;
+        if uniforms.bc_kind_lo[axis] == BC_PERIODIC && dims[axis] % 2u == 1u {
+            colours = 3u;
+            if gid[axis] + 1u == dims[axis] { c = 2u; }
+        }
+        sum += c;
+    }
+    return sum % colours;
+}
+
+// ONE COLOUR OF A RED-BLACK GAUSS-SEIDEL SWEEP: u = (f + neighbours) / (α + weights)
+@compute @workgroup_size(group_x, group_y, group_z)
+fn relax(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if !in_domain(gid, uniforms.dims) || sweep_colour(gid, uniforms.dims.xyz) != uniforms.flags[0] { return; }
+    let idx = gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3]);
+    let terms = stencil(gid, idx);
+    u[idx] = (f[idx] + terms.x) / (uniforms.weights[0] + terms.y);
+}
+
+// r = f - (α - β L) u, with one sum of r^2 per workgroup
+@compute @workgroup_size(group_x, group_y, group_z)
+fn residual(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) wid: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    var value = 0.0;
+    if in_domain(gid, uniforms.dims) {
+        let idx = gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3]);
+        let terms = stencil(gid, idx);
+        value = f[idx] - (((uniforms.weights[0] + terms.y) * u[idx]) - terms.x);
+        r[idx] = value;
+    }
+    reduce_squares(value, local_index, wid, groups);
+}
+
+// one sum of f^2 per workgroup, for ||f||
+@compute @workgroup_size(group_x, group_y, group_z)
+fn norm(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) wid: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    var value = 0.0;
+    if in_domain(gid, uniforms.dims) {
+        value = f[gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3])];
+    }
+    reduce_squares(value, local_index, wid, groups);
+}
+
+// COARSE f = MEAN OF THE RESIDUAL OVER EACH COARSE VOXEL'S CHILDREN, COARSE u = 0
+// dispatched over the coarse level
+@compute @workgroup_size(group_x, group_y, group_z)
+fn restrict_residual(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if !in_domain(gid, uniforms.coarse_dims) { return; }
+    let ratio = uniforms.ratio;
+    var sum = 0.0;
+    for (var z = gid.z * ratio.z; z < (gid.z + 1) * ratio.z; z++) {
+        for (var y = gid.y * ratio.y; y < (gid.y + 1) * ratio.y; y++) {
+            for (var x = gid.x * ratio.x; x < (gid.x + 1) * ratio.x; x++) {
+                sum += r[x + (y * uniforms.dims[0]) + (z * uniforms.dims[3])];
+            }
+        }
+    }
+    let coarse_idx = gid.x + (gid.y * uniforms.coarse_dims[0]) + (gid.z * uniforms.coarse_dims[3]);
+    coarse_f[coarse_idx] = sum / f32(ratio.x * ratio.y * ratio.z);
+    coarse_u[coarse_idx] = 0.0;
+}
+
+// coarse index c on an axis, resolved one voxel past either face, -1 for a Dirichlet halo (zero error)
+fn coarse_index(c: i32, dim: u32, kind_lo: u32, kind_hi: u32) -> i32 {
+    if c >= 0 && c < i32(dim) { return c; }
+    let kind = select(kind_hi, kind_lo, c < 0);
+    switch kind {
+        case BC_PERIODIC: { return (c + i32(dim)) % i32(dim); }
+        case BC_DIRICHLET: { return -1; }
+        default: { return clamp(c, 0, i32(dim) - 1); } // BC_NEUMANN
+    }
+}
+
+fn coarse_at(c: vec3<i32>) -> f32 {
+    let dims = uniforms.coarse_dims;
+    let x = coarse_index(c.x, dims[0], uniforms.bc_kind_lo.x, uniforms.bc_kind_hi.x);
+    let y = coarse_index(c.y, dims[1], uniforms.bc_kind_lo.y, uniforms.bc_kind_hi.y);
+    let z = coarse_index(c.z, dims[2], uniforms.bc_kind_lo.z, uniforms.bc_kind_hi.z);
+    if x < 0 || y < 0 || z < 0 { return 0.0; }
+    return coarse_u[u32(x) + (u32(y) * dims[0]) + (u32(z) * dims[3])];
+}
+
+// u += TRILINEAR INTERPOLATION OF THE COARSE CORRECTION
+// dispatched over this (fine) level
+@compute @workgroup_size(group_x, group_y, group_z)
+fn prolong(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if !in_domain(gid, uniforms.dims) { return; }
+    let position = ((vec3<f32>(gid) + 0.5) / vec3<f32>(uniforms.ratio.xyz)) - 0.5; // in coarse voxel centres
+    let lower = floor(position);
+    let t = position - lower;
+    let c = vec3<i32>(lower);
+    let x0 = mix(coarse_at(c), coarse_at(c + vec3<i32>(1, 0, 0)), t.x);
+    let x1 = mix(coarse_at(c + vec3<i32>(0, 1, 0)), coarse_at(c + vec3<i32>(1, 1, 0)), t.x);
+    let x2 = mix(coarse_at(c + vec3<i32>(0, 0, 1)), coarse_at(c + vec3<i32>(1, 0, 1)), t.x);
+    let x3 = mix(coarse_at(c + vec3<i32>(0, 1, 1)), coarse_at(c + vec3<i32>(1, 1, 1)), t.x);
+    let idx = gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3]);
+    u[idx] += mix(mix(x0, x1, t.y), mix(x2, x3, t.y), t.z);
+}
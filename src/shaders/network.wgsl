@@ -0,0 +1,111 @@
+struct Uniforms{
+    mid_window: vec4<u32>,
+    dims: vec4<u32>, // i, j, k, k stride
+    bounding_box: vec4<i32>,
+    cam_pos: vec4<f32>,
+    forward: vec4<f32>,
+    centre: vec4<f32>, // some k*forward
+    up: vec4<f32>,
+    right: vec4<f32>, // [3] horizontal scaling factor (not needed for up, 1:1)
+    timestep: vec4<f32>, // [0] dt
+    seed: vec4<u32>,
+    flags: vec4<u32>, // [0] reada flag 1 true, 0 false, [1] species, [2] plane stride in f32s
+    bc_kind_lo: vec4<u32>,
+    bc_kind_hi: vec4<u32>,
+    bc_value_lo: vec4<f32>,
+    bc_value_hi: vec4<f32>,
+    diffusion: vec4<f32>
+}
+
+// see world/network.rs, MAX_REACTANTS = 3, MAX_SPECIES = 8, MAX_REACTIONS = 32
+struct MassAction{
+    rate: vec4<f32>, // [0] k
+    reactants: vec4<u32>, // [0..2] reactant species
+    orders: vec4<u32>, // [0..2] their coefficients, 0 for unused slots
+    net: array<vec4<f32>, 2> // products - reactants per species
+}
+
+struct Network{
+    counts: vec4<u32>, // [0] reactions
+    reactions: array<MassAction, 32>
+}
+// BINDINGS
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+// every species' plane, flags[2] apart
+@group(0) @binding(1)
+var<storage, read_write> grid_a: array<f32>;
+
+@group(0) @binding(2)
+var<storage, read_write> grid_b: array<f32>;
+
+@group(0) @binding(3)
+var<uniform> network: Network;
+
+// CONSTS
+const group_x: u32 = 8;
+const group_y: u32 = 4;
+const group_z: u32 = 8;
+const max_species: u32 = 8;
+const max_reactants: u32 = 3;
+
+// x^n by squaring, in the order of f32::powi() so both backends round alike
+fn int_pow(x: f32, n: u32) -> f32 {
+    var result = 1.0;
+    var base = x;
+    var bits = n;
+    loop {
+        if (bits & 1u) == 1u { result *= base; }
+        bits = bits >> 1u;
+        if bits == 0u { break; }
+        base *= base;
+    }
+    return result;
+}
+
+// k * c^n per reactant, multiplied in the order of MassAction::flux()
+fn flux(r: u32, c: ptr<function, array<f32, max_species>>) -> f32 {
+    var result = network.reactions[r].rate.x;
+    for (var i = 0u; i < max_reactants; i++) {
+        result *= int_pow((*c)[network.reactions[r].reactants[i]], network.reactions[r].orders[i]);
+    }
+    return result;
+}
+
+fn current(idx: u32) -> f32 {
+    if uniforms.flags[0] == 1 { return grid_a[idx]; }
+    return grid_b[idx];
+}
+
+fn set_current(idx: u32, value: f32) {
+    if uniforms.flags[0] == 1 { grid_a[idx] = value; }
+    else { grid_b[idx] = value; }
+}
+
+// ONE EXPLICIT EULER STEP OF THE NETWORK, IN PLACE ON EVERY SPECIES' CURRENT PLANE
+// c += dt Σ net * flux, summed reaction by reaction as ReactionNetwork::rates()
+@compute @workgroup_size(group_x, group_y, group_z)
+fn react_network(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if gid.x >= uniforms.dims[0] || gid.y >= uniforms.dims[1] || gid.z >= uniforms.dims[2] { return; }
+    let idx = gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3]);
+    let species = uniforms.flags[1];
+    let stride = uniforms.flags[2];
+
+    var c: array<f32, max_species>;
+    var rates: array<f32, max_species>;
+    for (var s = 0u; s < species; s++) {
+        c[s] = current((s * stride) + idx);
+    }
+    for (var r = 0u; r < network.counts[0]; r++) {
+        let f = flux(r, &c);
+        for (var s = 0u; s < species; s++) {
+            rates[s] += network.reactions[r].net[s / 4][s % 4] * f;
+        }
+    }
+    let dt = uniforms.timestep[0];
+    for (var s = 0u; s < species; s++) {
+        set_current((s * stride) + idx, c[s] + (dt * rates[s]));
+    }
+}
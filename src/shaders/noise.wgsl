@@ -0,0 +1,84 @@
+struct Uniforms{
+    mid_window: vec4<u32>,
+    dims: vec4<u32>, // i, j, k, k stride
+    bounding_box: vec4<i32>,
+    cam_pos: vec4<f32>,
+    forward: vec4<f32>,
+    centre: vec4<f32>, // some k*forward
+    up: vec4<f32>,
+    right: vec4<f32>, // [3] horizontal scaling factor (not needed for up, 1:1)
+    timestep: vec4<f32>, // [0] dt, [1..3] amplitude per axis (conserved) or [1] per voxel (non-conserved)
+    seed: vec4<u32>, // [0] rand seed, [1] sub-step, [2] step low word, [3] step high word
+    flags: vec4<u32>, // [0] reada flag 1 true, 0 false, [1] 1 conserved
+    bc_kind_lo: vec4<u32>, // boundary kind per axis at index 0 (0 Neumann, 1 periodic, 2 Dirichlet)
+    bc_kind_hi: vec4<u32>, // ... at index dims - 1
+    bc_value_lo: vec4<f32>,
+    bc_value_hi: vec4<f32>,
+    diffusion: vec4<f32>
+}
+// BINDINGS
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(0) @binding(1)
+var<storage, read_write> grid_a: array<f32>;
+
+@group(0) @binding(2)
+var<storage, read_write> grid_b: array<f32>;
+
+// CONSTS
+const group_x: u32 = 8;
+const group_y: u32 = 4;
+const group_z: u32 = 8;
+
+// BOUNDARY CONDITIONS, see world/boundary.rs
+const BC_PERIODIC: u32 = 1;
+
+// four standard normals for a voxel of this step, normals4() is in rng.wgsl, prepended by Compute
+fn normals(idx: u32) -> vec4<f32> {
+    return normals4(vec4<u32>(idx, uniforms.seed[1], uniforms.seed[2], uniforms.seed[3]), vec2<u32>(uniforms.seed[0], NOISE_STREAM));
+}
+
+// flags[0] == 1: grid_a holds the current field (and vice versa)
+fn kick_current(idx: u32, kick: f32) {
+    if uniforms.flags[0] == 1 { grid_a[idx] += kick; }
+    else { grid_b[idx] += kick; }
+}
+
+// THERMAL NOISE, see world/noise.rs
+// non-conserved: amplitude * normal per voxel
+// conserved: each voxel owns the face to its upper neighbour along every axis and moves amplitude * normal across it,
+// faces on non-periodic boundaries carry nothing; same order as cpu/noise.rs
+@compute @workgroup_size(group_x, group_y, group_z)
+fn noise(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let dims = uniforms.dims;
+    if gid.x >= dims[0] || gid.y >= dims[1] || gid.z >= dims[2] { return; }
+    let idx = gid.x + (gid.y * dims[0]) + (gid.z * dims[3]);
+    if uniforms.flags[1] == 0 {
+        kick_current(idx, uniforms.timestep[1] * normals(idx).x);
+        return;
+    }
+
+    let own = normals(idx);
+    let p = vec3<u32>(gid.x, gid.y, gid.z);
+    let strides = vec3<u32>(1u, dims[0], dims[3]);
+    var kick = 0.0;
+    for (var axis = 0; axis < 3; axis++) {
+        let periodic = uniforms.bc_kind_lo[axis] == BC_PERIODIC;
+        let amplitude = uniforms.timestep[axis + 1];
+        var incoming = 0.0;
+        if p[axis] > 0 {
+            incoming = amplitude * normals(idx - strides[axis])[axis];
+        }
+        else if periodic {
+            incoming = amplitude * normals(idx + ((dims[axis] - 1) * strides[axis]))[axis];
+        }
+        var outgoing = 0.0;
+        if p[axis] + 1 < dims[axis] || periodic {
+            outgoing = amplitude * own[axis];
+        }
+        kick += incoming - outgoing;
+    }
+    kick_current(idx, kick);
+}
@@ -0,0 +1,229 @@
+struct Grid {
+    dims: vec4<u32>, // voxels (= cells) per axis, [3] particle count
+    extent: vec4<f32>, // box in nm per axis, [3] neighbour cutoff in nm
+    spacing: vec4<f32>, // voxel spacing in nm per axis, [3] dt in µs
+    seed: vec4<u32>, // [0] rand seed, [2] step lo, [3] step hi
+    periodic: vec4<u32> // 1 where the axis wraps, 0 where its faces reflect, [3] cells per scan chunk
+}
+
+// world/brownian_motion.rs's Particles, one per struct, 48 bytes
+struct Particle {
+    position: vec3<f32>, // nm
+    kind: u32,
+    velocity: vec3<f32>, // nm/µs
+    diffusivity: f32, // nm²/µs
+    image: vec3<i32>, // periodic wraps per axis
+    id: u32 // index in Particles, which keys its draws and maps sorted copies back
+}
+
+// BINDINGS
+
+@group(0) @binding(0)
+var<uniform> grid: Grid;
+
+@group(0) @binding(1)
+var<storage, read_write> particles: array<Particle>; // in Particles' order, the ones advance() steps
+
+@group(0) @binding(2)
+var<storage, read_write> sorted: array<Particle>; // copies grouped by cell, written by scatter()
+
+@group(0) @binding(3)
+var<storage, read_write> particle_cells: array<u32>; // cell of each particle, from count_cells()
+
+@group(0) @binding(4)
+var<storage, read_write> cell_counts: array<atomic<u32>>; // particles per cell, cells + 1 entries, the last always 0
+
+@group(0) @binding(5)
+var<storage, read_write> cell_start: array<u32>; // exclusive scan of cell_counts, cell c's particles are sorted[cell_start[c]..cell_start[c + 1]]
+
+@group(0) @binding(6)
+var<storage, read_write> chunk_sums: array<u32>; // SCAN_CHUNKS totals of the scan's chunks
+
+@group(0) @binding(7)
+var<storage, read_write> neighbours: array<u32>; // particles within the cutoff of each sorted particle
+
+// CONSTS
+const PARTICLE_GROUP: u32 = 64;
+const CELL_GROUP: u32 = 256;
+const SCAN_CHUNKS: u32 = 65536; // = CELL_GROUP², so scan_sums() is one workgroup of one serial run each
+
+// SPATIAL HASH
+// Cells are the voxels of the grid, so a particle's cell is the voxel it sits in, and cells are indexed as voxels
+// sort = clear_cells, count_cells, scan_chunks, scan_sums, add_offsets, clear_cells, scatter, one dispatch each:
+// a counting sort, whose exclusive scan runs as serial runs over chunks, a serial run over the chunk totals and a pass
+// adding each chunk's offset back, every pass a plain loop, the sorted order within a cell is whatever the atomics gave
+// Dispatches wider than 65535 workgroups go 2D, linear() flattens them back
+
+fn linear(gid: vec3<u32>, groups: vec3<u32>, size: u32) -> u32 {
+    return gid.x + gid.y * groups.x * size;
+}
+
+fn cell_count() -> u32 {
+    return grid.dims.x * grid.dims.y * grid.dims.z;
+}
+
+// voxel the position sits in, clamped into the grid, as Particles::voxel()
+fn cell_coords(position: vec3<f32>) -> vec3<u32> {
+    return min(vec3<u32>(max(position / grid.spacing.xyz, vec3<f32>(0.0))), grid.dims.xyz - 1u);
+}
+
+fn cell_index(coords: vec3<u32>) -> u32 {
+    return coords.x + coords.y * grid.dims.x + coords.z * grid.dims.x * grid.dims.y;
+}
+
+// BROWNIAN STEP, see Particles::step()
+// drift v dt plus sqrt(2 D dt) ξ, ξ counted by particle id and step under (seed, BROWNIAN_STREAM), see rng.wgsl (prepended by Compute)
+// periodic axes wrap and count images, the others fold back off their walls and turn the velocity round
+
+@compute @workgroup_size(PARTICLE_GROUP)
+fn advance(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    let i = linear(gid, groups, PARTICLE_GROUP);
+    if i >= grid.dims.w { return; }
+    var p = particles[i];
+    let dt = grid.spacing.w;
+    let xi = normals4(vec4<u32>(p.id, 0u, grid.seed.z, grid.seed.w), vec2<u32>(grid.seed.x, BROWNIAN_STREAM));
+    let amplitude = sqrt(2.0 * p.diffusivity * dt);
+    for (var axis = 0u; axis < 3u; axis++) {
+        let length = grid.extent[axis];
+        let x = p.position[axis] + p.velocity[axis] * dt + amplitude * xi[axis];
+        if grid.periodic[axis] == 1u {
+            let wraps = floor(x / length);
+            p.image[axis] += i32(wraps);
+            p.position[axis] = clamp(x - wraps * length, 0.0, length);
+        }
+        else {
+            var folded = x - 2.0 * length * floor(x / (2.0 * length));
+            if folded > length {
+                folded = 2.0 * length - folded;
+                p.velocity[axis] = -p.velocity[axis];
+            }
+            p.position[axis] = folded;
+        }
+    }
+    particles[i] = p;
+}
+
+// COUNTING SORT
+
+@compute @workgroup_size(CELL_GROUP)
+fn clear_cells(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    let c = linear(gid, groups, CELL_GROUP);
+    if c > cell_count() { return; }
+    atomicStore(&cell_counts[c], 0u);
+}
+
+@compute @workgroup_size(PARTICLE_GROUP)
+fn count_cells(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    let i = linear(gid, groups, PARTICLE_GROUP);
+    if i >= grid.dims.w { return; }
+    let cell = cell_index(cell_coords(particles[i].position));
+    particle_cells[i] = cell;
+    atomicAdd(&cell_counts[cell], 1u);
+}
+
+// exclusive scan of one chunk of cells + 1 counts, its total into chunk_sums
+@compute @workgroup_size(CELL_GROUP)
+fn scan_chunks(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    let chunk = linear(gid, groups, CELL_GROUP);
+    if chunk >= SCAN_CHUNKS { return; }
+    let start = chunk * grid.periodic.w;
+    let end = min(start + grid.periodic.w, cell_count() + 1u);
+    var running = 0u;
+    for (var c = start; c < end; c++) {
+        let count = atomicLoad(&cell_counts[c]);
+        cell_start[c] = running;
+        running += count;
+    }
+    chunk_sums[chunk] = running;
+}
+
+var<workgroup> run_totals: array<u32, CELL_GROUP>;
+
+// exclusive scan of the chunk totals, one workgroup: CELL_GROUP serial runs, then a serial scan of their totals
+@compute @workgroup_size(CELL_GROUP)
+fn scan_sums(@builtin(local_invocation_index) run: u32) {
+    var running = 0u;
+    for (var k = 0u; k < CELL_GROUP; k++) {
+        let chunk = run * CELL_GROUP + k;
+        let total = chunk_sums[chunk];
+        chunk_sums[chunk] = running;
+        running += total;
+    }
+    run_totals[run] = running;
+    workgroupBarrier();
+    if run == 0u {
+        var offset = 0u;
+        for (var k = 0u; k < CELL_GROUP; k++) {
+            let total = run_totals[k];
+            run_totals[k] = offset;
+            offset += total;
+        }
+    }
+    workgroupBarrier();
+    for (var k = 0u; k < CELL_GROUP; k++) {
+        chunk_sums[run * CELL_GROUP + k] += run_totals[run];
+    }
+}
+
+@compute @workgroup_size(CELL_GROUP)
+fn add_offsets(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    let c = linear(gid, groups, CELL_GROUP);
+    if c > cell_count() { return; }
+    cell_start[c] += chunk_sums[c / grid.periodic.w];
+}
+
+// cell_counts cleared again, each particle takes the next slot of its cell
+@compute @workgroup_size(PARTICLE_GROUP)
+fn scatter(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    let i = linear(gid, groups, PARTICLE_GROUP);
+    if i >= grid.dims.w { return; }
+    let cell = particle_cells[i];
+    let slot = atomicAdd(&cell_counts[cell], 1u);
+    sorted[cell_start[cell] + slot] = particles[i];
+}
+
+// NEIGHBOURS
+// Particles of the 27 cells around a sorted particle's own within the cutoff, itself excluded, nearest periodic image
+// The cutoff is at most the smallest voxel spacing (checked by GpuParticles), so no pair can sit further than one cell apart
+// Periodic axes of fewer than 3 cells visit each cell once, reflecting axes skip cells past their walls
+
+@compute @workgroup_size(PARTICLE_GROUP)
+fn count_neighbours(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    let j = linear(gid, groups, PARTICLE_GROUP);
+    if j >= grid.dims.w { return; }
+    let position = sorted[j].position;
+    let own = vec3<i32>(cell_coords(position));
+    let cutoff2 = grid.extent.w * grid.extent.w;
+    var count = 0u;
+    for (var dz = -1; dz <= 1; dz++) {
+        for (var dy = -1; dy <= 1; dy++) {
+            for (var dx = -1; dx <= 1; dx++) {
+                let offset = vec3<i32>(dx, dy, dz);
+                var cell = own + offset;
+                var inside = true;
+                for (var axis = 0u; axis < 3u; axis++) {
+                    let dims = i32(grid.dims[axis]);
+                    if grid.periodic[axis] == 1u {
+                        if dims < 3 && (offset[axis] < 0 || offset[axis] >= dims) { inside = false; }
+                        cell[axis] = (cell[axis] + dims) % dims;
+                    }
+                    else if cell[axis] < 0 || cell[axis] >= dims { inside = false; }
+                }
+                if !inside { continue; }
+                let c = cell_index(vec3<u32>(cell));
+                for (var k = cell_start[c]; k < cell_start[c + 1u]; k++) {
+                    if k == j { continue; }
+                    var d = sorted[k].position - position;
+                    for (var axis = 0u; axis < 3u; axis++) {
+                        if grid.periodic[axis] == 1u {
+                            let length = grid.extent[axis];
+                            d[axis] -= length * floor(d[axis] / length + 0.5);
+                        }
+                    }
+                    if dot(d, d) < cutoff2 { count += 1u; }
+                }
+            }
+        }
+    }
+    neighbours[j] = count;
+}
@@ -8,8 +8,13 @@ struct Uniforms{
     up: vec4<f32>,
     right: vec4<f32>, // [3] horizontal scaling factor (not needed for up, 1:1)
     timestep: vec4<f32>, // [0] time in seconds
-    seed: vec4<f32>,
-    flags: vec4<u32> // [0] reada flag 1 true, 0 false
+    seed: vec4<u32>,
+    flags: vec4<u32>, // [0] reada flag 1 true, 0 false
+    bc_kind_lo: vec4<u32>, // boundary kind per axis at index 0 (0 Neumann, 1 periodic, 2 Dirichlet)
+    bc_kind_hi: vec4<u32>, // ... at index dims - 1
+    bc_value_lo: vec4<f32>, // Dirichlet values
+    bc_value_hi: vec4<f32>,
+    diffusion: vec4<f32> // [0] D, [1..3] 1 / dx^2 per axis
 }
 
 // BINDINGS
@@ -167,7 +172,7 @@ fn raymarch(@builtin(global_invocation_id) gid: vec3<u32>) {
     // output_tex is rgba8unorm
     // larger accumulate, more R and A
     // I want to be able to see through the voxel cuboid mostly, so accumulate of 1.0 == A 1.0 is not a good idea
-    // the cells were initialised with the fract() of a stretched out sin(seed), so the max of a cell is .99999
+    // the cells were initialised with uniforms in (0, 1) from rng.wgsl, so the max of a cell is just under 1.0
     
     // Using Beer-Lambert
     let o: f32 = 0.6;
@@ -0,0 +1,97 @@
+struct Uniforms{
+    mid_window: vec4<u32>,
+    dims: vec4<u32>, // i, j, k, k stride
+    bounding_box: vec4<i32>,
+    cam_pos: vec4<f32>,
+    forward: vec4<f32>,
+    centre: vec4<f32>, // some k*forward
+    up: vec4<f32>,
+    right: vec4<f32>, // [3] horizontal scaling factor (not needed for up, 1:1)
+    timestep: vec4<f32>, // [0] dt
+    seed: vec4<u32>,
+    flags: vec4<u32>, // [0] reada flag 1 true, 0 false
+    bc_kind_lo: vec4<u32>,
+    bc_kind_hi: vec4<u32>,
+    bc_value_lo: vec4<f32>,
+    bc_value_hi: vec4<f32>,
+    diffusion: vec4<f32>
+}
+
+// see world/reaction.rs, MAX_TERMS = 8 per species
+struct Rates{
+    counts: vec4<u32>, // [0] u terms, [1] v terms
+    terms: array<vec4<f32>, 16> // u's terms then v's, each (coefficient, power of u, power of v, unused)
+}
+// BINDINGS
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(0) @binding(1)
+var<storage, read_write> u_a: array<f32>;
+
+@group(0) @binding(2)
+var<storage, read_write> u_b: array<f32>;
+
+@group(0) @binding(3)
+var<storage, read_write> v_a: array<f32>;
+
+@group(0) @binding(4)
+var<storage, read_write> v_b: array<f32>;
+
+@group(0) @binding(5)
+var<uniform> rates: Rates;
+
+// CONSTS
+const group_x: u32 = 8;
+const group_y: u32 = 4;
+const group_z: u32 = 8;
+const max_terms: u32 = 8;
+
+// x^n by squaring, in the order of f32::powi() so both backends round alike
+fn int_pow(x: f32, n: u32) -> f32 {
+    var result = 1.0;
+    var base = x;
+    var bits = n;
+    loop {
+        if (bits & 1u) == 1u { result *= base; }
+        bits = bits >> 1u;
+        if bits == 0u { break; }
+        base *= base;
+    }
+    return result;
+}
+
+// coefficient * u^a * v^b, multiplied in the order of Term::evaluate()
+fn term(t: vec4<f32>, u: f32, v: f32) -> f32 {
+    return t.x * int_pow(u, u32(t.y)) * int_pow(v, u32(t.z));
+}
+
+fn rate(species: u32, u: f32, v: f32) -> f32 {
+    var sum = 0.0;
+    for (var i = 0u; i < rates.counts[species]; i++) {
+        sum += term(rates.terms[(species * max_terms) + i], u, v);
+    }
+    return sum;
+}
+
+// ONE EXPLICIT EULER REACTION STEP, IN PLACE ON THE CURRENT BUFFERS
+// u += dt R_u(u, v), v += dt R_v(u, v), after both species have diffused
+@compute @workgroup_size(group_x, group_y, group_z)
+fn react(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if gid.x >= uniforms.dims[0] || gid.y >= uniforms.dims[1] || gid.z >= uniforms.dims[2] { return; }
+    let idx = gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3]);
+    let dt = uniforms.timestep[0];
+    if uniforms.flags[0] == 1 {
+        let u = u_a[idx];
+        let v = v_a[idx];
+        u_a[idx] = u + (dt * rate(0, u, v));
+        v_a[idx] = v + (dt * rate(1, u, v));
+    }
+    else {
+        let u = u_b[idx];
+        let v = v_b[idx];
+        u_b[idx] = u + (dt * rate(0, u, v));
+        v_b[idx] = v + (dt * rate(1, u, v));
+    }
+}
@@ -0,0 +1,62 @@
+// RANDOM NUMBERS, see world/rng.rs
+// Philox4x32-10: four random words as a pure function of a 128-bit counter and a 64-bit key
+// Integer arithmetic only, so it matches world/rng.rs bit for bit
+// Prepended to the shaders that draw random numbers by Compute, so nothing here may touch their bindings
+
+const PHILOX_M0: u32 = 0xD2511F53u;
+const PHILOX_M1: u32 = 0xCD9E8D57u;
+const PHILOX_W0: u32 = 0x9E3779B9u;
+const PHILOX_W1: u32 = 0xBB67AE85u;
+const PHILOX_ROUNDS: u32 = 10u;
+
+// STREAMS: key = (rand seed, stream), one per consumer so they never share numbers
+const INIT_STREAM: u32 = 0x696E6974u;
+const NOISE_STREAM: u32 = 0x6E6F6973u;
+const BROWNIAN_STREAM: u32 = 0x6272776Eu;
+
+const TAU: f32 = 6.2831855;
+
+// (high, low) words of the 64-bit product, from 16-bit halves since WGSL has no 64-bit integers
+fn mulhilo(a: u32, b: u32) -> vec2<u32> {
+    let a_lo = a & 0xFFFFu;
+    let a_hi = a >> 16u;
+    let b_lo = b & 0xFFFFu;
+    let b_hi = b >> 16u;
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let cross = (lo_lo >> 16u) + (hi_lo & 0xFFFFu) + lo_hi; // <= 0xFFFFFFFF, never wraps
+    return vec2<u32>((a_hi * b_hi) + (hi_lo >> 16u) + (cross >> 16u), a * b);
+}
+
+fn philox4x32(counter: vec4<u32>, key: vec2<u32>) -> vec4<u32> {
+    var c = counter;
+    var k = key;
+    for (var round = 0u; round < PHILOX_ROUNDS; round++) {
+        if round > 0u { k += vec2<u32>(PHILOX_W0, PHILOX_W1); }
+        let p0 = mulhilo(PHILOX_M0, c.x);
+        let p1 = mulhilo(PHILOX_M1, c.z);
+        c = vec4<u32>(p1.x ^ c.y ^ k.x, p1.y, p0.x ^ c.w ^ k.y, p0.y);
+    }
+    return c;
+}
+
+// top 23 bits as a uniform strictly inside (0, 1), never 0 nor 1, exact so it matches world/rng.rs bit for bit
+fn uniform01(word: u32) -> f32 {
+    return (f32(word >> 9u) + 0.5) / 8388608.0;
+}
+
+// four uniforms in (0, 1) for counter under key
+fn uniforms4(counter: vec4<u32>, key: vec2<u32>) -> vec4<f32> {
+    let words = philox4x32(counter, key);
+    return vec4<f32>(uniform01(words.x), uniform01(words.y), uniform01(words.z), uniform01(words.w));
+}
+
+// four standard normals for counter under key, two Box-Muller pairs
+fn normals4(counter: vec4<u32>, key: vec2<u32>) -> vec4<f32> {
+    let u = uniforms4(counter, key);
+    let r0 = sqrt(-2.0 * log(u.x));
+    let r1 = sqrt(-2.0 * log(u.z));
+    return vec4<f32>(r0 * cos(TAU * u.y), r0 * sin(TAU * u.y), r1 * cos(TAU * u.w), r1 * sin(TAU * u.w));
+}
+
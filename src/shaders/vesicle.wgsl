@@ -0,0 +1,175 @@
+struct Uniforms{
+    mid_window: vec4<u32>,
+    dims: vec4<u32>, // i, j, k, k stride
+    bounding_box: vec4<i32>,
+    cam_pos: vec4<f32>,
+    forward: vec4<f32>,
+    centre: vec4<f32>, // some k*forward
+    up: vec4<f32>,
+    right: vec4<f32>, // [3] horizontal scaling factor (not needed for up, 1:1)
+    timestep: vec4<f32>, // [0] dt
+    seed: vec4<u32>,
+    flags: vec4<u32>, // [0] reada flag 1 true, 0 false
+    bc_kind_lo: vec4<u32>, // boundary kind per axis at index 0 (0 Neumann, 1 periodic), never Dirichlet here
+    bc_kind_hi: vec4<u32>, // ... at index dims - 1
+    bc_value_lo: vec4<f32>,
+    bc_value_hi: vec4<f32>,
+    diffusion: vec4<f32> // [0] mobility γ, [1..3] 1 / dx^2 per axis
+}
+
+struct Membrane {
+    shape: vec4<f32>, // [0] rigidity k, [1] width ε, [2] spontaneous curvature C
+    multipliers: vec4<f32> // [0] λ_V, [1] λ_A, written between the potential and relax passes
+}
+// BINDINGS
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(0) @binding(1)
+var<storage, read_write> grid_a: array<f32>;
+
+@group(0) @binding(2)
+var<storage, read_write> grid_b: array<f32>;
+
+@group(0) @binding(3)
+var<storage, read_write> potential: array<f32>; // δE/δφ
+
+@group(0) @binding(4)
+var<storage, read_write> partials: array<f32>; // PARTIAL_STRIDE sums per workgroup, in VesicleSums::terms() order
+
+@group(0) @binding(5)
+var<uniform> membrane: Membrane;
+
+// CONSTS
+const group_x: u32 = 8;
+const group_y: u32 = 4;
+const group_z: u32 = 8;
+const group_size: u32 = group_x * group_y * group_z;
+const PARTIAL_STRIDE: u32 = 8; // 6 used
+const SQRT_2: f32 = 1.4142135623730951;
+
+// BOUNDARY CONDITIONS, see world/boundary.rs
+const BC_PERIODIC: u32 = 1; // neighbour is the voxel on the opposite face
+
+// VESICLE, see world/vesicle.rs
+// curvature() writes W into the other buffer, potential() δE/δφ into potential with one set of sums per workgroup,
+// the backend solves the multipliers from those and relax() writes φ' into the other buffer
+
+var<workgroup> sums_vah: array<vec4<f32>, group_size>; // v, a, h, h²
+var<workgroup> sums_g: array<vec2<f32>, group_size>; // g, h g
+
+const FIELD: u32 = 0; // φ, the current buffer
+const OTHER: u32 = 1; // W, once curvature() has run
+
+// flags[0] == 1: grid_a holds φ, grid_b is the other buffer (and vice versa)
+fn value(which: u32, idx: u32) -> f32 {
+    if (uniforms.flags[0] == 1) == (which == FIELD) { return grid_a[idx]; }
+    return grid_b[idx];
+}
+
+fn store_other(idx: u32, value: f32) {
+    if uniforms.flags[0] == 1 { grid_b[idx] = value; }
+    else { grid_a[idx] = value; }
+}
+
+// one side of the stencil as (weight * neighbour, weight), a zero flux face contributes nothing
+fn side(which: u32, inside: bool, idx: u32, kind: u32, wrap_idx: u32, weight: f32) -> vec2<f32> {
+    if inside { return vec2<f32>(value(which, idx) * weight, weight); }
+    if kind == BC_PERIODIC { return vec2<f32>(value(which, wrap_idx) * weight, weight); }
+    return vec2<f32>(0.0, 0.0);
+}
+
+// L at idx of φ or W, same order as implicit.wgsl's stencil()
+fn laplacian(which: u32, gid: vec3<u32>, idx: u32) -> f32 {
+    let dims = uniforms.dims;
+    var terms = side(which, gid.x > 0, idx - 1, uniforms.bc_kind_lo.x, idx + dims[0] - 1, uniforms.diffusion[1]);
+    terms += side(which, gid.x + 1 < dims[0], idx + 1, uniforms.bc_kind_hi.x, idx - gid.x, uniforms.diffusion[1]);
+    terms += side(which, gid.y > 0, idx - dims[0], uniforms.bc_kind_lo.y, idx + ((dims[1] - 1) * dims[0]), uniforms.diffusion[2]);
+    terms += side(which, gid.y + 1 < dims[1], idx + dims[0], uniforms.bc_kind_hi.y, idx - (gid.y * dims[0]), uniforms.diffusion[2]);
+    terms += side(which, gid.z > 0, idx - dims[3], uniforms.bc_kind_lo.z, idx + ((dims[2] - 1) * dims[3]), uniforms.diffusion[3]);
+    terms += side(which, gid.z + 1 < dims[2], idx + dims[3], uniforms.bc_kind_hi.z, idx - (gid.z * dims[3]), uniforms.diffusion[3]);
+    return terms.x - (terms.y * value(which, idx));
+}
+
+fn in_domain(gid: vec3<u32>) -> bool {
+    return gid.x < uniforms.dims[0] && gid.y < uniforms.dims[1] && gid.z < uniforms.dims[2];
+}
+
+// the pointwise terms, as Vesicle's methods of the same names
+fn curvature_of(phi: f32, lap: f32) -> f32 {
+    let eps = membrane.shape[1];
+    return (eps * lap) - ((((phi * phi) - 1.0) * (phi + (SQRT_2 * membrane.shape[2] * eps))) / eps);
+}
+
+fn bending_potential(phi: f32, w: f32, lap: f32) -> f32 {
+    let eps = membrane.shape[1];
+    let well = ((3.0 * phi * phi) - 1.0) + (2.0 * SQRT_2 * membrane.shape[2] * eps * phi);
+    return (membrane.shape[0] / eps) * ((eps * lap) - ((well * w) / eps));
+}
+
+fn area_potential(phi: f32, lap: f32) -> f32 {
+    let eps = membrane.shape[1];
+    return (((phi * phi * phi) - phi) / eps) - (eps * lap);
+}
+
+fn area_density(phi: f32, lap: f32) -> f32 {
+    let eps = membrane.shape[1];
+    let well = (phi * phi) - 1.0;
+    return ((well * well) / (4.0 * eps)) - (0.5 * eps * phi * lap);
+}
+
+// CURVATURE: W = ε L φ - (1/ε) (φ² - 1) (φ + √2 C ε)
+@compute @workgroup_size(group_x, group_y, group_z)
+fn curvature(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if !in_domain(gid) { return; }
+    let idx = gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3]);
+    store_other(idx, curvature_of(value(FIELD, idx), laplacian(FIELD, gid, idx)));
+}
+
+// POTENTIAL: δE/δφ from W and L W, with one set of sums per workgroup
+// every invocation reaches the barriers, those outside the grid adding zeros
+@compute @workgroup_size(group_x, group_y, group_z)
+fn bending(@builtin(global_invocation_id) gid: vec3<u32>, @builtin(local_invocation_index) local_index: u32,
+    @builtin(workgroup_id) wid: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    var vah = vec4<f32>(0.0);
+    var g_hg = vec2<f32>(0.0);
+    if in_domain(gid) {
+        let idx = gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3]);
+        let phi = value(FIELD, idx);
+        let lap = laplacian(FIELD, gid, idx);
+        let g = bending_potential(phi, value(OTHER, idx), laplacian(OTHER, gid, idx));
+        let h = area_potential(phi, lap);
+        potential[idx] = g;
+        vah = vec4<f32>((phi + 1.0) / 2.0, area_density(phi, lap), h, h * h);
+        g_hg = vec2<f32>(g, h * g);
+    }
+
+    sums_vah[local_index] = vah;
+    sums_g[local_index] = g_hg;
+    workgroupBarrier();
+    for (var stride = group_size / 2; stride > 0; stride = stride / 2) {
+        if local_index < stride {
+            sums_vah[local_index] += sums_vah[local_index + stride];
+            sums_g[local_index] += sums_g[local_index + stride];
+        }
+        workgroupBarrier();
+    }
+    if local_index == 0 {
+        let base = PARTIAL_STRIDE * (wid.x + (wid.y * groups.x) + (wid.z * groups.x * groups.y));
+        for (var i = 0u; i < 4; i++) { partials[base + i] = sums_vah[0][i]; }
+        partials[base + 4] = sums_g[0].x;
+        partials[base + 5] = sums_g[0].y;
+    }
+}
+
+// RELAX: φ' = φ - dt γ (g + λ_V / 2 + λ_A h)
+@compute @workgroup_size(group_x, group_y, group_z)
+fn relax(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if !in_domain(gid) { return; }
+    let idx = gid.x + (gid.y * uniforms.dims[0]) + (gid.z * uniforms.dims[3]);
+    let phi = value(FIELD, idx);
+    let h = area_potential(phi, laplacian(FIELD, gid, idx));
+    let multipliers = membrane.multipliers;
+    store_other(idx, phi - ((uniforms.timestep[0] * uniforms.diffusion[0]) * (potential[idx] + (0.5 * multipliers[0]) + (multipliers[1] * h))));
+}
@@ -0,0 +1,142 @@
+/// Boundary conditions for the diffusion stencil, per axis and per face
+/// Indexed [axis][face], axis 0, 1, 2 = x, y, z and face 0 = lower (index 0), 1 = upper (index dims - 1)
+/// Each face decides what the stencil sees in the halo cell just outside the grid
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Boundary {
+    #[default]
+    Neumann, // zero flux: halo takes the edge voxel's own value
+    Periodic, // wrap: halo takes the voxel on the opposite face, must be set on both faces of an axis
+    Dirichlet(f32) // fixed value: halo holds the given value, e.g. a reservoir
+}
+
+impl Boundary {
+    /// Kind as read by laplacian.wgsl, see BC_NEUMANN etc there
+    pub fn kind(&self) -> u32 {
+        match self {
+            Boundary::Neumann => 0,
+            Boundary::Periodic => 1,
+            Boundary::Dirichlet(_) => 2
+        }
+    }
+
+    pub fn value(&self) -> f32 {
+        match self {
+            Boundary::Dirichlet(value) => *value,
+            _ => 0.0
+        }
+    }
+
+    pub fn from_kind(kind: u32, value: f32) -> Option<Self> {
+        match kind {
+            0 => Some(Boundary::Neumann),
+            1 => Some(Boundary::Periodic),
+            2 => Some(Boundary::Dirichlet(value)),
+            _ => None
+        }
+    }
+
+    /// Value of the halo cell next to an edge voxel
+    /// wrapped is the voxel on the opposite face, only read when Periodic
+    pub fn halo(&self, edge: f32, wrapped: f32) -> f32 {
+        match self {
+            Boundary::Neumann => edge,
+            Boundary::Periodic => wrapped,
+            Boundary::Dirichlet(value) => *value
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct BoundaryConditions {
+    pub faces: [[Boundary; 2]; 3]
+}
+
+impl BoundaryConditions {
+    /// The same condition on every face
+    pub fn uniform(boundary: Boundary) -> Self {
+        BoundaryConditions {
+            faces: [[boundary; 2]; 3]
+        }
+    }
+
+    /// Sets one face, periodic is applied to both faces of the axis since it cannot be one-sided
+    pub fn set(&mut self, axis: usize, face: usize, boundary: Boundary) {
+        assert!(axis < 3 && face < 2, "Boundary axis should be < 3 and face < 2\n");
+        if boundary == Boundary::Periodic {
+            self.faces[axis] = [Boundary::Periodic; 2];
+        }
+        else {
+            // breaking one side of a periodic pair leaves the other zero flux
+            if self.faces[axis][1 - face] == Boundary::Periodic { self.faces[axis][1 - face] = Boundary::Neumann; }
+            self.faces[axis][face] = boundary;
+        }
+    }
+
+    pub fn set_axis(&mut self, axis: usize, boundary: Boundary) {
+        self.set(axis, 0, boundary);
+        self.set(axis, 1, boundary);
+    }
+
+    /// Kinds and values for the lower (face 0) or upper (face 1) faces as Uniforms vec4s, [3] unused
+    pub fn kinds(&self, face: usize) -> [u32; 4] {
+        [self.faces[0][face].kind(), self.faces[1][face].kind(), self.faces[2][face].kind(), 0]
+    }
+
+    pub fn values(&self, face: usize) -> [f32; 4] {
+        [self.faces[0][face].value(), self.faces[1][face].value(), self.faces[2][face].value(), 0.0]
+    }
+
+    /// These bounds with every Dirichlet face made Neumann, for operators that must not exchange mass with the outside
+    pub fn zero_flux(&self) -> Self {
+        let mut bounds = *self;
+        for face in bounds.faces.iter_mut().flatten() {
+            if let Boundary::Dirichlet(_) = face { *face = Boundary::Neumann; }
+        }
+        bounds
+    }
+
+    /// Neumann -> periodic -> Dirichlet(0) -> Neumann on every face, for runtime toggling
+    pub fn cycle(&self) -> Self {
+        match self.faces[0][0] {
+            Boundary::Neumann => Self::uniform(Boundary::Periodic),
+            Boundary::Periodic => Self::uniform(Boundary::Dirichlet(0.0)),
+            Boundary::Dirichlet(_) => Self::uniform(Boundary::Neumann)
+        }
+    }
+
+    /// Parses a comma separated spec, e.g. "xyz=periodic", "x=periodic,z-=dirichlet:1.0,z+=neumann"
+    /// Axes without a face sign set both faces, anything unspecified stays Neumann
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut bounds = BoundaryConditions::default();
+        for entry in spec.split(',').map(|e| e.trim()).filter(|e| !e.is_empty()) {
+            let (target, condition) = entry.split_once('=').ok_or(format!("Expected <axes>=<condition>, found '{}'", entry))?;
+            let boundary = match condition.split_once(':') {
+                None if condition == "neumann" => Boundary::Neumann,
+                None if condition == "periodic" => Boundary::Periodic,
+                Some(("dirichlet", value)) => Boundary::Dirichlet(value.parse::<f32>().map_err(|e| format!("Bad Dirichlet value '{}': {}", value, e))?),
+                _ => return Err(format!("Unknown boundary condition '{}', expected neumann, periodic or dirichlet:<value>", condition))
+            };
+
+            let (axes, face) = match target.strip_suffix('-') {
+                Some(axes) => (axes, Some(0)),
+                None => match target.strip_suffix('+') {
+                    Some(axes) => (axes, Some(1)),
+                    None => (target, None)
+                }
+            };
+            for axis in axes.chars() {
+                let axis = match axis {
+                    'x' => 0,
+                    'y' => 1,
+                    'z' => 2,
+                    other => return Err(format!("Unknown axis '{}', expected x, y or z", other))
+                };
+                match face {
+                    Some(face) => bounds.set(axis, face, boundary),
+                    None => bounds.set_axis(axis, boundary)
+                }
+            }
+        }
+        Ok(bounds)
+    }
+}
@@ -1,20 +1,217 @@
-use rand::prelude::*;
-use rand_distr::{Normal, Distribution};
+use crate::world::{
+    boundary::{Boundary, BoundaryConditions},
+    rng::{BROWNIAN_STREAM, SCATTER_STREAM, normals, philox4x32, uniform},
+    voxel_grid::{Dims3, P3}};
 
-use crate::components::brownian_motion::BrownianMotion;
-// TODO: write test for randomness
-pub fn brownian_motion_system() {
-    let mut rng = thread_rng();
+/// Point-like particles (lipids, proteins) diffusing through the voxel grid's box by Brownian motion
+/// Positions are in nm from the grid's lower corner, so the box is [0, dims x spacing] on each axis (PhysicalUnits::box_nm())
+/// Each step moves every particle by v dt + sqrt(2 D dt) ξ per axis, v its velocity in nm/µs (zero unless set),
+/// D its own diffusivity in µm²/s (= nm²/µs) and ξ standard normals from world/rng.rs keyed on the run's seed
+/// and counted by particle and clock step, so runs with the same seed reproduce exactly
+/// Walls follow the field's boundary conditions: periodic axes wrap, every other face reflects, turning the velocity back
+/// (Dirichlet values mean nothing to a particle)
+/// Periodic wraps are counted per particle, so msd() measures the unwrapped displacement
+/// Stepped here on the CPU, or by particles.wgsl through GpuParticles, which also sorts them into voxel cells for neighbour lookups
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Particles {
+    pub positions: Vec<P3>, // nm
+    pub velocities: Vec<P3>, // nm/µs
+    pub diffusivity: Vec<f32>, // µm²/s, per particle
+    pub kinds: Vec<u32>, // type, e.g. lipid or protein, for whatever forces act between them
+    pub images: Vec<[i32; 3]>, // periodic wraps per axis, unwrapped = position + images x box
+    origins: Vec<P3>, // unwrapped positions msd() measures from
+    elapsed: f64 // µs stepped since the origins were taken
+}
 
-    let normal = Normal::new(0.0, 1.0).unwrap();
+impl Particles {
+    /// count particles of diffusivity D and kind 0 placed uniformly at random in a box of extent nm, keyed on seed
+    pub fn scatter(count: usize, diffusivity: f32, extent: &P3, seed: u32) -> Self {
+        let mut particles = Particles::default();
+        for particle in 0..count {
+            let words = philox4x32([particle as u32, 0, 0, 0], [seed, SCATTER_STREAM]);
+            particles.add([0, 1, 2].map(|axis| uniform(words[axis]) * extent[axis]), diffusivity, 0);
+        }
+        particles
+    }
+
+    /// Appends a resting particle at position (nm, inside the box) with its own D and kind, measuring its displacement from there
+    /// Returns its index
+    pub fn add(&mut self, position: P3, diffusivity: f32, kind: u32) -> usize {
+        assert!(diffusivity >= 0.0 && diffusivity.is_finite(), "Particle diffusivity should be finite and >= 0.0\n");
+        self.positions.push(position);
+        self.velocities.push([0.0; 3]);
+        self.diffusivity.push(diffusivity);
+        self.kinds.push(kind);
+        self.images.push([0; 3]);
+        self.origins.push(position);
+        self.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn name(&self) -> String {
+        let mean = self.diffusivity.iter().map(|d| *d as f64).sum::<f64>() / self.len().max(1) as f64;
+        format!("{} particles, mean D = {} µm²/s", self.len(), mean)
+    }
 
-    for (mut transform, motion) in query.iter_mut() {
-        let dt = time.delta_secs();
+    /// Parses "<count>" or "<count>:<D µm²/s>", the D None when not given
+    pub fn parse(spec: &str) -> Result<(usize, Option<f32>), String> {
+        let (count, diffusivity) = match spec.split_once(':') {
+            Some((count, diffusivity)) => (count, Some(diffusivity.trim())),
+            None => (spec, None)
+        };
+        let count = count.trim().parse::<usize>().map_err(|e| format!("Bad particle count '{}': {}", count, e))?;
+        let diffusivity = match diffusivity {
+            Some(d) => {
+                let d = d.parse::<f32>().map_err(|e| format!("Bad particle diffusivity '{}': {}", d, e))?;
+                if !(d >= 0.0 && d.is_finite()) { return Err("Particle diffusivity should be finite and >= 0".to_string()); }
+                Some(d)
+            },
+            None => None
+        };
+        Ok((count, diffusivity))
+    }
+
+    /// Moves every particle by one Brownian step of timestep µs, drawn at the clock's step
+    /// extent is the box in nm, bounds the field's boundary conditions, which decide the walls
+    pub fn step(&mut self, timestep: f32, extent: &P3, bounds: &BoundaryConditions, seed: u32, step: u64) {
+        let periodic = [0, 1, 2].map(|axis| bounds.faces[axis][0] == Boundary::Periodic);
+        for particle in 0..self.len() {
+            let xi = normals([particle as u32, 0, step as u32, (step >> 32) as u32], [seed, BROWNIAN_STREAM]);
+            let amplitude = (2.0 * self.diffusivity[particle] * timestep).sqrt();
+            for axis in 0..3 {
+                let x = self.positions[particle][axis] + self.velocities[particle][axis] * timestep + amplitude * xi[axis];
+                self.positions[particle][axis] = if periodic[axis] {
+                    let wraps = (x / extent[axis]).floor();
+                    self.images[particle][axis] += wraps as i32;
+                    (x - wraps * extent[axis]).clamp(0.0, extent[axis])
+                }
+                else {
+                    let (x, turned) = reflect(x, extent[axis]);
+                    if turned { self.velocities[particle][axis] = -self.velocities[particle][axis]; }
+                    x
+                };
+            }
+        }
+        self.stepped(timestep);
+    }
+
+    /// Counts a step taken elsewhere (GpuParticles) towards the time free_msd() expects
+    pub fn stepped(&mut self, timestep: f32) {
+        self.elapsed += timestep as f64;
+    }
+
+    /// Position plus the periodic wraps it has made, nm
+    pub fn unwrapped(&self, particle: usize, extent: &P3) -> P3 {
+        [0, 1, 2].map(|axis| self.positions[particle][axis] + self.images[particle][axis] as f32 * extent[axis])
+    }
+
+    /// Mean squared displacement, nm², of the unwrapped positions since the origins were taken
+    pub fn msd(&self, extent: &P3) -> f64 {
+        if self.is_empty() { return 0.0; }
+        let total: f64 = (0..self.len()).map(|particle| {
+            let position = self.unwrapped(particle, extent);
+            (0..3).map(|axis| (position[axis] as f64 - self.origins[particle][axis] as f64).powi(2)).sum::<f64>()
+        }).sum();
+        total / self.len() as f64
+    }
 
-        let dx = normal.sample(&mut rng) * motion.intensity * dt;
-        let dy = normal.sample(&mut rng) * motion.intensity * dt;
-        let dz = normal.sample(&mut rng) * motion.intensity * dt;
+    /// (6 ⟨D⟩ t, its standard error) in nm²: what msd() is for free 3D Brownian motion over the elapsed time t
+    /// A particle's squared displacement has mean 6 D t and variance 24 (D t)², so msd() lands within a few standard errors
+    /// on periodic axes, and falls short once reflecting walls confine the particles
+    pub fn free_msd(&self) -> (f64, f64) {
+        if self.is_empty() { return (0.0, 0.0); }
+        let n = self.len() as f64;
+        let dt = self.diffusivity.iter().map(|d| *d as f64 * self.elapsed);
+        let mean = dt.clone().map(|dt| 6.0 * dt).sum::<f64>() / n;
+        let error = dt.map(|dt| 24.0 * dt * dt).sum::<f64>().sqrt() / n;
+        (mean, error)
+    }
+
+    /// µs stepped since the origins were taken
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Unwrapped positions msd() measures from, nm
+    pub fn origins(&self) -> &[P3] {
+        &self.origins
+    }
+
+    /// The same particles measuring from saved origins over elapsed µs, e.g. restored from a checkpoint
+    pub fn with_origins(mut self, origins: Vec<P3>, elapsed: f64) -> Self {
+        assert!(origins.len() == self.len(), "One origin per particle\n");
+        self.origins = origins;
+        self.elapsed = elapsed;
+        self
+    }
+
+    /// Measures displacements from the current positions from now on
+    pub fn reset_origins(&mut self, extent: &P3) {
+        self.origins = (0..self.len()).map(|particle| self.unwrapped(particle, extent)).collect();
+        self.elapsed = 0.0;
+    }
+
+    /// Voxel a particle sits in, for coupling to the fields, clamped into the grid
+    /// Also its cell of the spatial hash, whose cells are the voxels
+    pub fn voxel(&self, particle: usize, spacing: &P3, dims: &Dims3) -> [u32; 3] {
+        [0, 1, 2].map(|axis| ((self.positions[particle][axis] / spacing[axis]).max(0.0) as u32).min(dims[axis] - 1))
+    }
+
+    /// Pairs within cutoff nm a particle of uniform density would have on average, (count - 1) 4/3 π r³ / box volume
+    pub fn uniform_neighbours(&self, cutoff: f32, extent: &P3) -> f64 {
+        let volume = extent.iter().map(|l| *l as f64).product::<f64>();
+        self.len().saturating_sub(1) as f64 * 4.0 / 3.0 * std::f64::consts::PI * (cutoff as f64).powi(3) / volume
+    }
+}
+
+/// Folds x back into [0, length] across walls at both ends, as many times as a long step needs
+/// True when it ends up reflected an odd number of times, i.e. moving the other way
+fn reflect(x: f32, length: f32) -> (f32, bool) {
+    let folded = x.rem_euclid(2.0 * length);
+    if folded > length { (2.0 * length - folded, true) } else { (folded, false) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn periodic_msd_matches_free_diffusion() {
+        let extent = [40.0, 30.0, 20.0];
+        let bounds = BoundaryConditions::parse("xyz=periodic").unwrap();
+        let mut particles = Particles::scatter(4000, 0.8, &extent, 7);
+        for step in 0..200 {
+            particles.step(0.5, &extent, &bounds, 7, step);
+        }
+        let (free, error) = particles.free_msd();
+        let msd = particles.msd(&extent);
+        assert!((msd - free).abs() < 4.0 * error, "MSD {} against 6<D>t = {} ± {}", msd, free, error);
+        assert!(particles.images.iter().any(|image| *image != [0; 3]), "Some particle should have wrapped");
+    }
 
-        transform.translation += Vec3::new(dx, dy, dz);
+    #[test]
+    fn reflecting_walls_keep_particles_in_the_box() {
+        let extent = [5.0, 4.0, 3.0];
+        let bounds = BoundaryConditions::default();
+        let mut particles = Particles::scatter(500, 2.0, &extent, 3);
+        for particle in 0..particles.len() {
+            particles.velocities[particle] = [1.5, -2.0, 0.5];
+        }
+        for step in 0..300 {
+            particles.step(0.7, &extent, &bounds, 3, step);
+            for position in &particles.positions {
+                assert!((0..3).all(|axis| (0.0..=extent[axis]).contains(&position[axis])), "{:?} left the box", position);
+            }
+        }
+        assert!(particles.images.iter().all(|image| *image == [0; 3]));
+        let (free, _) = particles.free_msd();
+        assert!(particles.msd(&extent) < free, "Walls should confine the displacement");
     }
-}
\ No newline at end of file
+}
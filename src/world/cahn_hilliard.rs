@@ -0,0 +1,142 @@
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use crate::world::{
+    boundary::{Boundary, BoundaryConditions},
+    field::{VoxelField, voxel_index},
+    multigrid::{Helmholtz, MultigridConfig},
+    voxel_grid::{Dims3, P3}};
+
+/// Cahn-Hilliard phase separation of the field φ, e.g. the local lipid composition of a membrane (-1 one lipid, +1 the other)
+/// Free energy F = ∫ A/4 (φ² - 1)² + κ/2 |∇φ|², chemical potential μ = A (φ³ - φ) - κ L φ
+/// and mass conserving dynamics dφ/dt = M L μ, so domains (rafts) form and coarsen while the mean of φ stays put
+/// Stepped semi-implicitly (stabilised linear splitting): the fourth order and a stabilising linear term implicit,
+/// the double well explicit,
+/// (1 - dt M S L + dt M κ L²) φ' = φ + dt M L (A (φ³ - φ) - S φ)
+/// S >= A keeps every step energy stable whatever dt, and S >= 2 sqrt(κ / (dt M)) lets the operator factor into
+/// (1 - a L)(1 - b L) with a + b = dt M S and a b = dt M κ, so a step is two Helmholtz solves (world/multigrid.rs)
+/// Replaces the diffusion of species 0 while set, D is unused
+/// Dirichlet faces would let mass in or out, so they act as Neumann (zero flux) for Cahn-Hilliard
+/// CPU and GPU implementations live in backend_admin/cpu/cahn_hilliard.rs and shaders/cahn_hilliard.wgsl
+pub const DEFAULT_MOBILITY: f32 = 1.0; // nm²/µs
+pub const DEFAULT_KAPPA: f32 = 2.0; // nm², interfaces are ~ sqrt(2κ / A) wide
+pub const DEFAULT_WELL_DEPTH: f32 = 1.0;
+pub const INITIAL_NOISE: f32 = 0.05; // amplitude of the initial fluctuations about the mean composition
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CahnHilliard {
+    pub mobility: f32, // M, nm²/µs
+    pub kappa: f32, // κ, gradient energy coefficient, nm²
+    pub well_depth: f32, // A, height of the double well
+    pub mean: f32 // composition init seeds about, in (-1, 1) for phase separation
+}
+
+impl Default for CahnHilliard {
+    fn default() -> Self {
+        CahnHilliard {
+            mobility: DEFAULT_MOBILITY,
+            kappa: DEFAULT_KAPPA,
+            well_depth: DEFAULT_WELL_DEPTH,
+            mean: 0.0
+        }
+    }
+}
+
+impl CahnHilliard {
+    pub fn new(mobility: f32, kappa: f32, well_depth: f32) -> Self {
+        assert!(mobility >= 0.0 && kappa >= 0.0 && well_depth >= 0.0, "Mobility, kappa and well depth should be >= 0.0\n");
+        CahnHilliard {
+            mobility: mobility,
+            kappa: kappa,
+            well_depth: well_depth,
+            mean: 0.0
+        }
+    }
+
+    pub fn with_mean(mut self, mean: f32) -> Self {
+        self.mean = mean;
+        self
+    }
+
+    pub fn name(&self) -> String {
+        format!("Cahn-Hilliard M = {}, κ = {}, A = {}", self.mobility, self.kappa, self.well_depth)
+    }
+
+    /// S for a step of timestep, the smallest that is both energy stable and factorable
+    pub fn stabiliser(&self, timestep: f32) -> f32 {
+        let dt_m = timestep * self.mobility;
+        if dt_m <= 0.0 { return self.well_depth; }
+        self.well_depth.max(2.0 * (self.kappa / dt_m).sqrt())
+    }
+
+    /// (1 - a L) then (1 - b L), the two solves of a step, a >= b
+    pub fn factors(&self, timestep: f32) -> [Helmholtz; 2] {
+        let dt_m = timestep * self.mobility;
+        let sum = dt_m * self.stabiliser(timestep);
+        let root = ((sum * sum) - (4.0 * dt_m * self.kappa)).max(0.0).sqrt();
+        let a = (sum + root) / 2.0;
+        let b = if a > 0.0 { (dt_m * self.kappa) / a } else { 0.0 }; // a b = dt M κ without the cancellation of (sum - root) / 2
+        [Helmholtz { alpha: 1.0, beta: a }, Helmholtz { alpha: 1.0, beta: b }]
+    }
+
+    /// Both solves relax to a tighter tolerance than diffusion, since solver error is the only source of mass drift
+    pub fn solver(&self) -> MultigridConfig {
+        MultigridConfig { tolerance: 2.0e-5, ..MultigridConfig::default() }
+    }
+
+    /// mean + uniform noise of amplitude INITIAL_NOISE, in voxel_index order
+    pub fn initial_field(&self, dims: &Dims3, seed: u32) -> Vec<f32> {
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+        let len = (dims[0] * dims[1] * dims[2]) as usize;
+        (0..len).map(|_| self.mean + (INITIAL_NOISE * ((2.0 * rng.random::<f32>()) - 1.0))).collect()
+    }
+
+    /// F over the grid, in units of A nm³
+    /// Gradients are forward differences, wrapping across periodic faces and zero across the others
+    /// Non-increasing from step to step when the scheme is stable, so it doubles as a health check
+    pub fn free_energy(&self, field: &VoxelField, spacing: &P3, bounds: &BoundaryConditions) -> f64 {
+        let dims = field.dims;
+        let volume = (spacing[0] * spacing[1] * spacing[2]) as f64;
+        let (a, kappa) = (self.well_depth as f64, self.kappa as f64);
+        let mut energy = 0.0;
+        for z in 0..dims[2] {
+            for y in 0..dims[1] {
+                for x in 0..dims[0] {
+                    let phi = field.data[voxel_index(&dims, x, y, z)] as f64;
+                    let mut gradient = 0.0;
+                    for (axis, p) in [x, y, z].into_iter().enumerate() {
+                        let next = match (p + 1 < dims[axis], bounds.faces[axis][1]) {
+                            (true, _) => p + 1,
+                            (false, Boundary::Periodic) => 0,
+                            _ => continue
+                        };
+                        let mut n = [x, y, z];
+                        n[axis] = next;
+                        let d = (field.data[voxel_index(&dims, n[0], n[1], n[2])] as f64 - phi) / spacing[axis] as f64;
+                        gradient += d * d;
+                    }
+                    energy += (((a / 4.0) * ((phi * phi) - 1.0).powi(2)) + ((kappa / 2.0) * gradient)) * volume;
+                }
+            }
+        }
+        energy
+    }
+
+    /// Parses "default" or comma separated overrides of it, e.g. "mobility=2,kappa=1.5,depth=1,mean=0.3"
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut model = CahnHilliard::default();
+        for setting in spec.split(',').map(str::trim).filter(|s| !s.is_empty() && *s != "default") {
+            let (key, value) = setting.split_once('=').ok_or(format!("Expected <key>=<value>, got '{}'", setting))?;
+            let value = value.trim().parse::<f32>().map_err(|e| format!("Bad value '{}': {}", value, e))?;
+            match key.trim() {
+                "mobility" => model.mobility = value,
+                "kappa" => model.kappa = value,
+                "depth" => model.well_depth = value,
+                "mean" => model.mean = value,
+                other => return Err(format!("Unknown Cahn-Hilliard setting '{}', expected mobility, kappa, depth or mean", other))
+            }
+        }
+        if !(model.mobility >= 0.0 && model.kappa >= 0.0 && model.well_depth >= 0.0) {
+            return Err("Cahn-Hilliard mobility, kappa and depth should be >= 0".to_string());
+        }
+        Ok(model)
+    }
+}
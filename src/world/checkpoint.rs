@@ -0,0 +1,696 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path
+};
+use crate::world::{
+    boundary::{Boundary, BoundaryConditions},
+    brownian_motion::Particles,
+    cahn_hilliard::CahnHilliard,
+    camera::OrbitalCamera,
+    clock::StepMode,
+    diffusion::Diffusion,
+    field::VoxelField,
+    integrator::{ImplicitSolver, Integrator},
+    multigrid::MultigridConfig,
+    network::ReactionNetwork,
+    noise::ThermalNoise,
+    reaction::{Polynomial, Reaction, ReactionModel, SPECIES, Term},
+    species::{InitialCondition, Species, SpeciesRegistry},
+    vesicle::Vesicle,
+    voxel_grid::{Dims3, P3}};
+
+/// Binary checkpoint layout (all little endian):
+/// MAGIC (8 bytes) | VERSION (u32) | sections...
+/// each section is TAG (4 bytes) | LENGTH (u64, bytes) | payload
+/// Sections are self-describing, so readers skip tags they don't know
+/// and older files load as long as the required sections are present
+/// A reader that skipped a section changing how the run steps would resume a different run, so adding one bumps VERSION:
+/// version 2 added BNDS, DIFF, RCTN, FLD2, SPCS, FLDS, NTWK, CAHN, VSCL, NOIS, CLCK, INTG and PRTC, which version 1 readers refuse
+const MAGIC: &[u8; 8] = b"BOCSCKPT";
+pub const CHECKPOINT_VERSION: u32 = 2;
+
+const TAG_DIMS: &[u8; 4] = b"DIMS"; // 3 * u32
+const TAG_SEED: &[u8; 4] = b"SEED"; // u32, Bridge::rand_seed
+const TAG_PARITY: &[u8; 4] = b"PRTY"; // u8, 1 if ping holds the current field
+const TAG_TIME: &[u8; 4] = b"TIME"; // f64, elapsed sim time
+const TAG_STEP: &[u8; 4] = b"STEP"; // u64, steps taken, optional (0 if absent)
+const TAG_CAMERA: &[u8; 4] = b"CAMR"; // 5 * 3 * f32, c f u r centre
+const TAG_FIELD: &[u8; 4] = b"FELD"; // dims[0] * dims[1] * dims[2] * f32, voxel_index order
+const TAG_BOUNDS: &[u8; 4] = b"BNDS"; // 6 * (u32 kind, f32 value), [axis][face] order, optional (Neumann if absent)
+const TAG_DIFFUSION: &[u8; 4] = b"DIFF"; // 4 * f32, D then dx dy dz, optional (all 1.0 if absent)
+const TAG_REACTION: &[u8; 4] = b"RCTN"; // u32 model, f32 D_v / D_u, then F k (Gray-Scott) or 2 * u32 counts and (f32, u32, u32) terms, optional
+const TAG_FIELD_V: &[u8; 4] = b"FLD2"; // as FELD, species 1, present with RCTN or 2+ species in SPCS
+const TAG_SPECIES: &[u8; 4] = b"SPCS"; // u32 count, then per species u32 name length, name (UTF-8), u32 has D, f32 D,
+// u32 has boundaries, BNDS payload, u32 initial (0 random, 1 constant), f32 constant, optional (u, and v with RCTN, if absent)
+const TAG_FIELDS: &[u8; 4] = b"FLDS"; // u32 species then as FELD, one section per species from 2 on
+const TAG_NETWORK: &[u8; 4] = b"NTWK"; // ReactionNetwork::to_text() as UTF-8, read against SPCS, optional
+const TAG_CAHN_HILLIARD: &[u8; 4] = b"CAHN"; // 4 * f32, mobility, kappa, well depth, mean, optional
+const TAG_VESICLE: &[u8; 4] = b"VSCL"; // 4 * f32 rigidity, width, curvature, mobility, then 2 * u32 volume / area constrained, optional
+// (the constraint targets aren't stored, they are retaken from FELD on resume)
+const TAG_NOISE: &[u8; 4] = b"NOIS"; // f32 kT, u32 1 if conserved, optional
+// (no generator state either, the noise is counted by SEED and STEP)
+const TAG_CLOCK: &[u8; 4] = b"CLCK"; // f32 dt, u32 mode (0 real time, 1 per frame), u32 max steps or steps per frame, optional
+const TAG_INTEGRATOR: &[u8; 4] = b"INTG"; // u32 scheme (0 explicit, 1 implicit), f32 θ, u32 solver (0 Gauss-Seidel, 1 multigrid),
+// u32 iterations, f32 tolerance, u32 max cycles, pre-smooth, post-smooth and coarse sweeps, optional (explicit if absent)
+const TAG_PARTICLES: &[u8; 4] = b"PRTC"; // u32 count, f64 µs elapsed, then per particle 3 * f32 position, 3 * f32 velocity,
+// f32 D, u32 kind, 3 * i32 images, 3 * f32 origin, optional (no particles if absent)
+
+/// Everything needed to resume a run exactly
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub field: VoxelField,
+    pub rand_seed: u32,
+    pub read_ping: bool,
+    pub sim_time: f64,
+    pub step_count: u64,
+    pub camera: CameraBasis,
+    pub boundaries: BoundaryConditions,
+    pub diffusion: Diffusion,
+    pub species: SpeciesRegistry,
+    pub species_fields: Vec<VoxelField>, // species 1 on, field holds species 0
+    pub reaction: Option<Reaction>,
+    pub network: Option<ReactionNetwork>, // indexed as species
+    pub cahn_hilliard: Option<CahnHilliard>,
+    pub vesicle: Option<Vesicle>,
+    pub noise: Option<ThermalNoise>,
+    pub clock: Option<(f32, StepMode)>, // dt in µs and step mode, None from files before CLCK
+    pub integrator: Integrator,
+    pub particles: Option<Particles>
+}
+
+/// OrbitalCamera's basis, which is all of its state that changes at runtime
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CameraBasis {
+    pub c: P3,
+    pub f: P3,
+    pub u: P3,
+    pub r: P3,
+    pub centre: P3
+}
+
+impl CameraBasis {
+    pub fn from_camera(camera: &OrbitalCamera) -> Self {
+        CameraBasis {
+            c: camera.c,
+            f: camera.f,
+            u: camera.u,
+            r: camera.r,
+            centre: camera.centre
+        }
+    }
+
+    pub fn apply_to(&self, camera: &mut OrbitalCamera) {
+        camera.c = self.c;
+        camera.f = self.f;
+        camera.u = self.u;
+        camera.r = self.r;
+        camera.centre = self.centre;
+    }
+}
+
+impl Checkpoint {
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&CHECKPOINT_VERSION.to_le_bytes())?;
+
+        let dims: Vec<u8> = self.field.dims.iter().flat_map(|d| d.to_le_bytes()).collect();
+        write_section(&mut out, TAG_DIMS, &dims)?;
+        write_section(&mut out, TAG_SEED, &self.rand_seed.to_le_bytes())?;
+        write_section(&mut out, TAG_PARITY, &[self.read_ping as u8])?;
+        write_section(&mut out, TAG_TIME, &self.sim_time.to_le_bytes())?;
+        write_section(&mut out, TAG_STEP, &self.step_count.to_le_bytes())?;
+
+        let basis = [self.camera.c, self.camera.f, self.camera.u, self.camera.r, self.camera.centre];
+        let camera: Vec<u8> = basis.iter().flatten().flat_map(|v| v.to_le_bytes()).collect();
+        write_section(&mut out, TAG_CAMERA, &camera)?;
+
+        write_section(&mut out, TAG_BOUNDS, &boundaries_bytes(&self.boundaries))?;
+
+        let diffusion = [self.diffusion.coefficient, self.diffusion.spacing[0], self.diffusion.spacing[1], self.diffusion.spacing[2]];
+        let diffusion: Vec<u8> = diffusion.iter().flat_map(|v| v.to_le_bytes()).collect();
+        write_section(&mut out, TAG_DIFFUSION, &diffusion)?;
+
+        let field: Vec<u8> = self.field.data.iter().flat_map(|v| v.to_le_bytes()).collect();
+        write_section(&mut out, TAG_FIELD, &field)?;
+
+        if self.species_fields.len() + 1 != self.species.len() {
+            return Err("Checkpoint needs one field per species".into());
+        }
+        write_section(&mut out, TAG_SPECIES, &species_bytes(&self.species))?;
+        for (species, field) in self.species_fields.iter().enumerate().map(|(i, f)| (i + 1, f)) {
+            let mut bytes: Vec<u8> = if species == 1 { Vec::new() } else { (species as u32).to_le_bytes().to_vec() };
+            bytes.extend(field.data.iter().flat_map(|v| v.to_le_bytes()));
+            write_section(&mut out, if species == 1 { TAG_FIELD_V } else { TAG_FIELDS }, &bytes)?;
+        }
+
+        if let Some(reaction) = &self.reaction {
+            write_section(&mut out, TAG_REACTION, &reaction_bytes(reaction))?;
+        }
+
+        if let Some(network) = &self.network {
+            write_section(&mut out, TAG_NETWORK, network.to_text().as_bytes())?;
+        }
+
+        if let Some(model) = &self.cahn_hilliard {
+            let model = [model.mobility, model.kappa, model.well_depth, model.mean];
+            let model: Vec<u8> = model.iter().flat_map(|v| v.to_le_bytes()).collect();
+            write_section(&mut out, TAG_CAHN_HILLIARD, &model)?;
+        }
+
+        if let Some(model) = &self.vesicle {
+            write_section(&mut out, TAG_VESICLE, &vesicle_bytes(model))?;
+        }
+
+        if let Some(noise) = &self.noise {
+            let mut bytes = noise.temperature.to_le_bytes().to_vec();
+            bytes.extend((noise.conserved as u32).to_le_bytes());
+            write_section(&mut out, TAG_NOISE, &bytes)?;
+        }
+
+        if let Some((dt, mode)) = self.clock {
+            let (kind, steps) = match mode {
+                StepMode::RealTime { max_steps } => (0u32, max_steps),
+                StepMode::PerFrame(steps) => (1, steps)
+            };
+            let mut bytes = dt.to_le_bytes().to_vec();
+            bytes.extend(kind.to_le_bytes());
+            bytes.extend(steps.to_le_bytes());
+            write_section(&mut out, TAG_CLOCK, &bytes)?;
+        }
+
+        write_section(&mut out, TAG_INTEGRATOR, &integrator_bytes(&self.integrator))?;
+
+        if let Some(particles) = &self.particles {
+            write_section(&mut out, TAG_PARTICLES, &particles_bytes(particles))?;
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let file = File::open(path)?;
+        let mut remaining = file.metadata()?.len(); // bytes after what has been read, bounds each section's claimed length
+        let mut input = BufReader::new(file);
+
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC { return Err("Not a bocs checkpoint".into()); }
+
+        let mut version = [0u8; 4];
+        input.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version > CHECKPOINT_VERSION {
+            return Err(format!("Checkpoint version {} is newer than supported version {}", version, CHECKPOINT_VERSION).into());
+        }
+        remaining = remaining.saturating_sub((MAGIC.len() + 4) as u64);
+
+        let (mut dims, mut seed, mut parity, mut time, mut camera, mut field) = (None, None, None, None, None, None);
+        let (mut step, mut bounds, mut diffusion, mut reaction, mut field_v, mut cahn_hilliard) = (None, None, None, None, None, None);
+        let (mut vesicle, mut noise, mut species, mut network, mut clock) = (None, None, None, None, None);
+        let (mut integrator, mut particles) = (None, None);
+        let mut fields = Vec::new(); // (species, field) from FLDS
+        let mut tag = [0u8; 4];
+        while remaining > 0 { // the file may only end between sections
+            let mut len = [0u8; 8];
+            input.read_exact(&mut tag).and_then(|_| input.read_exact(&mut len))
+                .map_err(|e| format!("Checkpoint truncated inside a section header: {}", e))?;
+            let len = u64::from_le_bytes(len);
+            remaining = remaining.saturating_sub(12);
+            if len > remaining {
+                return Err(format!("Checkpoint section {} claims {} bytes, only {} remain", String::from_utf8_lossy(&tag), len, remaining).into());
+            }
+            remaining -= len;
+            let mut payload = vec![0u8; len as usize];
+            input.read_exact(&mut payload)?;
+
+            match &tag {
+                TAG_DIMS => dims = Some(u32s(&payload, 3)?),
+                TAG_SEED => seed = Some(u32s(&payload, 1)?[0]),
+                TAG_PARITY => parity = Some(*payload.first().ok_or("Empty parity section")? == 1),
+                TAG_TIME => time = Some(f64::from_le_bytes(payload.as_slice().try_into()?)),
+                TAG_STEP => step = Some(u64::from_le_bytes(payload.as_slice().try_into()?)),
+                TAG_CAMERA => camera = Some(f32s(&payload, Some(15))?),
+                TAG_FIELD => field = Some(f32s(&payload, None)?),
+                TAG_BOUNDS => bounds = Some(boundaries(&payload)?),
+                TAG_DIFFUSION => diffusion = Some(f32s(&payload, Some(4))?),
+                TAG_REACTION => reaction = Some(reaction_from(&payload)?),
+                TAG_FIELD_V => field_v = Some(f32s(&payload, None)?),
+                TAG_SPECIES => species = Some(species_from(&payload)?),
+                TAG_FIELDS => {
+                    if payload.len() < 4 { return Err("Malformed checkpoint section".into()); }
+                    fields.push((u32s(&payload[..4], 1)?[0] as usize, f32s(&payload[4..], None)?));
+                },
+                TAG_CAHN_HILLIARD => cahn_hilliard = Some(f32s(&payload, Some(4))?),
+                TAG_VESICLE => vesicle = Some(vesicle_from(&payload)?),
+                TAG_NOISE => noise = Some(noise_from(&payload)?),
+                TAG_NETWORK => network = Some(String::from_utf8(payload)?),
+                TAG_CLOCK => clock = Some(clock_from(&payload)?),
+                TAG_INTEGRATOR => integrator = Some(integrator_from(&payload)?),
+                TAG_PARTICLES => particles = Some(particles_from(&payload)?),
+                _ => {} // unknown section from a newer writer, skip
+            }
+        }
+
+        let dims = dims.ok_or("Checkpoint missing DIMS")?;
+        let dims: Dims3 = [dims[0], dims[1], dims[2]];
+        let voxels = dims.iter().try_fold(1usize, |n, d| n.checked_mul(*d as usize));
+        if dims.contains(&0) || voxels.is_none() { return Err(format!("Checkpoint dims {:?} are empty or too large", dims).into()); }
+        let field = field.ok_or("Checkpoint missing FELD")?;
+        if Some(field.len()) != voxels {
+            return Err("Checkpoint field length does not match its dims".into());
+        }
+        // files from before SPCS hold u, and v while reacting
+        let species = species.unwrap_or_else(|| SpeciesRegistry::default().with_reaction(reaction.is_some()));
+        if reaction.is_some() && species.len() < 2 {
+            return Err("Checkpoint RCTN needs a second species".into());
+        }
+        let network = match network {
+            Some(text) => match ReactionNetwork::parse(&text, &species)? {
+                (network, registry) if registry == species => Some(network),
+                _ => return Err("Checkpoint NTWK declares species SPCS doesn't hold".into())
+            },
+            None => None
+        };
+        fields.extend(field_v.map(|field_v| (1, field_v)));
+        fields.sort_by_key(|(species, _)| *species);
+        if fields.iter().map(|(species, _)| *species).ne(1..species.len()) || fields.iter().any(|(_, f)| f.len() != field.len()) {
+            return Err("Checkpoint FLD2 and FLDS do not match its species".into());
+        }
+        let camera = camera.ok_or("Checkpoint missing CAMR")?;
+        let p3 = |i: usize| -> P3 { [camera[i * 3], camera[i * 3 + 1], camera[i * 3 + 2]] };
+
+        Ok(Checkpoint {
+            field: VoxelField::new(dims, field),
+            rand_seed: seed.ok_or("Checkpoint missing SEED")?,
+            read_ping: parity.unwrap_or(true),
+            sim_time: time.unwrap_or(0.0),
+            step_count: step.unwrap_or(0),
+            camera: CameraBasis {
+                c: p3(0),
+                f: p3(1),
+                u: p3(2),
+                r: p3(3),
+                centre: p3(4)
+            },
+            boundaries: bounds.unwrap_or_default(),
+            diffusion: match diffusion {
+                Some(d) if d[0] >= 0.0 && d.iter().all(|v| v.is_finite()) && d[1..].iter().all(|dx| *dx > 0.0) => Diffusion::new(d[0], [d[1], d[2], d[3]]),
+                Some(_) => return Err("Invalid diffusion coefficient or voxel spacing in checkpoint".into()),
+                None => Diffusion::default()
+            },
+            species: species,
+            species_fields: fields.into_iter().map(|(_, f)| VoxelField::new(dims, f)).collect(),
+            reaction: reaction,
+            network: network,
+            cahn_hilliard: match cahn_hilliard {
+                Some(m) if m.iter().all(|v| v.is_finite()) && m[..3].iter().all(|v| *v >= 0.0) => Some(CahnHilliard::new(m[0], m[1], m[2]).with_mean(m[3])),
+                Some(_) => return Err("Invalid Cahn-Hilliard parameter in checkpoint".into()),
+                None => None
+            },
+            vesicle: vesicle,
+            noise: noise,
+            clock: clock,
+            integrator: integrator.unwrap_or_default(),
+            particles: particles
+        })
+    }
+}
+
+fn write_section(out: &mut impl Write, tag: &[u8; 4], payload: &[u8]) -> std::io::Result<()> {
+    out.write_all(tag)?;
+    out.write_all(&(payload.len() as u64).to_le_bytes())?;
+    out.write_all(payload)
+}
+
+fn boundaries_bytes(bounds: &BoundaryConditions) -> Vec<u8> {
+    bounds.faces.iter().flatten()
+        .flat_map(|b| [b.kind().to_le_bytes(), b.value().to_le_bytes()].concat())
+        .collect()
+}
+
+fn boundaries(payload: &[u8]) -> Result<BoundaryConditions, Box<dyn Error>> {
+    if payload.len() != 6 * 8 { return Err("Malformed checkpoint section".into()); }
+    let mut bounds = BoundaryConditions::default();
+    for (i, b) in payload.chunks_exact(8).enumerate() {
+        let kind = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+        let value = f32::from_le_bytes([b[4], b[5], b[6], b[7]]);
+        bounds.faces[i / 2][i % 2] = Boundary::from_kind(kind, value).ok_or("Unknown boundary kind in checkpoint")?;
+    }
+    Ok(bounds)
+}
+
+fn species_bytes(registry: &SpeciesRegistry) -> Vec<u8> {
+    let mut bytes = (registry.len() as u32).to_le_bytes().to_vec();
+    for species in registry.iter() {
+        bytes.extend((species.name.len() as u32).to_le_bytes());
+        bytes.extend(species.name.as_bytes());
+        bytes.extend((species.diffusion.is_some() as u32).to_le_bytes());
+        bytes.extend(species.diffusion.unwrap_or(0.0).to_le_bytes());
+        bytes.extend((species.boundaries.is_some() as u32).to_le_bytes());
+        bytes.extend(boundaries_bytes(&species.boundaries.unwrap_or_default()));
+        let (kind, value) = match species.initial {
+            InitialCondition::Random => (0u32, 0.0f32),
+            InitialCondition::Constant(value) => (1, value)
+        };
+        bytes.extend(kind.to_le_bytes());
+        bytes.extend(value.to_le_bytes());
+    }
+    bytes
+}
+
+fn species_from(payload: &[u8]) -> Result<SpeciesRegistry, Box<dyn Error>> {
+    let mut rest = payload;
+    let mut take = |len: usize| -> Result<&[u8], Box<dyn Error>> {
+        if rest.len() < len { return Err("Malformed checkpoint section".into()); }
+        let (head, tail) = rest.split_at(len);
+        rest = tail;
+        Ok(head)
+    };
+    let count = u32s(take(4)?, 1)?[0];
+    let mut species = Vec::new();
+    for _ in 0..count {
+        let name_len = u32s(take(4)?, 1)?[0] as usize;
+        let name = std::str::from_utf8(take(name_len)?)?;
+        let mut s = Species::new(name);
+        let [has_diffusion, diffusion] = [take(4)?, take(4)?];
+        if u32s(has_diffusion, 1)?[0] == 1 {
+            let diffusion = f32s(diffusion, Some(1))?[0];
+            if !(diffusion >= 0.0) { return Err("Negative species diffusion in checkpoint".into()); }
+            s = s.with_diffusion(diffusion);
+        }
+        let [has_bounds, bounds] = [take(4)?, take(6 * 8)?];
+        if u32s(has_bounds, 1)?[0] == 1 { s = s.with_boundaries(boundaries(bounds)?); }
+        let [kind, value] = [take(4)?, take(4)?];
+        s = s.with_initial(match u32s(kind, 1)?[0] {
+            0 => InitialCondition::Random,
+            1 => InitialCondition::Constant(f32s(value, Some(1))?[0]),
+            _ => return Err("Unknown initial condition in checkpoint".into())
+        });
+        species.push(s);
+    }
+    Ok(SpeciesRegistry::new(species)?)
+}
+
+fn reaction_bytes(reaction: &Reaction) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    match reaction.model {
+        ReactionModel::GrayScott { feed, kill } => {
+            bytes.extend(0u32.to_le_bytes());
+            bytes.extend(reaction.diffusion_ratio.to_le_bytes());
+            bytes.extend(feed.to_le_bytes());
+            bytes.extend(kill.to_le_bytes());
+        },
+        ReactionModel::Polynomial(polynomial) => {
+            bytes.extend(1u32.to_le_bytes());
+            bytes.extend(reaction.diffusion_ratio.to_le_bytes());
+            for species in 0..SPECIES { bytes.extend((polynomial.terms(species).len() as u32).to_le_bytes()); }
+            for term in polynomial.terms(0).iter().chain(polynomial.terms(1)) {
+                bytes.extend(term.coefficient.to_le_bytes());
+                bytes.extend(term.powers[0].to_le_bytes());
+                bytes.extend(term.powers[1].to_le_bytes());
+            }
+        }
+    }
+    bytes
+}
+
+fn reaction_from(payload: &[u8]) -> Result<Reaction, Box<dyn Error>> {
+    if payload.len() < 8 || payload.len() % 4 != 0 { return Err("Malformed checkpoint section".into()); }
+    let word = |i: usize| [payload[i * 4], payload[(i * 4) + 1], payload[(i * 4) + 2], payload[(i * 4) + 3]];
+    let ratio = f32::from_le_bytes(word(1));
+    if !(ratio >= 0.0) { return Err("Negative diffusion ratio in checkpoint".into()); }
+    let reaction = match (u32::from_le_bytes(word(0)), payload.len() / 4) {
+        (0, 4) => {
+            let (feed, kill) = (f32::from_le_bytes(word(2)), f32::from_le_bytes(word(3)));
+            if !(feed >= 0.0 && feed.is_finite() && kill >= 0.0 && kill.is_finite()) { return Err("Invalid Gray-Scott rates in checkpoint".into()); }
+            Reaction::gray_scott(feed, kill)
+        },
+        (1, words) if words >= 4 => {
+            let counts = [u32::from_le_bytes(word(2)) as usize, u32::from_le_bytes(word(3)) as usize];
+            if words != 4 + (3 * (counts[0] + counts[1])) { return Err("Malformed checkpoint section".into()); }
+            let terms: Vec<Term> = (0..counts[0] + counts[1])
+                .map(|t| 4 + (3 * t))
+                .map(|i| Term::new(f32::from_le_bytes(word(i)), [u32::from_le_bytes(word(i + 1)), u32::from_le_bytes(word(i + 2))]))
+                .collect();
+            Reaction::polynomial(Polynomial::new(&terms[..counts[0]], &terms[counts[0]..])?)
+        },
+        _ => return Err("Unknown reaction model in checkpoint".into())
+    };
+    Ok(reaction.with_diffusion_ratio(ratio))
+}
+
+fn vesicle_bytes(model: &Vesicle) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for value in [model.rigidity, model.width, model.spontaneous_curvature, model.mobility] {
+        bytes.extend(value.to_le_bytes());
+    }
+    bytes.extend((model.constrain_volume as u32).to_le_bytes());
+    bytes.extend((model.constrain_area as u32).to_le_bytes());
+    bytes
+}
+
+fn vesicle_from(payload: &[u8]) -> Result<Vesicle, Box<dyn Error>> {
+    if payload.len() != 24 { return Err("Malformed checkpoint section".into()); }
+    let values = f32s(&payload[..16], Some(4))?;
+    let constraints = u32s(&payload[16..], 2)?;
+    if !(values[0] >= 0.0 && values[1] > 0.0 && values[3] >= 0.0) {
+        return Err("Invalid vesicle parameter in checkpoint".into());
+    }
+    Ok(Vesicle::new(values[0], values[1], values[2], values[3]).with_constraints(constraints[0] == 1, constraints[1] == 1))
+}
+
+fn noise_from(payload: &[u8]) -> Result<ThermalNoise, Box<dyn Error>> {
+    if payload.len() != 8 { return Err("Malformed checkpoint section".into()); }
+    let temperature = f32s(&payload[..4], Some(1))?[0];
+    if !(temperature >= 0.0 && temperature.is_finite()) { return Err("Invalid noise temperature in checkpoint".into()); }
+    Ok(ThermalNoise::new(temperature, u32s(&payload[4..], 1)?[0] == 1))
+}
+
+fn clock_from(payload: &[u8]) -> Result<(f32, StepMode), Box<dyn Error>> {
+    if payload.len() != 12 { return Err("Malformed checkpoint section".into()); }
+    let dt = f32s(&payload[..4], Some(1))?[0];
+    if !(dt > 0.0 && dt.is_finite()) { return Err("Invalid timestep in checkpoint".into()); }
+    let mode = u32s(&payload[4..], 2)?;
+    match mode[0] {
+        0 => Ok((dt, StepMode::RealTime { max_steps: mode[1] })),
+        1 => Ok((dt, StepMode::PerFrame(mode[1]))),
+        _ => Err("Unknown step mode in checkpoint".into())
+    }
+}
+
+fn integrator_bytes(integrator: &Integrator) -> Vec<u8> {
+    let (scheme, theta, solver) = match integrator {
+        Integrator::Explicit => (0u32, 1.0f32, ImplicitSolver::default()),
+        Integrator::Implicit { theta, solver } => (1, *theta, *solver)
+    };
+    let (kind, iterations, config) = match solver {
+        ImplicitSolver::GaussSeidel { iterations } => (0u32, iterations, MultigridConfig::default()),
+        ImplicitSolver::Multigrid(config) => (1, 0, config)
+    };
+    let mut bytes = scheme.to_le_bytes().to_vec();
+    bytes.extend(theta.to_le_bytes());
+    bytes.extend(kind.to_le_bytes());
+    bytes.extend(iterations.to_le_bytes());
+    bytes.extend(config.tolerance.to_le_bytes());
+    for count in [config.max_cycles, config.pre_smooth, config.post_smooth, config.coarse_sweeps] {
+        bytes.extend(count.to_le_bytes());
+    }
+    bytes
+}
+
+fn integrator_from(payload: &[u8]) -> Result<Integrator, Box<dyn Error>> {
+    let words = u32s(payload, 9)?;
+    let [theta, tolerance] = [words[1], words[4]].map(f32::from_bits);
+    let solver = match words[2] {
+        0 if words[3] > 0 => ImplicitSolver::gauss_seidel(words[3]),
+        1 if tolerance > 0.0 && tolerance.is_finite() => ImplicitSolver::Multigrid(MultigridConfig {
+            tolerance: tolerance,
+            max_cycles: words[5],
+            pre_smooth: words[6],
+            post_smooth: words[7],
+            coarse_sweeps: words[8]
+        }),
+        _ => return Err("Invalid implicit solver in checkpoint".into())
+    };
+    match words[0] {
+        0 => Ok(Integrator::Explicit),
+        1 if (0.5..=1.0).contains(&theta) => Ok(Integrator::implicit(theta, solver)),
+        _ => Err("Invalid integrator in checkpoint".into())
+    }
+}
+
+fn particles_bytes(particles: &Particles) -> Vec<u8> {
+    let mut bytes = (particles.len() as u32).to_le_bytes().to_vec();
+    bytes.extend(particles.elapsed().to_le_bytes());
+    for particle in 0..particles.len() {
+        for value in particles.positions[particle].iter().chain(&particles.velocities[particle]).chain([&particles.diffusivity[particle]]) {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(particles.kinds[particle].to_le_bytes());
+        bytes.extend(particles.images[particle].iter().flat_map(|i| i.to_le_bytes()));
+        bytes.extend(particles.origins()[particle].iter().flat_map(|v| v.to_le_bytes()));
+    }
+    bytes
+}
+
+fn particles_from(payload: &[u8]) -> Result<Particles, Box<dyn Error>> {
+    if payload.len() < 12 { return Err("Malformed checkpoint section".into()); }
+    let count = u32s(&payload[..4], 1)?[0] as usize;
+    let elapsed = f64::from_le_bytes(payload[4..12].try_into()?);
+    let words = u32s(&payload[12..], count.checked_mul(14).ok_or("Malformed checkpoint section")?)?;
+    if !(elapsed >= 0.0 && elapsed.is_finite()) { return Err("Invalid particle time in checkpoint".into()); }
+
+    let (mut particles, mut origins) = (Particles::default(), Vec::with_capacity(count));
+    for w in words.chunks_exact(14) {
+        let value = |i: usize| f32::from_bits(w[i]);
+        let p3 = |i: usize| -> P3 { [value(i), value(i + 1), value(i + 2)] };
+        let (position, velocity, diffusivity, origin) = (p3(0), p3(3), value(6), p3(11));
+        if !(position.iter().chain(&velocity).chain(&origin).all(|v| v.is_finite()) && diffusivity >= 0.0 && diffusivity.is_finite()) {
+            return Err("Invalid particle in checkpoint".into());
+        }
+        let particle = particles.add(position, diffusivity, w[7]);
+        particles.velocities[particle] = velocity;
+        particles.images[particle] = [w[8] as i32, w[9] as i32, w[10] as i32];
+        origins.push(origin);
+    }
+    Ok(particles.with_origins(origins, elapsed))
+}
+
+fn u32s(payload: &[u8], count: usize) -> Result<Vec<u32>, Box<dyn Error>> {
+    if payload.len() != count * 4 { return Err("Malformed checkpoint section".into()); }
+    Ok(payload.chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect())
+}
+
+fn f32s(payload: &[u8], count: Option<usize>) -> Result<Vec<f32>, Box<dyn Error>> {
+    if payload.len() % 4 != 0 || count.is_some_and(|c| payload.len() != c * 4) {
+        return Err("Malformed checkpoint section".into());
+    }
+    Ok(payload.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint() -> Checkpoint {
+        let dims = [4, 3, 2];
+        let field = |offset: f32| VoxelField::new(dims, (0..24).map(|i| offset + i as f32 * 0.25).collect());
+        let species = SpeciesRegistry::parse("A:0.5;B").unwrap();
+        let (network, species) = ReactionNetwork::parse("u + A <-> B, k=0.5, kr=0.25", &species).unwrap();
+        let (extent, bounds) = ([4.0, 3.0, 2.0], BoundaryConditions::parse("x=periodic,y-=dirichlet:0.5").unwrap());
+        let mut particles = Particles::scatter(6, 2.0, &extent, 7);
+        for step in 0..20 {
+            particles.step(0.5, &extent, &bounds, 7, step);
+            particles.stepped(0.5);
+        }
+        Checkpoint {
+            field: field(0.0),
+            rand_seed: 0xDEAD_BEEF,
+            read_ping: false,
+            sim_time: 12.5,
+            step_count: 50,
+            camera: CameraBasis { c: [1.0, 2.0, 3.0], f: [0.0, 0.0, -1.0], u: [0.0, 1.0, 0.0], r: [1.0, 0.0, 0.0], centre: [0.5, 0.5, 0.5] },
+            boundaries: bounds,
+            diffusion: Diffusion::new(0.75, [1.0, 1.5, 2.0]),
+            species: species,
+            species_fields: vec![field(1.0), field(2.0)],
+            reaction: None,
+            network: Some(network),
+            cahn_hilliard: Some(CahnHilliard::new(1.0, 0.5, 2.0).with_mean(-0.2)),
+            vesicle: None,
+            noise: Some(ThermalNoise::new(0.01, false)),
+            clock: Some((0.25, StepMode::PerFrame(3))),
+            integrator: Integrator::parse("crank-nicolson:mg:1e-5").unwrap(),
+            particles: Some(particles)
+        }
+    }
+
+    /// Saves checkpoint, lets edit change its bytes and loads them back
+    fn reload(name: &str, checkpoint: &Checkpoint, edit: impl FnOnce(&mut Vec<u8>)) -> Result<Checkpoint, String> {
+        let path = std::env::temp_dir().join(format!("bocs_checkpoint_{}_{}.ckpt", name, std::process::id()));
+        checkpoint.save(&path).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        edit(&mut bytes);
+        std::fs::write(&path, &bytes).unwrap();
+        let loaded = Checkpoint::load(&path).map_err(|e| e.to_string());
+        std::fs::remove_file(&path).unwrap();
+        loaded
+    }
+
+    /// Offset of a section's tag in a saved checkpoint
+    fn section(bytes: &[u8], tag: &[u8; 4]) -> usize {
+        let mut at = MAGIC.len() + 4;
+        while &bytes[at..at + 4] != tag {
+            at += 12 + u64::from_le_bytes(bytes[at + 4..at + 12].try_into().unwrap()) as usize;
+        }
+        at
+    }
+
+    #[test]
+    fn round_trips_every_section() {
+        let checkpoint = checkpoint();
+        assert!(checkpoint.particles.as_ref().is_some_and(|p| p.elapsed() > 0.0));
+        assert_eq!(reload("round_trip", &checkpoint, |_| {}).unwrap(), checkpoint);
+
+        let mut reacting = checkpoint.clone();
+        reacting.reaction = Some(Reaction::gray_scott(0.03, 0.06).with_diffusion_ratio(0.4));
+        reacting.cahn_hilliard = None;
+        reacting.vesicle = Some(Vesicle::new(1.0, 2.0, 0.1, 0.5));
+        reacting.clock = Some((2.0, StepMode::RealTime { max_steps: 8 }));
+        reacting.integrator = Integrator::default();
+        reacting.particles = None;
+        assert_eq!(reload("reacting", &reacting, |_| {}).unwrap(), reacting);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let error = reload("newer", &checkpoint(), |bytes| bytes[8..12].copy_from_slice(&(CHECKPOINT_VERSION + 1).to_le_bytes())).unwrap_err();
+        assert!(error.contains("newer"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_truncated_sections() {
+        let field_end = |bytes: &Vec<u8>| section(bytes, TAG_FIELD) + 12 + 24 * 4;
+        let error = reload("truncated_payload", &checkpoint(), |bytes| bytes.truncate(field_end(bytes) - 5)).unwrap_err();
+        assert!(error.contains("claims"), "{}", error);
+        let error = reload("truncated_header", &checkpoint(), |bytes| bytes.truncate(field_end(bytes) + 6)).unwrap_err();
+        assert!(error.contains("inside a section header"), "{}", error);
+
+        // a FLDS too short for its species index
+        let error = reload("short_flds", &checkpoint(), |bytes| {
+            bytes.extend_from_slice(TAG_FIELDS);
+            bytes.extend_from_slice(&2u64.to_le_bytes());
+            bytes.extend_from_slice(&[2, 0]);
+        }).unwrap_err();
+        assert!(error.contains("Malformed"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_bad_tags_and_values() {
+        // a required section under an unknown tag is skipped, so it is missing
+        let error = reload("bad_tag", &checkpoint(), |bytes| {
+            let at = section(bytes, TAG_FIELD);
+            bytes[at..at + 4].copy_from_slice(b"FELX");
+        }).unwrap_err();
+        assert!(error.contains("missing FELD"), "{}", error);
+
+        let error = reload("bad_magic", &checkpoint(), |bytes| bytes[0] = b'X').unwrap_err();
+        assert!(error.contains("Not a bocs checkpoint"), "{}", error);
+
+        let error = reload("nan_mean", &checkpoint(), |bytes| {
+            let at = section(bytes, TAG_CAHN_HILLIARD) + 12 + 12;
+            bytes[at..at + 4].copy_from_slice(&f32::NAN.to_le_bytes());
+        }).unwrap_err();
+        assert!(error.contains("Cahn-Hilliard"), "{}", error);
+
+        let error = reload("zero_dims", &checkpoint(), |bytes| {
+            let at = section(bytes, TAG_DIMS) + 12;
+            bytes[at..at + 4].copy_from_slice(&0u32.to_le_bytes());
+        }).unwrap_err();
+        assert!(error.contains("empty or too large"), "{}", error);
+    }
+}
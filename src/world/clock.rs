@@ -0,0 +1,96 @@
+/// Fixed-step simulation clock, decoupled from the frame rate
+/// Every step is exactly dt µs long, so the field after step k is the same on any machine;
+/// wall time only decides how many steps each rendered frame gets
+pub const DEFAULT_TIMESTEP: f32 = 0.1; // µs
+pub const DEFAULT_MAX_STEPS_PER_FRAME: u32 = 8;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StepMode {
+    /// Wall time * time scale accumulates, and is paid out in whole dt steps (at most max_steps per frame)
+    RealTime { max_steps: u32 },
+    /// Exactly this many steps per frame whatever the wall time, e.g. for headless runs
+    PerFrame(u32)
+}
+
+#[derive(Debug, Clone)]
+pub struct SimClock {
+    pub dt: f32, // µs per step
+    pub mode: StepMode,
+    pub paused: bool,
+    accumulator: f64, // µs owed to the simulation but not yet stepped, RealTime only
+    pending_steps: u32, // single steps requested while paused
+    sim_time: f64, // µs, sum of all steps taken
+    step_count: u64
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        SimClock::new(DEFAULT_TIMESTEP, StepMode::RealTime { max_steps: DEFAULT_MAX_STEPS_PER_FRAME })
+    }
+}
+
+impl SimClock {
+    pub fn new(dt: f32, mode: StepMode) -> Self {
+        assert!(dt > 0.0, "Timestep should be > 0.0\n");
+        SimClock {
+            dt: dt,
+            mode: mode,
+            paused: false,
+            accumulator: 0.0,
+            pending_steps: 0,
+            sim_time: 0.0,
+            step_count: 0
+        }
+    }
+
+    /// Number of steps to run this frame, given elapsed_us of sim time since the last frame
+    /// (wall time * time scale, see PhysicalUnits::sim_us(), ignored unless RealTime)
+    /// Steps are counted as taken, so call once per frame and run exactly that many
+    pub fn advance(&mut self, elapsed_us: f32) -> u32 {
+        let steps = if self.paused {
+            std::mem::take(&mut self.pending_steps)
+        }
+        else {
+            match self.mode {
+                StepMode::PerFrame(n) => n,
+                StepMode::RealTime { max_steps } => {
+                    self.accumulator += elapsed_us as f64;
+                    let owed = (self.accumulator / self.dt as f64).floor() as u32;
+                    let steps = owed.min(max_steps);
+                    // time beyond max_steps is dropped rather than carried, so a slow frame can't snowball
+                    self.accumulator = if owed > max_steps { 0.0 } else { self.accumulator - (steps as f64 * self.dt as f64) };
+                    steps
+                }
+            }
+        };
+        self.sim_time += steps as f64 * self.dt as f64;
+        self.step_count += steps as u64;
+        steps
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.accumulator = 0.0; // no burst of catch-up steps on resume
+    }
+
+    /// Queues one step for the next frame while paused
+    pub fn single_step(&mut self) {
+        if self.paused { self.pending_steps += 1; }
+    }
+
+    pub fn sim_time(&self) -> f64 {
+        self.sim_time
+    }
+
+    pub fn step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    /// Resumes at a checkpointed time and step
+    pub fn restore(&mut self, sim_time: f64, step_count: u64) {
+        self.sim_time = sim_time;
+        self.step_count = step_count;
+        self.accumulator = 0.0;
+        self.pending_steps = 0;
+    }
+}
@@ -0,0 +1,45 @@
+use crate::world::voxel_grid::P3;
+
+/// Diffusion coefficient and voxel spacing for the laplacian stencil
+/// Spacing in nm and D in nm^2/µs, so timesteps are in µs (see units.rs for entering physical values)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Diffusion {
+    pub coefficient: f32, // D, nm^2/µs
+    pub spacing: P3 // dx, dy, dz in nm, may differ per axis
+}
+
+impl Default for Diffusion {
+    /// D = 1.0, dx = 1.0: the values laplacian.wgsl used to hard-code
+    fn default() -> Self {
+        Diffusion {
+            coefficient: 1.0,
+            spacing: [1.0, 1.0, 1.0]
+        }
+    }
+}
+
+impl Diffusion {
+    pub fn new(coefficient: f32, spacing: P3) -> Self {
+        assert!(coefficient >= 0.0, "Diffusion coefficient should be >= 0.0\n");
+        assert!(spacing.iter().all(|dx| *dx > 0.0), "Voxel spacing should be > 0.0 on every axis\n");
+        Diffusion {
+            coefficient: coefficient,
+            spacing: spacing
+        }
+    }
+
+    /// [D, 1/dx^2, 1/dy^2, 1/dz^2] as written into Uniforms, computed once on the CPU
+    /// so the shader and the CPU reference multiply by exactly the same weights
+    pub fn stencil_weights(&self) -> [f32; 4] {
+        let [dx, dy, dz] = self.spacing;
+        [self.coefficient, 1.0 / (dx * dx), 1.0 / (dy * dy), 1.0 / (dz * dz)]
+    }
+
+    /// Largest stable explicit Euler timestep for the 7-point stencil:
+    /// dt <= 1 / (2D (1/dx^2 + 1/dy^2 + 1/dz^2)), which is dx^2 / 6D when isotropic
+    pub fn stable_timestep(&self) -> f32 {
+        let [_, wx, wy, wz] = self.stencil_weights();
+        if self.coefficient == 0.0 { return f32::INFINITY; } // nothing diffuses, any step is stable
+        1.0 / (2.0 * self.coefficient * (wx + wy + wz))
+    }
+}
@@ -0,0 +1,59 @@
+use crate::world::voxel_grid::Dims3;
+
+/// Flat index of voxel (x, y, z), identical to the WGSL side
+/// x + y * dims[0] + z * dims[0] * dims[1] (dims[3] in Uniforms is the xy plane stride)
+pub fn voxel_index(dims: &Dims3, x: u32, y: u32, z: u32) -> usize {
+    (x + (y * dims[0]) + (z * dims[0] * dims[1])) as usize
+}
+
+/// CPU-side copy of a concentration field, laid out exactly like the voxel buffers
+/// Returned by GPU readback and SimulationBackend::read_field()
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxelField {
+    pub dims: Dims3,
+    pub data: Vec<f32>
+}
+
+impl VoxelField {
+    pub fn new(dims: Dims3, data: Vec<f32>) -> Self {
+        assert!(data.len() == (dims[0] * dims[1] * dims[2]) as usize, "Field length does not match dims\n");
+        VoxelField {
+            dims: dims,
+            data: data
+        }
+    }
+
+    /// Builds a field by evaluating f at every voxel, e.g. a designed lipid distribution
+    pub fn from_fn(dims: Dims3, f: impl Fn(u32, u32, u32) -> f32) -> Self {
+        let mut data = Vec::with_capacity((dims[0] * dims[1] * dims[2]) as usize);
+        for z in 0..dims[2] {
+            for y in 0..dims[1] {
+                for x in 0..dims[0] {
+                    data.push(f(x, y, z)); // x fastest, matching voxel_index
+                }
+            }
+        }
+        VoxelField::new(dims, data)
+    }
+
+    pub fn get(&self, x: u32, y: u32, z: u32) -> f32 {
+        assert!(x < self.dims[0] && y < self.dims[1] && z < self.dims[2]);
+        self.data[voxel_index(&self.dims, x, y, z)]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, z: u32, value: f32) {
+        assert!(x < self.dims[0] && y < self.dims[1] && z < self.dims[2]);
+        let idx = voxel_index(&self.dims, x, y, z);
+        self.data[idx] = value;
+    }
+
+    /// Sum over all voxels, conserved by the laplacian under Neumann bounds
+    pub fn total(&self) -> f64 {
+        self.data.iter().map(|v| *v as f64).sum()
+    }
+
+    /// total() per voxel, e.g. the composition Cahn-Hilliard conserves
+    pub fn mean(&self) -> f64 {
+        self.total() / self.data.len() as f64
+    }
+}
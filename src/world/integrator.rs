@@ -0,0 +1,197 @@
+use crate::world::{boundary::{Boundary, BoundaryConditions}, multigrid::MultigridConfig, voxel_grid::Dims3};
+
+/// Time integration of the diffusion operator
+/// Explicit Euler (laplacian.wgsl) is cheap per step but only stable below dx^2 / 6D,
+/// the implicit theta scheme (implicit.wgsl) is stable for any dt and solves
+/// (1 - θ dt D L) u' = (1 + (1 - θ) dt D L) u in place on the current field,
+/// by red-black Gauss-Seidel sweeps or multigrid V-cycles (see world/multigrid.rs)
+pub const DEFAULT_ITERATIONS: u32 = 32;
+pub const MAX_SWEEP_COLOURS: u32 = 3;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ImplicitSolver {
+    /// iterations red + black sweep pairs per step, not run to a tolerance:
+    /// error smoother than a few voxels decays slowly under them, so very large steps want more iterations
+    GaussSeidel { iterations: u32 },
+    /// V-cycles until ||r|| / ||f|| falls below the config's tolerance, cost barely grows with dt
+    Multigrid(MultigridConfig)
+}
+
+impl Default for ImplicitSolver {
+    fn default() -> Self {
+        ImplicitSolver::GaussSeidel { iterations: DEFAULT_ITERATIONS }
+    }
+}
+
+impl ImplicitSolver {
+    pub fn gauss_seidel(iterations: u32) -> Self {
+        assert!(iterations > 0, "Implicit solves need at least one iteration\n");
+        ImplicitSolver::GaussSeidel { iterations: iterations }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImplicitSolver::GaussSeidel { .. } => "Gauss-Seidel",
+            ImplicitSolver::Multigrid(_) => "multigrid"
+        }
+    }
+
+    /// Gauss-Seidel <-> multigrid with default settings, e.g. for a key binding
+    pub fn toggle(&self) -> Self {
+        match self {
+            ImplicitSolver::GaussSeidel { .. } => ImplicitSolver::Multigrid(MultigridConfig::default()),
+            ImplicitSolver::Multigrid(_) => ImplicitSolver::default()
+        }
+    }
+
+    /// Parses what follows the scheme: "<iterations>" or "mg" with an optional ":<tolerance>"
+    fn parse(parts: &[&str]) -> Result<Self, String> {
+        match parts {
+            [] => Ok(ImplicitSolver::default()),
+            ["mg"] => Ok(ImplicitSolver::Multigrid(MultigridConfig::default())),
+            ["mg", tolerance] => {
+                let tolerance = tolerance.parse::<f32>().map_err(|e| format!("Bad tolerance '{}': {}", tolerance, e))?;
+                if !(tolerance > 0.0) { return Err(format!("Tolerance {} should be > 0", tolerance)); }
+                Ok(ImplicitSolver::Multigrid(MultigridConfig { tolerance: tolerance, ..MultigridConfig::default() }))
+            },
+            [n] => match n.parse::<u32>() {
+                Ok(iterations) if iterations > 0 => Ok(ImplicitSolver::gauss_seidel(iterations)),
+                _ => Err(format!("Expected a positive iteration count or mg[:<tolerance>], got '{}'", n))
+            },
+            _ => Err(format!("Expected at most <iterations> or mg[:<tolerance>], got '{}'", parts.join(":")))
+        }
+    }
+}
+
+/// Colours a Gauss-Seidel sweep updates in turn, each reading only the others: red-black ((x + y + z) even, then odd),
+/// unless an axis is periodic and of odd length, whose last voxel would share its colour with the one it wraps to
+pub fn sweep_colours(dims: &Dims3, bounds: &BoundaryConditions) -> u32 {
+    if (0..3).any(|axis| odd_periodic(dims, bounds, axis)) { MAX_SWEEP_COLOURS } else { 2 }
+}
+
+/// Colour of voxel x, y, z among sweep_colours(): each axis counts its parity, or 2 at the last voxel of an odd periodic axis,
+/// and the sum is taken modulo the colour count, so neighbours differ by 1 or 2 in it, never 3
+pub fn sweep_colour(dims: &Dims3, bounds: &BoundaryConditions, x: u32, y: u32, z: u32) -> u32 {
+    let sum: u32 = [x, y, z].iter().enumerate()
+        .map(|(axis, c)| if odd_periodic(dims, bounds, axis) && c + 1 == dims[axis] { 2 } else { c % 2 })
+        .sum();
+    sum % sweep_colours(dims, bounds)
+}
+
+fn odd_periodic(dims: &Dims3, bounds: &BoundaryConditions, axis: usize) -> bool {
+    bounds.faces[axis][0] == Boundary::Periodic && dims[axis] % 2 == 1
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Integrator {
+    #[default]
+    Explicit,
+    /// θ = 1 is backward Euler, θ = 0.5 Crank-Nicolson
+    Implicit { theta: f32, solver: ImplicitSolver }
+}
+
+impl Integrator {
+    pub fn backward_euler(solver: ImplicitSolver) -> Self {
+        Integrator::implicit(1.0, solver)
+    }
+
+    pub fn crank_nicolson(solver: ImplicitSolver) -> Self {
+        Integrator::implicit(0.5, solver)
+    }
+
+    /// θ below 0.5 is only conditionally stable, which defeats the point of solving implicitly
+    pub fn implicit(theta: f32, solver: ImplicitSolver) -> Self {
+        assert!((0.5..=1.0).contains(&theta), "Theta should be within [0.5, 1.0]\n");
+        Integrator::Implicit { theta: theta, solver: solver }
+    }
+
+    /// Whether explicit stability limits apply, see world::stability
+    pub fn is_explicit(&self) -> bool {
+        matches!(self, Integrator::Explicit)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Integrator::Explicit => "explicit Euler",
+            Integrator::Implicit { theta, .. } if *theta == 1.0 => "backward Euler",
+            Integrator::Implicit { theta, .. } if *theta == 0.5 => "Crank-Nicolson",
+            Integrator::Implicit { .. } => "implicit θ"
+        }
+    }
+
+    pub fn solver(&self) -> Option<ImplicitSolver> {
+        match self {
+            Integrator::Explicit => None,
+            Integrator::Implicit { solver, .. } => Some(*solver)
+        }
+    }
+
+    /// Explicit -> backward Euler -> Crank-Nicolson -> explicit, e.g. for a key binding
+    pub fn cycle(&self) -> Self {
+        match self {
+            Integrator::Explicit => Integrator::backward_euler(ImplicitSolver::default()),
+            Integrator::Implicit { theta, solver } if *theta == 1.0 => Integrator::crank_nicolson(*solver),
+            Integrator::Implicit { .. } => Integrator::Explicit
+        }
+    }
+
+    /// Same scheme with the other implicit solver, explicit stays explicit
+    pub fn toggle_solver(&self) -> Self {
+        match self {
+            Integrator::Explicit => Integrator::Explicit,
+            Integrator::Implicit { theta, solver } => Integrator::implicit(*theta, solver.toggle())
+        }
+    }
+
+    /// Parses "explicit", "backward-euler", "crank-nicolson" or "theta:<θ>",
+    /// each implicit one with an optional ":<iterations>" (Gauss-Seidel) or ":mg[:<tolerance>]" (multigrid)
+    /// e.g. "backward-euler:64", "theta:0.75:16" or "crank-nicolson:mg:1e-5"
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut parts = spec.trim().split(':');
+        let scheme = parts.next().unwrap_or("");
+        let theta = match scheme {
+            "explicit" => return match parts.next() {
+                None => Ok(Integrator::Explicit),
+                Some(_) => Err("Explicit Euler takes no iterations".to_string())
+            },
+            "backward-euler" => 1.0,
+            "crank-nicolson" => 0.5,
+            "theta" => {
+                let theta = parts.next().ok_or("Expected theta:<θ>")?;
+                theta.parse::<f32>().map_err(|e| format!("Bad theta '{}': {}", theta, e))?
+            },
+            other => return Err(format!("Unknown integrator '{}', expected explicit, backward-euler, crank-nicolson or theta:<θ>", other))
+        };
+        if !(0.5..=1.0).contains(&theta) {
+            return Err(format!("Theta {} is outside [0.5, 1.0]", theta));
+        }
+        let solver = ImplicitSolver::parse(&parts.collect::<Vec<_>>())?;
+        Ok(Integrator::implicit(theta, solver))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sweep_colours_differ_across_every_face() {
+        for (dims, spec) in [([6, 4, 8], "xyz=periodic"), ([5, 4, 3], "xyz=periodic"), ([7, 1, 6], "x=periodic,z=dirichlet:1.0"), ([3, 5, 2], "")] {
+            let bounds = BoundaryConditions::parse(spec).unwrap();
+            let colours = sweep_colours(&dims, &bounds);
+            assert_eq!(colours, if dims.iter().any(|n| n % 2 == 1) && !spec.is_empty() { 3 } else { 2 });
+            for n in 0..dims[0] * dims[1] * dims[2] {
+                let voxel = [n % dims[0], n / dims[0] % dims[1], n / (dims[0] * dims[1])];
+                let colour = sweep_colour(&dims, &bounds, voxel[0], voxel[1], voxel[2]);
+                assert!(colour < colours);
+                for axis in 0..3 {
+                    let mut neighbour = voxel;
+                    neighbour[axis] = (voxel[axis] + 1) % dims[axis];
+                    let wraps = voxel[axis] + 1 == dims[axis];
+                    if neighbour == voxel || (wraps && bounds.faces[axis][0] != Boundary::Periodic) { continue; }
+                    assert_ne!(colour, sweep_colour(&dims, &bounds, neighbour[0], neighbour[1], neighbour[2]), "{:?} {} {:?} axis {}", dims, spec, voxel, axis);
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,23 @@
 pub mod voxel_grid;
+pub mod field;
+pub mod boundary;
+pub mod diffusion;
+pub mod integrator;
+pub mod multigrid;
+pub mod reaction;
+pub mod species;
+pub mod network;
+pub mod cahn_hilliard;
+pub mod vesicle;
+pub mod noise;
+pub mod rdme;
+pub mod brownian_motion;
+pub mod rng;
+pub mod units;
+pub mod clock;
+pub mod stability;
+pub mod checkpoint;
+pub mod npy;
+pub mod vtk;
 pub mod camera;
 pub mod world;
\ No newline at end of file
@@ -0,0 +1,143 @@
+use crate::world::{boundary::{Boundary, BoundaryConditions}, voxel_grid::{Dims3, P3}};
+
+/// Geometric multigrid for (α - β L) u = f on the voxel grid
+/// α = 0 is Poisson (electrostatics, pressure projection), α = 1, β = θ dt D an implicit diffusion step
+/// L is the 7-point laplacian of laplacian.wgsl with per-axis spacing and the same boundary faces
+/// Levels are cell centred: each coarse voxel covers 2 voxels per coarsened axis of the level above,
+/// residuals are restricted by averaging and corrections prolonged trilinearly
+/// Only even axes are halved, so grids whose dims are a power of two times a small number coarsen furthest;
+/// an odd axis stays at full resolution on every coarser level, which slows convergence
+/// CPU and GPU implementations live in backend_admin/cpu/multigrid.rs and backend_admin/gpu/multigrid.rs
+pub const COARSEST_DIM: u32 = 2; // axes this short or shorter are not halved again
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Helmholtz {
+    pub alpha: f32,
+    pub beta: f32
+}
+
+impl Helmholtz {
+    pub fn poisson() -> Self {
+        Helmholtz { alpha: 0.0, beta: 1.0 }
+    }
+
+    /// (1 - θ dt D L) u' = rhs, see world/integrator.rs
+    pub fn implicit_diffusion(theta: f32, timestep: f32, coefficient: f32) -> Self {
+        Helmholtz { alpha: 1.0, beta: theta * timestep * coefficient }
+    }
+
+    /// [α, β/dx^2, β/dy^2, β/dz^2] for a level with this spacing, as the smoother and residual read them
+    pub fn weights(&self, spacing: &P3) -> [f32; 4] {
+        let [dx, dy, dz] = *spacing;
+        [self.alpha, self.beta / (dx * dx), self.beta / (dy * dy), self.beta / (dz * dz)]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MultigridConfig {
+    pub tolerance: f32, // stop once ||r|| / ||f|| falls below this
+    pub max_cycles: u32,
+    pub pre_smooth: u32, // red + black sweep pairs before restricting
+    pub post_smooth: u32, // ... and after prolonging
+    pub coarse_sweeps: u32 // sweep pairs standing in for an exact solve on the coarsest level
+}
+
+impl Default for MultigridConfig {
+    fn default() -> Self {
+        MultigridConfig {
+            tolerance: 1.0e-4, // f32 residuals bottom out around 1e-5
+            max_cycles: 20,
+            pre_smooth: 2,
+            post_smooth: 2,
+            coarse_sweeps: 32
+        }
+    }
+}
+
+/// Relative residual ||r|| / ||f|| before the first V-cycle and after each one
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConvergenceHistory {
+    pub residuals: Vec<f64>,
+    pub converged: bool
+}
+
+impl ConvergenceHistory {
+    pub fn cycles(&self) -> usize {
+        self.residuals.len().saturating_sub(1)
+    }
+
+    pub fn final_residual(&self) -> Option<f64> {
+        self.residuals.last().copied()
+    }
+
+    /// Geometric mean of the per-cycle reduction, ~0.1 for a healthy V-cycle
+    pub fn mean_reduction(&self) -> Option<f64> {
+        match (self.residuals.first(), self.residuals.last()) {
+            (Some(first), Some(last)) if self.cycles() > 0 && *first > 0.0 => Some((last / first).powf(1.0 / self.cycles() as f64)),
+            _ => None
+        }
+    }
+
+    /// e.g. "4 V-cycles, |r|/|f| = 3.2e-6"
+    pub fn summary(&self) -> String {
+        format!("{} V-cycles, |r|/|f| = {:.1e}{}", self.cycles(), self.final_residual().unwrap_or(0.0),
+            if self.converged { "" } else { " (not converged)" })
+    }
+}
+
+/// One level of the hierarchy, scale multiplies the finest spacing per axis
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Level {
+    pub dims: Dims3,
+    pub scale: P3
+}
+
+impl Level {
+    pub fn len(&self) -> usize {
+        (self.dims[0] * self.dims[1] * self.dims[2]) as usize
+    }
+
+    pub fn spacing(&self, finest: &P3) -> P3 {
+        [finest[0] * self.scale[0], finest[1] * self.scale[1], finest[2] * self.scale[2]]
+    }
+
+    /// Dirichlet face weights per axis, relative to an interior neighbour
+    /// The finest level holds the value half a voxel outside its face (the halo centre, as laplacian.wgsl does)
+    /// A coarse voxel's halo centre lies further out, so the value is extrapolated back to that same plane:
+    /// a coarse voxel s fine voxels wide has its centre (s + 1) / 2 fine voxels from it, giving 2s / (s + 1)
+    /// Without this every level moves the boundary outwards and coarse corrections overshoot
+    pub fn dirichlet_weights(&self) -> [f32; 3] {
+        self.scale.map(|s| 2.0 * s / (s + 1.0))
+    }
+}
+
+/// Finest first, halving every even axis longer than COARSEST_DIM until none is left to halve
+pub fn hierarchy(dims: &Dims3) -> Vec<Level> {
+    let halves = |d: u32| d > COARSEST_DIM && d % 2 == 0;
+    let mut levels = vec![Level { dims: *dims, scale: [1.0, 1.0, 1.0] }];
+    while let Some(finer) = levels.last() && finer.dims.iter().any(|d| halves(*d)) {
+        let mut coarse = *finer;
+        for axis in 0..3 {
+            if halves(finer.dims[axis]) {
+                coarse.dims[axis] = finer.dims[axis] / 2;
+                coarse.scale[axis] *= 2.0;
+            }
+        }
+        levels.push(coarse);
+    }
+    levels
+}
+
+/// Fine voxels per coarse voxel along each axis between two neighbouring levels
+pub fn ratios(fine: &Level, coarse: &Level) -> [u32; 3] {
+    [0, 1, 2].map(|axis| fine.dims[axis] / coarse.dims[axis])
+}
+
+/// Coarse levels solve for the error, which is zero on Dirichlet faces
+pub fn coarse_bounds(bounds: &BoundaryConditions) -> BoundaryConditions {
+    let mut coarse = *bounds;
+    for face in coarse.faces.iter_mut().flatten() {
+        if let Boundary::Dirichlet(_) = face { *face = Boundary::Dirichlet(0.0); }
+    }
+    coarse
+}
@@ -0,0 +1,297 @@
+use std::{error::Error, fs, path::Path};
+use crate::world::{
+    reaction::int_pow,
+    species::{MAX_SPECIES, Species, SpeciesRegistry}};
+
+/// Mass-action reaction networks over the species of a SpeciesRegistry, written as text, one reaction per line:
+///     # comments run to the end of a line
+///     species C:0.1:0            declares a species the run doesn't have yet (Species::parse syntax)
+///     A + B -> C, k=0.3          flux k [A] [B], consumes A and B, produces C
+///     2 A -> B, k=0.01           coefficients are leading integers, 2A works too
+///     C -> , k=0.1               degradation, an empty side (or 0) is nothing
+///     -> A, k=0.05               constant source
+///     A + B <-> C, k=0.3, kr=0.1 reversible, the reverse flux kr [C]
+/// Every voxel reacts by one explicit Euler step after diffusion and any two-species reaction (Lie splitting):
+/// d[X]/dt += Σ (products - reactants of X) k Π [reactant]^coefficient
+/// k is per µs, in units of concentration^(1 - order), concentrations being whatever the fields hold
+/// Evaluated by network.wgsl on the GPU and cpu/network.rs, both interpreting the same packed reactions
+pub const MAX_REACTIONS: usize = 32; // as network.wgsl's uniforms hold them, reversible ones count twice
+pub const MAX_ORDER: u32 = 3; // total reactant coefficient, higher orders should be split into elementary steps
+pub const MAX_REACTANTS: usize = MAX_ORDER as usize; // distinct reactant species, at most one per unit of order
+
+/// One irreversible reaction, species indexed as the registry it was resolved against
+#[derive(Debug, Clone, PartialEq)]
+pub struct MassAction {
+    pub reactants: Vec<(usize, u32)>, // (species, stoichiometric coefficient), each species once
+    pub products: Vec<(usize, u32)>,
+    pub rate: f32 // k
+}
+
+impl MassAction {
+    /// Sum of the reactants' coefficients, saturating so an overflow still reads as above MAX_ORDER
+    pub fn order(&self) -> u32 {
+        self.reactants.iter().fold(0, |order, (_, n)| order.saturating_add(*n))
+    }
+
+    /// Products minus reactants per species
+    pub fn net(&self) -> [f32; MAX_SPECIES] {
+        let mut net = [0.0; MAX_SPECIES];
+        for (species, n) in &self.products { net[*species] += *n as f32; }
+        for (species, n) in &self.reactants { net[*species] -= *n as f32; }
+        net
+    }
+
+    /// k Π c^n, multiplied in the order of network.wgsl's flux()
+    pub fn flux(&self, concentrations: &[f32]) -> f32 {
+        self.reactants.iter().fold(self.rate, |flux, (species, n)| flux * int_pow(concentrations[*species], *n))
+    }
+}
+
+/// Reactions plus the species names they were resolved against, so they can be re-indexed when the registry changes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReactionNetwork {
+    pub reactions: Vec<MassAction>,
+    species: Vec<String>
+}
+
+impl ReactionNetwork {
+    /// Parses a network file's text against registry
+    /// Returns the network and registry with the declared species it didn't have appended (declared ones it has keep their settings)
+    /// Errors name the line: unknown species, malformed or zero coefficients, orders above MAX_ORDER,
+    /// reactions without reactants or products or that change nothing, and missing or negative rates
+    pub fn parse(text: &str, registry: &SpeciesRegistry) -> Result<(Self, SpeciesRegistry), String> {
+        let mut registry = registry.clone();
+        let mut lines = Vec::new();
+        for (number, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l)) {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() { continue; }
+            match line.strip_prefix("species ") {
+                Some(spec) => {
+                    let species = Species::parse(spec).map_err(|e| format!("line {}: {}", number, e))?;
+                    if registry.index_of(&species.name).is_none() {
+                        registry.add(species).map_err(|e| format!("line {}: {}", number, e))?;
+                    }
+                },
+                None => lines.push((number, line))
+            }
+        }
+
+        let mut reactions = Vec::new();
+        for (number, line) in lines {
+            reactions.extend(parse_reaction(line, &registry).map_err(|e| format!("line {}: {}", number, e))?);
+        }
+        if reactions.is_empty() { return Err("The network has no reactions".to_string()); }
+        if reactions.len() > MAX_REACTIONS {
+            return Err(format!("{} reactions (reversible ones count twice), at most {} are supported", reactions.len(), MAX_REACTIONS));
+        }
+        let network = ReactionNetwork {
+            reactions: reactions,
+            species: registry.iter().map(|s| s.name.clone()).collect()
+        };
+        Ok((network, registry))
+    }
+
+    /// parse() of a file
+    pub fn load(path: &Path, registry: &SpeciesRegistry) -> Result<(Self, SpeciesRegistry), Box<dyn Error>> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Unable to read network {}: {}", path.display(), e))?;
+        Ok(Self::parse(&text, registry).map_err(|e| format!("{}: {}", path.display(), e))?)
+    }
+
+    /// The same reactions indexed against another registry, which must hold every species they involve
+    pub fn resolve(&self, registry: &SpeciesRegistry) -> Result<Self, String> {
+        let index = |species: usize| registry.index_of(&self.species[species])
+            .ok_or(format!("The reaction network needs species {}", self.species[species]));
+        let side = |side: &[(usize, u32)]| side.iter().map(|(s, n)| Ok((index(*s)?, *n))).collect::<Result<Vec<(usize, u32)>, String>>();
+        let reactions = self.reactions.iter()
+            .map(|r| Ok(MassAction { reactants: side(&r.reactants)?, products: side(&r.products)?, rate: r.rate }))
+            .collect::<Result<Vec<MassAction>, String>>()?;
+        Ok(ReactionNetwork {
+            reactions: reactions,
+            species: registry.iter().map(|s| s.name.clone()).collect()
+        })
+    }
+
+    /// Number of species the network is indexed over
+    pub fn species_count(&self) -> usize {
+        self.species.len()
+    }
+
+    pub fn name(&self) -> String {
+        format!("{} mass-action reactions", self.reactions.len())
+    }
+
+    /// Rate of change of every species at concentrations, summed reaction by reaction in the order of network.wgsl
+    pub fn rates(&self, concentrations: &[f32], rates: &mut [f32]) {
+        rates.fill(0.0);
+        for reaction in &self.reactions {
+            let (flux, net) = (reaction.flux(concentrations), reaction.net());
+            for (rate, change) in rates.iter_mut().zip(net) {
+                *rate += change * flux;
+            }
+        }
+    }
+
+    /// Bound on a stable explicit step, µs, as Reaction::stable_timestep():
+    /// for concentrations of order 1 a reaction moves a species at most |net| k order per unit change
+    pub fn stable_timestep(&self) -> f32 {
+        let mut stiffness = [0.0f32; MAX_SPECIES];
+        for reaction in &self.reactions {
+            for (s, change) in stiffness.iter_mut().zip(reaction.net()) {
+                *s += change.abs() * reaction.rate * reaction.order() as f32;
+            }
+        }
+        let stiffness = stiffness.into_iter().fold(0.0, f32::max);
+        if stiffness == 0.0 { f32::INFINITY } else { 1.0 / stiffness }
+    }
+
+    /// The reactions as parse() reads them, one irreversible reaction per line, for checkpoints
+    pub fn to_text(&self) -> String {
+        let side = |side: &[(usize, u32)]| side.iter()
+            .map(|(s, n)| if *n == 1 { self.species[*s].clone() } else { format!("{} {}", n, self.species[*s]) })
+            .collect::<Vec<String>>()
+            .join(" + ");
+        self.reactions.iter()
+            .map(|r| format!("{} -> {}, k={}", side(&r.reactants), side(&r.products), r.rate).trim_start().to_string() + "\n")
+            .collect()
+    }
+}
+
+/// "A + B <-> C, k=0.3, kr=0.1" -> the forward and reverse reactions
+fn parse_reaction(line: &str, registry: &SpeciesRegistry) -> Result<Vec<MassAction>, String> {
+    let mut parts = line.split(',').map(str::trim);
+    let equation = parts.next().unwrap_or("");
+    let (lhs, rhs, reversible) = match (equation.split_once("<->"), equation.split_once("->")) {
+        (Some((lhs, rhs)), _) => (lhs, rhs, true),
+        (None, Some((lhs, rhs))) => (lhs, rhs, false),
+        (None, None) => return Err(format!("expected 'reactants -> products' or '<->', got '{}'", equation))
+    };
+
+    let (mut forward, mut reverse) = (None, None);
+    for option in parts.filter(|p| !p.is_empty()) {
+        let (key, value) = option.split_once('=').ok_or(format!("expected k=<rate>, got '{}'", option))?;
+        let value = value.trim().parse::<f32>().map_err(|e| format!("bad rate constant '{}': {}", value.trim(), e))?;
+        if !(value >= 0.0 && value.is_finite()) { return Err(format!("rate constant {} should be finite and >= 0", key.trim())); }
+        match key.trim() {
+            "k" => forward = Some(value),
+            "kr" if reversible => reverse = Some(value),
+            "kr" => return Err("kr is only for reversible reactions (<->)".to_string()),
+            other => return Err(format!("unknown option '{}', expected k or kr", other))
+        }
+    }
+    let forward = forward.ok_or("missing rate constant k=<rate>")?;
+
+    let (reactants, products) = (parse_side(lhs, registry)?, parse_side(rhs, registry)?);
+    let mut reactions = vec![mass_action(reactants.clone(), products.clone(), forward)?];
+    if reversible {
+        let reverse = reverse.ok_or("reversible reactions need kr=<rate> too")?;
+        reactions.push(mass_action(products, reactants, reverse)?);
+    }
+    Ok(reactions)
+}
+
+/// Checks the stoichiometry of reactants -> products
+fn mass_action(reactants: Vec<(usize, u32)>, products: Vec<(usize, u32)>, rate: f32) -> Result<MassAction, String> {
+    if reactants.is_empty() && products.is_empty() { return Err("a reaction needs reactants or products".to_string()); }
+    let reaction = MassAction { reactants: reactants, products: products, rate: rate };
+    if reaction.order() > MAX_ORDER {
+        return Err(format!("reactant order {} exceeds {}, split it into elementary steps", reaction.order(), MAX_ORDER));
+    }
+    if reaction.net().iter().all(|n| *n == 0.0) {
+        return Err("every species has the same coefficient on both sides, the reaction changes nothing".to_string());
+    }
+    Ok(reaction)
+}
+
+/// "2 A + B" -> [(A, 2), (B, 1)], a species named twice adds up ("A + A" is "2 A")
+fn parse_side(side: &str, registry: &SpeciesRegistry) -> Result<Vec<(usize, u32)>, String> {
+    let side = side.trim();
+    if side.is_empty() || side == "0" || side == "∅" { return Ok(Vec::new()); }
+    let mut terms: Vec<(usize, u32)> = Vec::new();
+    for term in side.split('+').map(str::trim) {
+        let digits = term.find(|c: char| !c.is_ascii_digit()).unwrap_or(term.len());
+        let (coefficient, name) = (&term[..digits], term[digits..].trim());
+        let coefficient = match coefficient {
+            "" => 1,
+            n => n.parse::<u32>().map_err(|e| format!("bad stoichiometric coefficient '{}': {}", n, e))?
+        };
+        if name.is_empty() { return Err(format!("'{}' names no species", term)); }
+        if coefficient == 0 { return Err(format!("stoichiometric coefficient of {} should be a positive integer", name)); }
+        if name.contains(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')) {
+            return Err(format!("bad stoichiometric coefficient or species name '{}'", term));
+        }
+        let species = registry.index_of(name)
+            .ok_or(format!("unknown species '{}', declare it with 'species {}' or add it with --species", name, name))?;
+        match terms.iter_mut().find(|(s, _)| *s == species) {
+            Some((_, n)) => *n = n.checked_add(coefficient).ok_or(format!("stoichiometric coefficients of {} overflow", name))?,
+            None => terms.push((species, coefficient))
+        }
+    }
+    Ok(terms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> SpeciesRegistry {
+        SpeciesRegistry::parse("A;B;C").unwrap()
+    }
+
+    fn error(text: &str) -> String {
+        ReactionNetwork::parse(text, &registry()).unwrap_err()
+    }
+
+    #[test]
+    fn parses_reversible_reactions_and_declared_species() {
+        let (network, species) = ReactionNetwork::parse("species D\nA + 2B <-> C, k=0.3, kr=0.1 # binding\nC -> D, k=1", &registry()).unwrap();
+        assert_eq!(species.len(), 5);
+        assert_eq!(network.reactions, vec![
+            MassAction { reactants: vec![(1, 1), (2, 2)], products: vec![(3, 1)], rate: 0.3 },
+            MassAction { reactants: vec![(3, 1)], products: vec![(1, 1), (2, 2)], rate: 0.1 },
+            MassAction { reactants: vec![(3, 1)], products: vec![(4, 1)], rate: 1.0 }
+        ]);
+        assert_eq!(network.reactions[0].order(), 3);
+    }
+
+    #[test]
+    fn rejects_unknown_species() {
+        assert!(error("A + X -> C, k=1").contains("unknown species 'X'"));
+        assert!(error("\n\nA -> 2 Y, k=1").starts_with("line 3:"));
+    }
+
+    #[test]
+    fn rejects_malformed_rate_constants() {
+        assert!(error("A -> B, k=fast").contains("bad rate constant"));
+        assert!(error("A -> B, k=-1").contains("should be finite"));
+        assert!(error("A -> B, k=inf").contains("should be finite"));
+        assert!(error("A -> B, 0.3").contains("expected k=<rate>"));
+        assert!(error("A -> B").contains("missing rate constant"));
+        assert!(error("A -> B, k=1, kr=1").contains("only for reversible"));
+    }
+
+    #[test]
+    fn rejects_reversible_reactions_without_kr() {
+        assert!(error("A <-> B, k=1").contains("need kr"));
+    }
+
+    #[test]
+    fn rejects_bad_stoichiometry() {
+        assert!(error("0 A -> B, k=1").contains("positive integer"));
+        assert!(error("4294967295 A + A -> B, k=1").contains("overflow"));
+        assert!(error("4294967295 A + 2 B -> C, k=1").contains("exceeds 3"));
+        assert!(error("2 A + 2 B -> C, k=1").contains("exceeds 3"));
+        assert!(error("A + B -> A + B, k=1").contains("changes nothing"));
+        assert!(error(" -> , k=1").contains("needs reactants or products"));
+    }
+
+    #[test]
+    fn resolve_reindexes_by_name() {
+        let (network, _) = ReactionNetwork::parse("A + B -> C, k=0.5", &registry()).unwrap();
+        let shuffled = SpeciesRegistry::parse("C;extra;A;B").unwrap();
+        let resolved = network.resolve(&shuffled).unwrap();
+        assert_eq!(resolved.reactions[0].reactants, vec![(3, 1), (4, 1)]);
+        assert_eq!(resolved.reactions[0].products, vec![(1, 1)]);
+        assert_eq!(resolved.species_count(), 5);
+        assert!(network.resolve(&SpeciesRegistry::parse("A;B").unwrap()).unwrap_err().contains("needs species C"));
+    }
+}
@@ -0,0 +1,65 @@
+use crate::world::voxel_grid::P3;
+
+/// Thermal noise added to species 0 at the end of every step, so nucleation and fluctuations happen at all
+/// Non-conserved (Langevin, model A): φ += sqrt(2 Γ kT dt / dV) ξ per voxel
+/// Conserved (Cahn-Hilliard-Cook, model B): φ -= dt ∇·ζ, with a random flux ζ through every voxel face,
+/// i.e. each face moves sqrt(2 Γ kT dt / dV) / dx ξ from one voxel to its neighbour, so the total is exactly unchanged
+/// ξ are standard normals from world/rng.rs keyed on Bridge::rand_seed and counted by the step index and sub-step,
+/// so runs with the same seed reproduce exactly, and a resumed checkpoint continues the run it was saved from
+/// Γ is the mobility of whatever species 0 follows: M under Cahn-Hilliard, γ for a vesicle, D when it diffuses
+/// kT is in the units of that model's energy (A nm³ for Cahn-Hilliard, k for a vesicle)
+/// Faces on non-periodic boundaries carry no flux
+/// CPU and GPU implementations live in backend_admin/cpu/noise.rs and shaders/noise.wgsl
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ThermalNoise {
+    pub temperature: f32, // kT
+    pub conserved: bool
+}
+
+impl Default for ThermalNoise {
+    fn default() -> Self {
+        ThermalNoise {
+            temperature: 0.01,
+            conserved: true
+        }
+    }
+}
+
+impl ThermalNoise {
+    pub fn new(temperature: f32, conserved: bool) -> Self {
+        assert!(temperature >= 0.0, "Temperature should be >= 0.0\n");
+        ThermalNoise {
+            temperature: temperature,
+            conserved: conserved
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("{} noise kT = {}", if self.conserved { "conserved" } else { "non-conserved" }, self.temperature)
+    }
+
+    /// Standard deviation of each voxel's kick (non-conserved, all three equal)
+    /// or of each face's transfer per axis (conserved), for one step of timestep
+    pub fn amplitudes(&self, mobility: f32, timestep: f32, spacing: &P3) -> [f32; 3] {
+        let volume = spacing[0] * spacing[1] * spacing[2];
+        let amplitude = (2.0 * mobility * self.temperature * timestep / volume).max(0.0).sqrt();
+        if self.conserved { [amplitude / spacing[0], amplitude / spacing[1], amplitude / spacing[2]] }
+        else { [amplitude; 3] }
+    }
+
+    /// Parses "<kT>", "<kT>:conserved" or "<kT>:non-conserved", conserved unless stated
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (temperature, kind) = match spec.split_once(':') {
+            Some((temperature, kind)) => (temperature, Some(kind.trim())),
+            None => (spec, None)
+        };
+        let temperature = temperature.trim().parse::<f32>().map_err(|e| format!("Bad temperature '{}': {}", temperature, e))?;
+        if !(temperature >= 0.0 && temperature.is_finite()) { return Err("Noise temperature should be finite and >= 0".to_string()); }
+        let conserved = match kind {
+            None | Some("conserved") => true,
+            Some("non-conserved") => false,
+            Some(other) => return Err(format!("Unknown noise kind '{}', expected conserved or non-conserved", other))
+        };
+        Ok(ThermalNoise::new(temperature, conserved))
+    }
+}
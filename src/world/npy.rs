@@ -0,0 +1,341 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path
+};
+use crate::world::{field::VoxelField, voxel_grid::Dims3};
+
+/// NumPy .npy / .npz exchange for VoxelFields
+/// Voxels are stored x fastest (see field::voxel_index), which is C order for shape (z, y, x)
+/// and Fortran order for shape (x, y, z) - so both orders write the same bytes, only the header differs
+const NPY_MAGIC: &[u8; 6] = b"\x93NUMPY";
+const NPY_ALIGN: usize = 64; // header padded so data starts 64 byte aligned, as numpy does
+
+/// Axis order of the array numpy sees on load
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AxisOrder {
+    ZYX, // arr[z, y, x], C order
+    XYZ  // arr[x, y, z], Fortran order
+}
+
+/// Serialises a field as .npy (version 1.0, little endian f32)
+pub fn npy_bytes(field: &VoxelField, order: AxisOrder) -> Vec<u8> {
+    let [x, y, z] = field.dims;
+    let (fortran_order, shape) = match order {
+        AxisOrder::ZYX => ("False", [z, y, x]),
+        AxisOrder::XYZ => ("True", [x, y, z])
+    };
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': {}, 'shape': ({}, {}, {}), }}",
+        fortran_order, shape[0], shape[1], shape[2]);
+    // magic (6) + version (2) + header length (2) + header + '\n' must be a multiple of NPY_ALIGN
+    let unpadded = NPY_MAGIC.len() + 2 + 2 + header.len() + 1;
+    header.push_str(&" ".repeat(unpadded.next_multiple_of(NPY_ALIGN) - unpadded));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(NPY_MAGIC.len() + 4 + header.len() + field.data.len() * 4);
+    bytes.extend_from_slice(NPY_MAGIC);
+    bytes.extend_from_slice(&[1, 0]);
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend(field.data.iter().flat_map(|v| v.to_le_bytes()));
+    bytes
+}
+
+pub fn write_npy(path: &Path, field: &VoxelField, order: AxisOrder) -> Result<(), Box<dyn Error>> {
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(&npy_bytes(field, order))?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Bundles several named fields (time points, species...) into one .npz
+/// np.load(path)[name] gives each array back
+pub fn write_npz(path: &Path, arrays: &[(&str, &VoxelField)], order: AxisOrder) -> Result<(), Box<dyn Error>> {
+    let mut zip = StoredZip::new();
+    for (name, field) in arrays {
+        zip.add(&format!("{}.npy", name), npy_bytes(field, order))?;
+    }
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(&zip.finish()?)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Loads a 3D .npy array as a VoxelField, e.g. as an initial condition for State::seed_field()
+/// C order arrays are read as (z, y, x) and Fortran order arrays as (x, y, z), matching write_npy()
+/// Accepts little endian f4 and f8 (f8 is narrowed to f32)
+/// Rejects zero-length axes and shapes of more voxels than a u32 counts
+pub fn read_npy(path: &Path) -> Result<VoxelField, Box<dyn Error>> {
+    npy_from_bytes(&std::fs::read(path)?)
+}
+
+/// read_npy() of a file's bytes
+fn npy_from_bytes(bytes: &[u8]) -> Result<VoxelField, Box<dyn Error>> {
+    if bytes.len() < 10 || &bytes[..6] != NPY_MAGIC { return Err("Not a .npy file".into()); }
+
+    let (header_len, header_start) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 => {
+            if bytes.len() < 12 { return Err("Truncated .npy header".into()); }
+            (u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize, 12)
+        },
+        v => return Err(format!("Unsupported .npy version {}", v).into())
+    };
+    let data_start = header_start + header_len;
+    if bytes.len() < data_start { return Err("Truncated .npy header".into()); }
+    let header = std::str::from_utf8(&bytes[header_start..data_start])?;
+
+    let descr = header_value(header, "descr").ok_or(".npy header missing descr")?;
+    let fortran_order = header_value(header, "fortran_order").ok_or(".npy header missing fortran_order")? == "True";
+    let shape: Vec<u32> = header_value(header, "shape").ok_or(".npy header missing shape")?
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<u32>())
+        .collect::<Result<_, _>>()?;
+    if shape.len() != 3 { return Err(format!("Expected a 3D array, found shape {:?}", shape).into()); }
+
+    let dims: Dims3 = if fortran_order { [shape[0], shape[1], shape[2]] }
+        else { [shape[2], shape[1], shape[0]] };
+    if dims.contains(&0) { return Err(format!("Zero-length axis in shape {:?}", shape).into()); }
+    // voxel indices are u32 throughout, see field::voxel_index()
+    let len = dims.iter().try_fold(1u32, |len, d| len.checked_mul(*d))
+        .ok_or_else(|| format!("Shape {:?} has too many voxels", shape))? as usize;
+
+    let payload = &bytes[data_start..];
+    let data: Vec<f32> = match descr.trim_matches('\'') {
+        "<f4" if payload.len() / 4 >= len => payload.chunks_exact(4).take(len)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect(),
+        "<f8" if payload.len() / 8 >= len => payload.chunks_exact(8).take(len)
+            .map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32).collect(),
+        "<f4" | "<f8" => return Err("Truncated .npy data".into()),
+        other => return Err(format!("Unsupported .npy dtype {}", other).into())
+    };
+
+    Ok(VoxelField::new(dims, data))
+}
+
+/// Raw value text for key in a .npy header dict, e.g. "'<f4'", "False", "(8, 4, 2)"
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}'", key))? + key.len() + 2;
+    let rest = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = if rest.starts_with('(') { rest.find(')')? + 1 }
+        else { rest.find([',', '}'])? }; // the last key may have no trailing comma
+    Some(rest[..end].trim())
+}
+
+/// Minimal zip writer (stored, no compression) - all .npz needs
+struct StoredZip {
+    bytes: Vec<u8>,
+    central_directory: Vec<u8>,
+    entries: u16
+}
+
+impl StoredZip {
+    fn new() -> Self {
+        StoredZip {
+            bytes: Vec::new(),
+            central_directory: Vec::new(),
+            entries: 0
+        }
+    }
+
+    fn add(&mut self, name: &str, data: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        if data.len() > u32::MAX as usize || self.bytes.len() > u32::MAX as usize {
+            return Err(".npz entries over 4 GiB need zip64, which is not supported".into());
+        }
+        let crc = crc32(&data);
+        let offset = self.bytes.len() as u32;
+
+        // local file header
+        self.bytes.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        self.bytes.extend_from_slice(&Self::entry_fields(name, crc, data.len() as u32));
+        self.bytes.extend_from_slice(name.as_bytes());
+        self.bytes.extend_from_slice(&data);
+
+        // matching central directory record
+        self.central_directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        self.central_directory.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        self.central_directory.extend_from_slice(&Self::entry_fields(name, crc, data.len() as u32));
+        self.central_directory.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        self.central_directory.extend_from_slice(&0u16.to_le_bytes()); // disk number
+        self.central_directory.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        self.central_directory.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        self.central_directory.extend_from_slice(&offset.to_le_bytes());
+        self.central_directory.extend_from_slice(name.as_bytes());
+
+        self.entries += 1;
+        Ok(())
+    }
+
+    /// Fields shared by local headers and central directory records, from "version needed" to "extra length"
+    fn entry_fields(name: &str, crc: u32, size: u32) -> Vec<u8> {
+        let mut fields = Vec::with_capacity(26);
+        fields.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        fields.extend_from_slice(&0u16.to_le_bytes()); // flags
+        fields.extend_from_slice(&0u16.to_le_bytes()); // method: stored
+        fields.extend_from_slice(&0u16.to_le_bytes()); // mod time
+        fields.extend_from_slice(&0x21u16.to_le_bytes()); // mod date (1980-01-01)
+        fields.extend_from_slice(&crc.to_le_bytes());
+        fields.extend_from_slice(&size.to_le_bytes()); // compressed size
+        fields.extend_from_slice(&size.to_le_bytes()); // uncompressed size
+        fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes()); // extra length
+        fields
+    }
+
+    fn finish(mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+        if self.bytes.len() > u32::MAX as usize { return Err(".npz over 4 GiB needs zip64, which is not supported".into()); }
+        let directory_offset = self.bytes.len() as u32;
+        let directory_size = self.central_directory.len() as u32;
+        self.bytes.append(&mut self.central_directory);
+
+        // end of central directory record
+        self.bytes.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // this disk
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // directory disk
+        self.bytes.extend_from_slice(&self.entries.to_le_bytes());
+        self.bytes.extend_from_slice(&self.entries.to_le_bytes());
+        self.bytes.extend_from_slice(&directory_size.to_le_bytes());
+        self.bytes.extend_from_slice(&directory_offset.to_le_bytes());
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        Ok(self.bytes)
+    }
+}
+
+/// CRC-32 (IEEE, reflected) as required by zip
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(0xFFFF_FFFFu32, |crc, byte| {
+        CRC_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field() -> VoxelField {
+        let dims = [4, 3, 2];
+        VoxelField::new(dims, (0..24).map(|i| i as f32 * 0.5 - 3.0).collect())
+    }
+
+    /// A version 1.0 .npy of header dict and raw payload
+    fn npy_with(dict: &str, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = NPY_MAGIC.to_vec();
+        bytes.extend_from_slice(&[1, 0]);
+        bytes.extend_from_slice(&((dict.len() + 1) as u16).to_le_bytes());
+        bytes.extend_from_slice(dict.as_bytes());
+        bytes.push(b'\n');
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    fn error(bytes: &[u8]) -> String {
+        npy_from_bytes(bytes).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn round_trips_both_axis_orders() {
+        let field = field();
+        for (order, shape) in [(AxisOrder::ZYX, "(2, 3, 4)"), (AxisOrder::XYZ, "(4, 3, 2)")] {
+            let bytes = npy_bytes(&field, order);
+            assert_eq!((bytes.len() - field.data.len() * 4) % NPY_ALIGN, 0);
+            let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+            assert!(std::str::from_utf8(&bytes[10..10 + header_len]).unwrap().contains(shape));
+            let read = npy_from_bytes(&bytes).unwrap();
+            assert_eq!((read.dims, &read.data), (field.dims, &field.data));
+        }
+
+        let path = std::env::temp_dir().join(format!("bocs_npy_test_{}.npy", std::process::id()));
+        write_npy(&path, &field, AxisOrder::XYZ).unwrap();
+        let read = read_npy(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap().data, field.data);
+    }
+
+    #[test]
+    fn reads_headers_in_any_key_order_and_spacing() {
+        let payload: Vec<u8> = (0..6).flat_map(|i| (i as f32).to_le_bytes()).collect();
+        for dict in ["{'shape':(3,2,1),'descr':'<f4','fortran_order':True}", "{ 'fortran_order' : True , 'shape' : ( 3 , 2 , 1 ) , 'descr' : '<f4' }"] {
+            let read = npy_from_bytes(&npy_with(dict, &payload)).unwrap();
+            assert_eq!((read.dims, read.data.len()), ([3, 2, 1], 6), "{}", dict);
+        }
+    }
+
+    #[test]
+    fn reads_f8_narrowed() {
+        let payload: Vec<u8> = [0.25f64, -1.5].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let read = npy_from_bytes(&npy_with("{'descr': '<f8', 'fortran_order': False, 'shape': (1, 1, 2), }", &payload)).unwrap();
+        assert_eq!((read.dims, read.data), ([2, 1, 1], vec![0.25, -1.5]));
+    }
+
+    #[test]
+    fn rejects_malformed_arrays() {
+        let payload = vec![0; 4 * 8];
+        assert!(error(&npy_with("{'descr': '<f4', 'fortran_order': False, 'shape': (0, 2, 2), }", &payload)).contains("Zero-length axis"));
+        assert!(error(&npy_with("{'descr': '<f4', 'fortran_order': False, 'shape': (65536, 65536, 2), }", &payload)).contains("too many voxels"));
+        assert!(error(&npy_with("{'descr': '<f4', 'fortran_order': False, 'shape': (2, 2, 3), }", &payload)).contains("Truncated .npy data"));
+        assert!(error(&npy_with("{'descr': '<f8', 'fortran_order': False, 'shape': (2, 2, 2), }", &payload)).contains("Truncated .npy data"));
+        assert!(error(&npy_with("{'descr': '<f4', 'fortran_order': False, 'shape': (4, 8), }", &payload)).contains("Expected a 3D array"));
+        assert!(error(&npy_with("{'descr': '<i4', 'fortran_order': False, 'shape': (2, 2, 2), }", &payload)).contains("Unsupported .npy dtype"));
+        assert!(error(&npy_with("{'descr': '<f4', 'shape': (2, 2, 2), }", &payload)).contains("missing fortran_order"));
+        assert!(error(&npy_bytes(&field(), AxisOrder::ZYX)[..40]).contains("Truncated .npy header"));
+        assert!(error(b"PK\x03\x04 not an array").contains("Not a .npy file"));
+    }
+
+    #[test]
+    fn crc32_matches_the_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn npz_directory_points_at_every_entry() {
+        let (a, b) = (field(), VoxelField::new([1, 1, 2], vec![7.0, 8.0]));
+        let mut zip = StoredZip::new();
+        zip.add("a.npy", npy_bytes(&a, AxisOrder::ZYX)).unwrap();
+        zip.add("step_1.npy", npy_bytes(&b, AxisOrder::ZYX)).unwrap();
+        let bytes = zip.finish().unwrap();
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]) as usize;
+        let u32_at = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]) as usize;
+
+        // end of central directory record, then each central record against its local header and data
+        let end = bytes.len() - 22;
+        assert_eq!(u32_at(end), 0x06054b50);
+        assert_eq!((u16_at(end + 8), u16_at(end + 10)), (2, 2));
+        let (size, mut record) = (u32_at(end + 12), u32_at(end + 16));
+        assert_eq!(record + size, end);
+        for (name, field) in [("a.npy", &a), ("step_1.npy", &b)] {
+            assert_eq!(u32_at(record), 0x02014b50);
+            let (crc, length, name_length, local) = (u32_at(record + 16), u32_at(record + 24), u16_at(record + 28), u32_at(record + 42));
+            assert_eq!(&bytes[record + 46..record + 46 + name_length], name.as_bytes());
+            assert_eq!(u32_at(local), 0x04034b50);
+            assert_eq!((u32_at(local + 14), u32_at(local + 22)), (crc, length));
+            let data = &bytes[local + 30 + name_length..local + 30 + name_length + length];
+            assert_eq!(crc32(data) as usize, crc);
+            assert_eq!(npy_from_bytes(data).unwrap().data, field.data);
+            record += 46 + name_length;
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::world::network::MassAction;
+
+/// Stochastic reaction-diffusion (the reaction-diffusion master equation, RDME) for low copy numbers,
+/// where continuum concentrations are wrong: every voxel holds a whole number of molecules per species
+/// Diffusion: each molecule hops to one of its six neighbours with probability D dt / dx² per face (per axis spacing),
+/// drawn per voxel as a multinomial, so the expected counts after a step are exactly the explicit laplacian step's
+/// Neumann faces turn hops back, periodic ones wrap, Dirichlet faces are reservoirs at their value:
+/// molecules hopping out are absorbed and Poisson(D dt / dx² value Ω) hop in
+/// Reactions: an exact Gillespie SSA per voxel over each step, the network's mass-action rates (world/network.rs)
+/// turned into propensities k Ω^(1 - order) Π n!/(n - m)!, whose large-Ω limit is the deterministic network
+/// Ω (volume) is the number of molecules a voxel holds at concentration 1, fields read out as count / Ω,
+/// so rendering, export and checkpoints see concentrations as with any other backend
+/// Draws come from world/rng.rs keyed on Bridge::rand_seed and counted by the step index,
+/// so runs with the same seed reproduce exactly, and a resumed checkpoint continues the run it was saved from
+/// Runs on the CPU, see backend_admin/cpu/rdme.rs
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rdme {
+    pub volume: f32 // Ω, molecules per voxel at concentration 1
+}
+
+impl Default for Rdme {
+    fn default() -> Self {
+        Rdme {
+            volume: 100.0
+        }
+    }
+}
+
+impl Rdme {
+    pub fn new(volume: f32) -> Self {
+        assert!(volume > 0.0 && volume.is_finite(), "RDME volume should be finite and > 0.0\n");
+        Rdme {
+            volume: volume
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("RDME Ω = {} molecules per unit concentration", self.volume)
+    }
+
+    /// Parses "rdme" or "rdme:<Ω>", as given to --backend
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        match spec.trim().strip_prefix("rdme") {
+            Some("") => Ok(Rdme::default()),
+            Some(volume) if volume.starts_with(':') => {
+                let volume = volume[1..].trim();
+                let volume = volume.parse::<f32>().map_err(|e| format!("Bad RDME volume '{}': {}", volume, e))?;
+                if !(volume > 0.0 && volume.is_finite()) { return Err("RDME volume should be finite and > 0".to_string()); }
+                Ok(Rdme::new(volume))
+            },
+            _ => Err(format!("Expected rdme or rdme:<molecules per unit concentration>, got '{}'", spec))
+        }
+    }
+
+    /// Nearest whole number of molecules to a concentration, none below 0
+    pub fn count(&self, concentration: f32) -> u32 {
+        (concentration as f64 * self.volume as f64).round().clamp(0.0, u32::MAX as f64) as u32
+    }
+
+    pub fn concentration(&self, count: u32) -> f32 {
+        (count as f64 / self.volume as f64) as f32
+    }
+
+    /// Probability per µs that reaction fires in a voxel holding counts
+    /// k Ω^(1 - order) times the number of distinct reactant combinations, Π n (n - 1) .. (n - m + 1)
+    pub fn propensity(&self, reaction: &MassAction, counts: &[u32]) -> f64 {
+        let scale = reaction.rate as f64 * (self.volume as f64).powi(1 - reaction.order() as i32);
+        reaction.reactants.iter().fold(scale, |propensity, (species, m)| {
+            (0..*m).fold(propensity, |p, i| p * counts[*species].saturating_sub(i) as f64)
+        })
+    }
+}
@@ -0,0 +1,295 @@
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use crate::world::voxel_grid::Dims3;
+
+/// Two-species reaction-diffusion: u (the original field) and v diffuse with the chosen integrator,
+/// then react pointwise by one explicit Euler step (Lie splitting), see reaction.wgsl and cpu/reaction.rs
+/// du/dt = D L u + R_u(u, v), dv/dt = ratio D L v + R_v(u, v)
+/// Reaction rates are polynomials in u and v with rates per µs, so every model lowers to the same kernel
+/// Both species share the boundary conditions, Dirichlet values included
+pub const MAX_TERMS: usize = 8; // per species, as reaction.wgsl's uniforms hold them
+pub const SPECIES: usize = 2;
+pub const MAX_POWER: u32 = 8; // of u or v in one term, higher powers are neither stable nor cheap
+
+/// Gray-Scott defaults in the spot-forming regime (Pearson 1993)
+pub const GRAY_SCOTT_FEED: f32 = 0.037;
+pub const GRAY_SCOTT_KILL: f32 = 0.06;
+pub const DEFAULT_DIFFUSION_RATIO: f32 = 0.5; // D_v / D_u
+
+/// coefficient * u^powers[0] * v^powers[1]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Term {
+    pub coefficient: f32,
+    pub powers: [u32; 2]
+}
+
+impl Term {
+    pub fn new(coefficient: f32, powers: [u32; 2]) -> Self {
+        Term { coefficient: coefficient, powers: powers }
+    }
+
+    /// Same multiplication order as reaction.wgsl's term(), so both backends round alike
+    pub fn evaluate(&self, u: f32, v: f32) -> f32 {
+        self.coefficient * int_pow(u, self.powers[0]) * int_pow(v, self.powers[1])
+    }
+}
+
+/// x^n by squaring, as powi's compiler-rt __powisf2 multiplies and the shaders' int_pow() mirror
+pub fn int_pow(x: f32, n: u32) -> f32 {
+    x.powi(n as i32)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ReactionModel {
+    /// R_u = -u v^2 + F (1 - u), R_v = u v^2 - (F + k) v
+    GrayScott { feed: f32, kill: f32 },
+    /// R_u and R_v as sums of at most MAX_TERMS terms each
+    Polynomial(Polynomial)
+}
+
+/// Reaction rates of u and v
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Polynomial {
+    terms: [[Term; MAX_TERMS]; SPECIES],
+    counts: [usize; SPECIES]
+}
+
+impl Polynomial {
+    pub fn new(u_terms: &[Term], v_terms: &[Term]) -> Result<Self, String> {
+        let mut polynomial = Polynomial { terms: [[Term::new(0.0, [0, 0]); MAX_TERMS]; SPECIES], counts: [0; SPECIES] };
+        for (species, terms) in [u_terms, v_terms].into_iter().enumerate() {
+            if terms.len() > MAX_TERMS {
+                return Err(format!("{} terms for {}, at most {} are supported", terms.len(), species_name(species), MAX_TERMS));
+            }
+            if terms.iter().any(|t| !t.coefficient.is_finite()) {
+                return Err(format!("A term of {} has a coefficient that is not finite", species_name(species)));
+            }
+            if terms.iter().any(|t| t.powers.iter().any(|p| *p > MAX_POWER)) {
+                return Err(format!("A term of {} has a power above {}", species_name(species), MAX_POWER));
+            }
+            polynomial.terms[species][..terms.len()].copy_from_slice(terms);
+            polynomial.counts[species] = terms.len();
+        }
+        Ok(polynomial)
+    }
+
+    pub fn terms(&self, species: usize) -> &[Term] {
+        &self.terms[species][..self.counts[species]]
+    }
+
+    /// Rate of change of species at (u, v)
+    pub fn rate(&self, species: usize, u: f32, v: f32) -> f32 {
+        self.terms(species).iter().fold(0.0, |sum, term| sum + term.evaluate(u, v))
+    }
+
+    /// Parses "du=<terms>;dv=<terms>", terms being products of a number, u, v, u^n and v^n joined by + and -, powers at most MAX_POWER
+    /// e.g. "du=0.037-0.037*u-u*v^2;dv=u*v^2-0.097*v" (Gray-Scott)
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut terms: [Option<Vec<Term>>; SPECIES] = [None, None];
+        for equation in spec.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+            let (lhs, rhs) = equation.split_once('=').ok_or(format!("Expected du=... or dv=..., got '{}'", equation))?;
+            let species = match lhs.trim() {
+                "du" => 0,
+                "dv" => 1,
+                other => return Err(format!("Unknown rate '{}', expected du or dv", other))
+            };
+            if terms[species].is_some() { return Err(format!("{} is given twice", lhs.trim())); }
+            terms[species] = Some(parse_terms(rhs)?);
+        }
+        let [u_terms, v_terms] = terms;
+        Polynomial::new(&u_terms.unwrap_or_default(), &v_terms.unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Reaction {
+    pub model: ReactionModel,
+    pub diffusion_ratio: f32 // D_v / D_u
+}
+
+impl Reaction {
+    pub fn gray_scott(feed: f32, kill: f32) -> Self {
+        Reaction { model: ReactionModel::GrayScott { feed: feed, kill: kill }, diffusion_ratio: DEFAULT_DIFFUSION_RATIO }
+    }
+
+    pub fn polynomial(polynomial: Polynomial) -> Self {
+        Reaction { model: ReactionModel::Polynomial(polynomial), diffusion_ratio: DEFAULT_DIFFUSION_RATIO }
+    }
+
+    pub fn with_diffusion_ratio(mut self, ratio: f32) -> Self {
+        assert!(ratio >= 0.0, "Diffusion ratio should be >= 0.0\n");
+        self.diffusion_ratio = ratio;
+        self
+    }
+
+    pub fn name(&self) -> String {
+        match self.model {
+            ReactionModel::GrayScott { feed, kill } => format!("Gray-Scott F = {}, k = {}", feed, kill),
+            ReactionModel::Polynomial(_) => "polynomial reaction".to_string()
+        }
+    }
+
+    /// The model lowered to the polynomial both backends evaluate
+    pub fn rates(&self) -> Polynomial {
+        match self.model {
+            ReactionModel::GrayScott { feed, kill } => Polynomial::new(
+                &[Term::new(-1.0, [1, 2]), Term::new(feed, [0, 0]), Term::new(-feed, [1, 0])],
+                &[Term::new(1.0, [1, 2]), Term::new(-(feed + kill), [0, 1])]
+            ).expect("Gray-Scott fits in MAX_TERMS"),
+            ReactionModel::Polynomial(polynomial) => polynomial
+        }
+    }
+
+    /// Bound on a stable explicit reaction step, µs
+    /// For concentrations of order 1 every |dR/du| + |dR/dv| is at most sum |c| (a + b) over a species' terms,
+    /// and explicit Euler on a rate λ needs dt <= 1 / λ
+    pub fn stable_timestep(&self) -> f32 {
+        let rates = self.rates();
+        let stiffness = (0..SPECIES)
+            .map(|species| rates.terms(species).iter().map(|t| t.coefficient.abs() * (t.powers[0] + t.powers[1]) as f32).sum::<f32>())
+            .fold(0.0, f32::max);
+        if stiffness == 0.0 { f32::INFINITY } else { 1.0 / stiffness }
+    }
+
+    /// Initial u and v in voxel_index order, from seed
+    /// Gray-Scott: u = 1, v = 0, with a central cube a fifth of the grid across at u = 0.5, v = 0.25, all with 1% noise
+    /// Polynomial: uniform noise in [0, 1) for both, as init.wgsl seeds a single field
+    pub fn initial_fields(&self, dims: &Dims3, seed: u32) -> [Vec<f32>; SPECIES] {
+        let len = (dims[0] * dims[1] * dims[2]) as usize;
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+        match self.model {
+            ReactionModel::GrayScott { .. } => {
+                let (mut u, mut v) = (vec![1.0; len], vec![0.0; len]);
+                let inside = |i: u32, d: u32| (i as i64 - (d / 2) as i64).unsigned_abs() <= (d / 10) as u64;
+                for z in 0..dims[2] {
+                    for y in 0..dims[1] {
+                        for x in 0..dims[0] {
+                            let idx = (x + (y * dims[0]) + (z * dims[0] * dims[1])) as usize;
+                            if inside(x, dims[0]) && inside(y, dims[1]) && inside(z, dims[2]) {
+                                u[idx] = 0.5;
+                                v[idx] = 0.25;
+                            }
+                            u[idx] += 0.01 * (rng.random::<f32>() - 0.5);
+                            v[idx] = (v[idx] + 0.01 * (rng.random::<f32>() - 0.5)).max(0.0);
+                        }
+                    }
+                }
+                [u, v]
+            },
+            ReactionModel::Polynomial(_) => {
+                let u = (0..len).map(|_| rng.random::<f32>()).collect();
+                let v = (0..len).map(|_| rng.random::<f32>()).collect();
+                [u, v]
+            }
+        }
+    }
+
+    /// Parses "gray-scott", "gray-scott:<F>:<k>" or "poly:<du=...;dv=...>" (see Polynomial::parse), F and k finite and >= 0
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        if let Some(polynomial) = spec.strip_prefix("poly:") {
+            return Ok(Reaction::polynomial(Polynomial::parse(polynomial)?));
+        }
+        let parts: Vec<&str> = spec.split(':').collect();
+        let number = |s: &str| match s.parse::<f32>() {
+            Ok(rate) if rate >= 0.0 && rate.is_finite() => Ok(rate),
+            Ok(rate) => Err(format!("Rate {} should be finite and >= 0", rate)),
+            Err(e) => Err(format!("Bad rate '{}': {}", s, e))
+        };
+        match parts.as_slice() {
+            ["gray-scott"] => Ok(Reaction::gray_scott(GRAY_SCOTT_FEED, GRAY_SCOTT_KILL)),
+            ["gray-scott", feed, kill] => Ok(Reaction::gray_scott(number(feed)?, number(kill)?)),
+            _ => Err(format!("Unknown reaction '{}', expected gray-scott[:<F>:<k>] or poly:du=...;dv=...", spec))
+        }
+    }
+}
+
+pub fn species_name(species: usize) -> &'static str {
+    ["u", "v"][species]
+}
+
+/// "0.037-0.037*u-u*v^2" -> terms, splitting on + and - that don't belong to a number's exponent
+fn parse_terms(rhs: &str) -> Result<Vec<Term>, String> {
+    let rhs: String = rhs.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut terms = Vec::new();
+    let mut start = 0;
+    let bytes = rhs.as_bytes();
+    for i in 1..=bytes.len() {
+        let split = i == bytes.len() || ((bytes[i] == b'+' || bytes[i] == b'-') && !matches!(bytes[i - 1], b'e' | b'E' | b'*' | b'^'));
+        if split {
+            terms.push(parse_term(&rhs[start..i])?);
+            start = i;
+        }
+    }
+    Ok(terms)
+}
+
+/// "-0.5*u^2*v" -> Term { -0.5, [2, 1] }
+fn parse_term(term: &str) -> Result<Term, String> {
+    let (sign, body) = match term.as_bytes().first() {
+        Some(b'-') => (-1.0, &term[1..]),
+        Some(b'+') => (1.0, &term[1..]),
+        _ => (1.0, term)
+    };
+    if body.is_empty() { return Err(format!("Empty term in '{}'", term)); }
+    let mut parsed = Term::new(sign, [0, 0]);
+    for factor in body.split('*') {
+        let (base, power) = match factor.split_once('^') {
+            Some((base, power)) => (base, power.parse::<u32>().map_err(|e| format!("Bad power '{}': {}", power, e))?),
+            None => (factor, 1)
+        };
+        if power > MAX_POWER { return Err(format!("Power {} in '{}' exceeds {}", power, term, MAX_POWER)); }
+        let species = match base {
+            "u" => 0,
+            "v" => 1,
+            number => {
+                parsed.coefficient *= int_pow(number.parse::<f32>().map_err(|e| format!("Bad factor '{}': {}", number, e))?, power);
+                continue;
+            }
+        };
+        parsed.powers[species] = parsed.powers[species].checked_add(power).filter(|p| *p <= MAX_POWER)
+            .ok_or(format!("Power of {} in '{}' exceeds {}", species_name(species), term, MAX_POWER))?;
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_gray_scott_and_rejects_bad_rates() {
+        assert_eq!(Reaction::parse("gray-scott").unwrap(), Reaction::gray_scott(GRAY_SCOTT_FEED, GRAY_SCOTT_KILL));
+        assert_eq!(Reaction::parse(" gray-scott:0.03:0.062 ").unwrap(), Reaction::gray_scott(0.03, 0.062));
+        for spec in ["gray-scott:-0.01:0.06", "gray-scott:0.03:inf", "gray-scott:nan:0.06", "gray-scott:0.03", "gray-scott:a:b", "brusselator"] {
+            assert!(Reaction::parse(spec).is_err(), "{} should be rejected", spec);
+        }
+    }
+
+    #[test]
+    fn polynomial_lowers_to_the_gray_scott_rates() {
+        let parsed = Polynomial::parse("du=0.037-0.037*u-u*v^2;dv=u*v^2-0.097*v").unwrap();
+        let lowered = Reaction::gray_scott(0.037, 0.06).rates();
+        for (u, v) in [(1.0, 0.0), (0.5, 0.25), (0.2, 0.8)] {
+            for species in 0..SPECIES {
+                assert!((parsed.rate(species, u, v) - lowered.rate(species, u, v)).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn polynomial_parses_products_powers_and_exponents() {
+        let parsed = Polynomial::parse("du = -2*u^2*v + 1.5e-1 + u*u;dv=").unwrap();
+        assert_eq!(parsed.terms(0), &[Term::new(-2.0, [2, 1]), Term::new(0.15, [0, 0]), Term::new(1.0, [2, 0])]);
+        assert!(parsed.terms(1).is_empty());
+        assert_eq!(Polynomial::parse("dv=2^3*v").unwrap().terms(1), &[Term::new(8.0, [0, 1])]);
+    }
+
+    #[test]
+    fn polynomial_rejects_malformed_terms() {
+        for spec in ["du=u^9", "du=u^4000000000*u^4000000000", "du=u^5*u^4", "du=inf*u", "du=1e30^8", "du=nan",
+                     "du=u+", "du=w", "du=u^-1", "dw=u", "du=u;du=v", "u"] {
+            assert!(Polynomial::parse(spec).is_err(), "{} should be rejected", spec);
+        }
+        assert!(Polynomial::parse("du=u^8*v^8").is_ok());
+    }
+}
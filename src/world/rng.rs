@@ -0,0 +1,164 @@
+// Counter-based random numbers: Philox4x32-10 (Salmon et al., "Parallel random numbers: as easy as 1, 2, 3", SC11)
+// A pure function of a 128-bit counter and a 64-bit key, so every voxel of every step draws its own numbers
+// without any generator state to carry between passes, threads or backends
+// Integer arithmetic only, so shaders/rng.wgsl (shared by init.wgsl, noise.wgsl and particles.wgsl) produces the same bits,
+// uniform() is exact on both too, only normals() differ by the rounding of ln, cos and sin
+// Streams: key = [Bridge::rand_seed, stream], one stream per consumer so they never share numbers
+
+pub const PHILOX_M0: u32 = 0xD251_1F53;
+pub const PHILOX_M1: u32 = 0xCD9E_8D57;
+pub const PHILOX_W0: u32 = 0x9E37_79B9; // golden ratio
+pub const PHILOX_W1: u32 = 0xBB67_AE85; // sqrt(3) - 1
+pub const PHILOX_ROUNDS: usize = 10;
+
+pub const INIT_STREAM: u32 = 0x696E_6974; // "init", the random initial field, see init.wgsl
+pub const NOISE_STREAM: u32 = 0x6E6F_6973; // "nois", thermal noise, see world/noise.rs
+pub const HOP_STREAM: u32 = 0x686F_7073; // "hops", RDME diffusion, see world/rdme.rs
+pub const BATH_STREAM: u32 = 0x6261_7468; // "bath", RDME molecules entering through Dirichlet faces
+pub const SSA_STREAM: u32 = 0x7373_6120; // "ssa ", RDME reactions
+pub const BROWNIAN_STREAM: u32 = 0x6272_776E; // "brwn", particle steps, see world/brownian_motion.rs
+pub const SCATTER_STREAM: u32 = 0x7363_6174; // "scat", particles' starting positions
+
+/// (high, low) words of the 64-bit product
+fn mulhilo(a: u32, b: u32) -> (u32, u32) {
+    let product = (a as u64) * (b as u64);
+    ((product >> 32) as u32, product as u32)
+}
+
+/// Four random words for counter under key
+pub fn philox4x32(counter: [u32; 4], key: [u32; 2]) -> [u32; 4] {
+    let (mut c, mut k) = (counter, key);
+    for round in 0..PHILOX_ROUNDS {
+        if round > 0 {
+            k = [k[0].wrapping_add(PHILOX_W0), k[1].wrapping_add(PHILOX_W1)];
+        }
+        let (hi0, lo0) = mulhilo(PHILOX_M0, c[0]);
+        let (hi1, lo1) = mulhilo(PHILOX_M1, c[2]);
+        c = [hi1 ^ c[1] ^ k[0], lo1, hi0 ^ c[3] ^ k[1], lo0];
+    }
+    c
+}
+
+/// A word as a uniform strictly inside (0, 1), from its top 23 bits: (k + 0.5) / 2^23 is exact in f32 for every k,
+/// so it is never 0 (safe under ln) and never rounds up to 1 (safe for inversion), at most 1 - 2^-24
+pub fn uniform(word: u32) -> f32 {
+    (((word >> 9) as f32) + 0.5) / 8_388_608.0
+}
+
+/// Four standard normals for counter under key, two Box-Muller pairs
+pub fn normals(counter: [u32; 4], key: [u32; 2]) -> [f32; 4] {
+    let words = philox4x32(counter, key);
+    let pair = |a: u32, b: u32| {
+        let radius = (-2.0 * uniform(a).ln()).sqrt();
+        let angle = std::f32::consts::TAU * uniform(b);
+        [radius * angle.cos(), radius * angle.sin()]
+    };
+    let ([n0, n1], [n2, n3]) = (pair(words[0], words[1]), pair(words[2], words[3]));
+    [n0, n1, n2, n3]
+}
+
+/// Uniforms drawn one after another from consecutive counters: counter[1] counts blocks of four words,
+/// the rest of counter and the key stay put, so a consumer can draw as many numbers as it needs from one position
+pub struct UniformStream {
+    counter: [u32; 4],
+    key: [u32; 2],
+    words: [u32; 4],
+    next: usize // words already used, 4 refills
+}
+
+impl UniformStream {
+    pub fn new(counter: [u32; 4], key: [u32; 2]) -> Self {
+        UniformStream { counter: counter, key: key, words: [0; 4], next: 4 }
+    }
+
+    pub fn uniform(&mut self) -> f32 {
+        if self.next == 4 {
+            self.words = philox4x32(self.counter, self.key);
+            self.counter[1] = self.counter[1].wrapping_add(1);
+            self.next = 0;
+        }
+        self.next += 1;
+        uniform(self.words[self.next - 1])
+    }
+}
+
+/// Largest n (binomial) or mean (Poisson) one uniform is inverted for, larger ones are drawn as sums of smaller ones
+/// so q^n and e^-mean never underflow
+const BINOMIAL_CHUNK: u32 = 1000;
+const POISSON_CHUNK: f64 = 500.0;
+
+/// Whether inversion has reached the cdf's rounding: past the mean with a pmf too small to move the cdf,
+/// so u is only above the cdf because the cdf summed to a little under 1, and the draw stops where it is
+fn cdf_exhausted(k: u32, mean: f64, pmf: f64, cdf: f64) -> bool {
+    k as f64 > mean && pmf < cdf * f64::EPSILON
+}
+
+/// Successes of n trials with probability p, by inversion, one uniform per BINOMIAL_CHUNK trials
+pub fn binomial(n: u32, p: f64, stream: &mut UniformStream) -> u32 {
+    if n == 0 || p <= 0.0 { return 0; }
+    if p > 0.5 { return n - binomial(n, 1.0 - p, stream); } // keeps q^n above f64's smallest normal
+    let (mut successes, mut left) = (0, n);
+    while left > 0 {
+        let trials = left.min(BINOMIAL_CHUNK);
+        left -= trials;
+        let u = stream.uniform() as f64;
+        let (mut k, mut pmf) = (0, (1.0 - p).powi(trials as i32));
+        let mut cdf = pmf;
+        while u > cdf && k < trials && !cdf_exhausted(k, trials as f64 * p, pmf, cdf) {
+            pmf *= ((trials - k) as f64 / (k + 1) as f64) * (p / (1.0 - p));
+            k += 1;
+            cdf += pmf;
+        }
+        successes += k;
+    }
+    successes
+}
+
+/// A Poisson draw of the given mean, by inversion, one uniform per POISSON_CHUNK of mean
+pub fn poisson(mean: f64, stream: &mut UniformStream) -> u32 {
+    let (mut count, mut left) = (0, mean.max(0.0));
+    while left > 0.0 {
+        let chunk = left.min(POISSON_CHUNK);
+        left -= chunk;
+        let u = stream.uniform() as f64;
+        let (mut k, mut pmf) = (0u32, (-chunk).exp());
+        let mut cdf = pmf;
+        while u > cdf && !cdf_exhausted(k, chunk, pmf, cdf) {
+            k += 1;
+            pmf *= chunk / k as f64;
+            cdf += pmf;
+        }
+        count += k;
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn philox_matches_the_sc11_known_answers() {
+        // Random123's kat_vectors for philox4x32_10
+        assert_eq!(philox4x32([0; 4], [0; 2]), [0x6627e8d5, 0xe169c58d, 0xbc57ac4c, 0x9b00dbd8]);
+        assert_eq!(philox4x32([u32::MAX; 4], [u32::MAX; 2]), [0x408f276d, 0x41c83b0e, 0xa20bc7c6, 0x6d5451fd]);
+        assert_eq!(philox4x32([0x243f6a88, 0x85a308d3, 0x13198a2e, 0x03707344], [0xa4093822, 0x299f31d0]),
+            [0xd16cfe09, 0x94fdcceb, 0x5001e420, 0x24126ea1]);
+    }
+
+    #[test]
+    fn uniform_stays_strictly_inside_unit_interval() {
+        assert!(uniform(0) > 0.0);
+        assert!(uniform(u32::MAX) < 1.0);
+        assert_eq!(uniform(u32::MAX), 1.0 - 1.0 / 16_777_216.0);
+    }
+
+    #[test]
+    fn samplers_stay_within_support_at_the_top_uniform() {
+        // a stream whose first word has its top 23 bits set, as inversion's worst case
+        let key = [0, BATH_STREAM];
+        let counter = (0u32..).map(|c| [c, 0, 0, 0]).find(|c| philox4x32(*c, key)[0] >> 9 == (1 << 23) - 1).unwrap();
+        assert!(binomial(1000, 0.01, &mut UniformStream::new(counter, key)) < 60);
+        assert!(poisson(0.001, &mut UniformStream::new(counter, key)) <= 2);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::world::{
+    boundary::BoundaryConditions,
+    diffusion::Diffusion,
+    reaction::Reaction,
+    rng::{self, INIT_STREAM},
+    voxel_grid::Dims3};
+
+/// Most species a run can hold, every one is a ping/pong pair of full fields (see Resources::set_species_count)
+pub const MAX_SPECIES: usize = 8;
+
+/// Name of the species a reaction adds when the registry has none to couple species 0 to
+pub const REACTION_PARTNER: &str = "v";
+
+/// How a species' field starts, see SimulationBackend::init()
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InitialCondition {
+    Random, // uniform in (0, 1) per voxel from world/rng.rs, species 0's is exactly init.wgsl's
+    Constant(f32)
+}
+
+impl InitialCondition {
+    /// The field in voxel_index order, the same on every backend
+    /// Random draws are counted by voxel index and species, so every species of a seed gets its own numbers
+    pub fn field(&self, dims: &Dims3, seed: u32, species: usize) -> Vec<f32> {
+        let len = (dims[0] * dims[1] * dims[2]) as usize;
+        match self {
+            InitialCondition::Random => (0..len)
+                .map(|idx| rng::uniform(rng::philox4x32([idx as u32, species as u32, 0, 0], [seed, INIT_STREAM])[0]))
+                .collect(),
+            InitialCondition::Constant(value) => vec![*value; len]
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            InitialCondition::Random => "random".to_string(),
+            InitialCondition::Constant(value) => value.to_string()
+        }
+    }
+
+    /// "random" or a constant, e.g. "0.25"
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        match spec.trim() {
+            "random" => Ok(InitialCondition::Random),
+            value => match value.parse::<f32>() {
+                Ok(value) if value.is_finite() => Ok(InitialCondition::Constant(value)),
+                _ => Err(format!("Unknown initial condition '{}', expected random or a number", value))
+            }
+        }
+    }
+}
+
+/// One field of the simulation: a lipid, a protein, the solvent...
+/// diffusion and boundaries are the run's (PhysicalUnits' D, World::boundaries) while None,
+/// so a species without its own follows --diffusion, --bounds and B in the app
+#[derive(Debug, Clone, PartialEq)]
+pub struct Species {
+    pub name: String,
+    pub diffusion: Option<f32>, // D in µm²/s, i.e. nm²/µs
+    pub boundaries: Option<BoundaryConditions>,
+    pub initial: InitialCondition
+}
+
+impl Species {
+    /// Follows the run's D and boundaries, seeded at random
+    pub fn new(name: &str) -> Self {
+        Species {
+            name: name.to_string(),
+            diffusion: None,
+            boundaries: None,
+            initial: InitialCondition::Random
+        }
+    }
+
+    pub fn with_diffusion(mut self, diffusion: f32) -> Self {
+        assert!(diffusion >= 0.0, "Diffusion coefficient should be >= 0.0\n");
+        self.diffusion = Some(diffusion);
+        self
+    }
+
+    pub fn with_boundaries(mut self, boundaries: BoundaryConditions) -> Self {
+        self.boundaries = Some(boundaries);
+        self
+    }
+
+    pub fn with_initial(mut self, initial: InitialCondition) -> Self {
+        self.initial = initial;
+        self
+    }
+
+    /// Parses "<name>[:<D>[:<initial>[:<bounds>]]]", e.g. "lipid:0.5:random:xyz=periodic" or "protein::0.1"
+    /// Empty fields keep the run's value, bounds takes the --bounds syntax
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut fields = spec.splitn(4, ':').map(|f| f.trim());
+        let name = fields.next().unwrap_or("");
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+            return Err(format!("Bad species name '{}', expected letters, digits, _ or -", name));
+        }
+        let mut species = Species::new(name);
+        if let Some(diffusion) = fields.next().filter(|f| !f.is_empty()) {
+            let diffusion = diffusion.parse::<f32>().map_err(|e| format!("Bad diffusion coefficient '{}': {}", diffusion, e))?;
+            if !(diffusion >= 0.0 && diffusion.is_finite()) { return Err(format!("Species {} should diffuse with a finite D >= 0", name)); }
+            species = species.with_diffusion(diffusion);
+        }
+        if let Some(initial) = fields.next().filter(|f| !f.is_empty()) {
+            species = species.with_initial(InitialCondition::parse(initial)?);
+        }
+        if let Some(bounds) = fields.next().filter(|f| !f.is_empty()) {
+            species = species.with_boundaries(BoundaryConditions::parse(bounds)?);
+        }
+        Ok(species)
+    }
+
+    /// e.g. "lipid (D = 0.5 µm²/s, random)", D and boundaries only when they aren't the run's
+    pub fn describe(&self) -> String {
+        let mut details = Vec::new();
+        if let Some(diffusion) = self.diffusion { details.push(format!("D = {} µm²/s", diffusion)); }
+        if self.boundaries.is_some() { details.push("own boundaries".to_string()); }
+        details.push(self.initial.name());
+        format!("{} ({})", self.name, details.join(", "))
+    }
+}
+
+/// Every species the backends store, in order
+/// Species 0 is the one Cahn-Hilliard, a vesicle and thermal noise act on and the one drawn by default;
+/// while reacting, species 1 is the one the reaction couples to it
+/// Resources stores them as planes of one ping/pong pair, the CPU backend as a pair each
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeciesRegistry {
+    species: Vec<Species>
+}
+
+impl Default for SpeciesRegistry {
+    /// Species 0 alone, "u" as the reaction names it
+    fn default() -> Self {
+        SpeciesRegistry {
+            species: vec![Species::new("u")]
+        }
+    }
+}
+
+impl SpeciesRegistry {
+    pub fn new(species: Vec<Species>) -> Result<Self, String> {
+        let mut registry = SpeciesRegistry { species: Vec::new() };
+        for s in species {
+            registry.add(s)?;
+        }
+        if registry.species.is_empty() { return Err("A run needs at least one species".to_string()); }
+        Ok(registry)
+    }
+
+    /// Appends a species, names are unique
+    pub fn add(&mut self, species: Species) -> Result<usize, String> {
+        if self.index_of(&species.name).is_some() { return Err(format!("Species {} is already registered", species.name)); }
+        if self.species.len() == MAX_SPECIES { return Err(format!("At most {} species are supported", MAX_SPECIES)); }
+        self.species.push(species);
+        Ok(self.species.len() - 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.species.len()
+    }
+
+    pub fn get(&self, species: usize) -> Option<&Species> {
+        self.species.get(species)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Species> {
+        self.species.iter()
+    }
+
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.species.iter().position(|s| s.name == name)
+    }
+
+    /// Name of a species, "?" past the end
+    pub fn name(&self, species: usize) -> &str {
+        self.species.get(species).map(|s| s.name.as_str()).unwrap_or("?")
+    }
+
+    /// The registry a reaction runs on: a REACTION_PARTNER is added when species 0 has nothing to couple to,
+    /// and dropped again (only if it is still the default one) once the reaction is
+    pub fn with_reaction(mut self, reacting: bool) -> Self {
+        let partner = Species::new(REACTION_PARTNER);
+        if reacting && self.species.len() < 2 {
+            self.species.push(partner);
+        }
+        else if !reacting && self.species.len() == 2 && self.species[1] == partner {
+            self.species.truncate(1);
+        }
+        self
+    }
+
+    /// D and spacing of a species: its own D if it has one, else the run's,
+    /// scaled by the reaction's diffusion ratio for species 1 while reacting (see world/reaction.rs)
+    pub fn diffusion(&self, species: usize, run: &Diffusion, reaction: Option<&Reaction>) -> Diffusion {
+        match (self.species.get(species).and_then(|s| s.diffusion), species, reaction) {
+            (Some(coefficient), _, _) => Diffusion::new(coefficient, run.spacing),
+            (None, 1, Some(reaction)) => Diffusion::new(run.coefficient * reaction.diffusion_ratio, run.spacing),
+            (None, _, _) => *run
+        }
+    }
+
+    /// Boundary faces of a species: its own if it has them, else the run's
+    pub fn boundaries(&self, species: usize, run: &BoundaryConditions) -> BoundaryConditions {
+        self.species.get(species).and_then(|s| s.boundaries).unwrap_or(*run)
+    }
+
+    /// Parses the species after species 0, separated by ';', see Species::parse()
+    /// e.g. "lipid:0.5;protein:0.05:0.1;solvent"
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut registry = SpeciesRegistry::default();
+        for entry in spec.split(';').map(|e| e.trim()).filter(|e| !e.is_empty()) {
+            registry.add(Species::parse(entry)?)?;
+        }
+        Ok(registry)
+    }
+}
@@ -0,0 +1,116 @@
+use crate::world::{cahn_hilliard::CahnHilliard, integrator::Integrator, network::ReactionNetwork, reaction::Reaction, species::SpeciesRegistry, units::PhysicalUnits, vesicle::Vesicle};
+
+/// Adaptive timestep control for the explicit operators
+/// Every active operator bounds the step it can take stably; the clock's dt is the step the user asked for,
+/// and when it exceeds the tightest bound each step is split into equal sub-steps that all stay within it
+/// sim time and step count still advance by dt per step, so sub-stepping never changes what a step means
+/// Past MAX_SUBSTEPS a step is refused rather than ground through, see TimestepController::plan()
+pub const MAX_SUBSTEPS: u32 = 1000;
+
+/// The largest stable step of one operator, µs
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StabilityLimit {
+    pub operator: &'static str,
+    pub max_dt: f32
+}
+
+/// Limits of every operator active with these units
+/// Diffusion: dt <= 1 / (2D (1/dx^2 + 1/dy^2 + 1/dz^2)), i.e. dx^2 / 6D when isotropic,
+/// only when it is integrated explicitly, and for the fastest species that diffuses (species 0 doesn't under Cahn-Hilliard or a vesicle),
+/// each at its own D, see SpeciesRegistry::diffusion()
+/// Reaction: always explicit, see Reaction::stable_timestep()
+/// Network: always explicit too, see ReactionNetwork::stable_timestep()
+/// Cahn-Hilliard: stable at any step, see world/cahn_hilliard.rs
+/// Bending: the vesicle is always explicit, see Vesicle::stable_timestep(), and only steps while Cahn-Hilliard isn't set
+pub fn stability_limits(units: &PhysicalUnits, integrator: &Integrator, species: &SpeciesRegistry, reaction: Option<&Reaction>, network: Option<&ReactionNetwork>, cahn_hilliard: Option<&CahnHilliard>, vesicle: Option<&Vesicle>) -> Vec<StabilityLimit> {
+    let mut limits = Vec::new();
+    let vesicle = vesicle.filter(|_| cahn_hilliard.is_none());
+    let first = if cahn_hilliard.is_none() && vesicle.is_none() { 0 } else { 1 };
+    let tightest = (first..species.len())
+        .map(|i| species.diffusion(i, &units.diffusion(), reaction).stable_timestep())
+        .reduce(f32::min);
+    if integrator.is_explicit() && let Some(max_dt) = tightest {
+        limits.push(StabilityLimit { operator: "diffusion", max_dt: max_dt });
+    }
+    if let Some(reaction) = reaction {
+        limits.push(StabilityLimit { operator: "reaction", max_dt: reaction.stable_timestep() });
+    }
+    if let Some(network) = network {
+        limits.push(StabilityLimit { operator: "network", max_dt: network.stable_timestep() });
+    }
+    if let Some(vesicle) = vesicle {
+        limits.push(StabilityLimit { operator: "bending", max_dt: vesicle.stable_timestep(&units.voxel_nm) });
+    }
+    limits
+}
+
+/// How one requested step is carried out
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TimestepPlan {
+    pub requested: f32, // µs
+    pub substeps: u32,
+    pub sub_dt: f32, // requested / substeps
+    pub limited_by: Option<StabilityLimit> // the tightest limit, when it forced substeps > 1
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TimestepController {
+    last: Option<TimestepPlan>,
+    report: Option<String> // set when the limiting changes, taken by whoever logs it
+}
+
+impl TimestepController {
+    /// Splits requested into the fewest equal sub-steps that satisfy every limit
+    /// Errs, leaving the last plan as it was, when that takes more than MAX_SUBSTEPS,
+    /// pointing at the implicit integrator when explicit diffusion is the limit
+    pub fn plan(&mut self, requested: f32, limits: &[StabilityLimit]) -> Result<TimestepPlan, String> {
+        assert!(requested > 0.0, "Timestep should be > 0.0\n");
+        let tightest = limits.iter().copied().min_by(|a, b| a.max_dt.total_cmp(&b.max_dt));
+
+        let plan = match tightest {
+            Some(limit) if requested > limit.max_dt => {
+                let mut substeps = (requested / limit.max_dt).ceil().min(u32::MAX as f32) as u32;
+                if requested / substeps as f32 > limit.max_dt { substeps = substeps.saturating_add(1); } // float rounding
+                if substeps > MAX_SUBSTEPS || !(limit.max_dt > 0.0) {
+                    let remedy = if limit.operator == "diffusion" { "use an implicit integrator or a shorter dt" } else { "take a shorter dt" };
+                    return Err(format!("Timestep {} µs would take more than {} sub-steps of the {} limit of {} µs, {}",
+                        requested, MAX_SUBSTEPS, limit.operator, limit.max_dt, remedy));
+                }
+                TimestepPlan {
+                    requested: requested,
+                    substeps: substeps,
+                    sub_dt: requested / substeps as f32,
+                    limited_by: Some(limit)
+                }
+            },
+            _ => TimestepPlan {
+                requested: requested,
+                substeps: 1,
+                sub_dt: requested,
+                limited_by: None
+            }
+        };
+
+        // only worth a line when limiting starts, changes or stops
+        let was_limited = self.last.is_some_and(|last| last.limited_by.is_some());
+        if self.last != Some(plan) && (plan.limited_by.is_some() || was_limited) {
+            self.report = Some(match plan.limited_by {
+                Some(limit) => format!("Timestep {} µs exceeds the {} limit of {} µs, running {} sub-steps of {} µs",
+                    plan.requested, limit.operator, limit.max_dt, plan.substeps, plan.sub_dt),
+                None => format!("Timestep {} µs is within every stability limit again", plan.requested)
+            });
+        }
+        self.last = Some(plan);
+        Ok(plan)
+    }
+
+    /// The most recent plan, if any step has been planned
+    pub fn last(&self) -> Option<TimestepPlan> {
+        self.last
+    }
+
+    /// A line describing the latest change in limiting, once per change
+    pub fn take_report(&mut self) -> Option<String> {
+        self.report.take()
+    }
+}
@@ -0,0 +1,82 @@
+use crate::world::{diffusion::Diffusion, voxel_grid::{Dims3, P3}};
+
+/// Physical unit layer
+/// Parameters are entered as voxel edge length in nm, D in µm^2/s and time in µs
+/// The shaders and the CPU reference work in nm and µs, so Diffusion holds spacing in nm and D in nm^2/µs
+/// 1 µm^2/s = 10^6 nm^2 / 10^6 µs = 1 nm^2/µs, which is why D passes through unscaled
+pub const NM_PER_UM: f64 = 1.0e3;
+pub const US_PER_S: f64 = 1.0e6;
+pub const UM2_PER_S_TO_NM2_PER_US: f64 = NM_PER_UM * NM_PER_UM / US_PER_S; // = 1.0
+
+/// Physical parameters of a run, see Diffusion for the values the shaders actually read
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PhysicalUnits {
+    pub voxel_nm: P3, // voxel edge length per axis
+    pub diffusion_um2_per_s: f32,
+    pub time_scale: f32 // µs simulated per wall-clock second, used by State::render()
+}
+
+impl Default for PhysicalUnits {
+    /// 1 nm voxels, D = 1 µm^2/s and 1 µs per second: numerically the old unitless D = 1.0, dx = 1.0
+    fn default() -> Self {
+        PhysicalUnits {
+            voxel_nm: [1.0, 1.0, 1.0],
+            diffusion_um2_per_s: 1.0,
+            time_scale: 1.0
+        }
+    }
+}
+
+impl PhysicalUnits {
+    pub fn new(voxel_nm: P3, diffusion_um2_per_s: f32, time_scale: f32) -> Self {
+        assert!(time_scale > 0.0, "Time scale should be > 0.0\n");
+        let units = PhysicalUnits {
+            voxel_nm: voxel_nm,
+            diffusion_um2_per_s: diffusion_um2_per_s,
+            time_scale: time_scale
+        };
+        units.diffusion(); // validates D and spacing
+        units
+    }
+
+    /// Inverse of diffusion(), e.g. for a Diffusion restored from a checkpoint
+    pub fn from_diffusion(diffusion: &Diffusion, time_scale: f32) -> Self {
+        PhysicalUnits::new(diffusion.spacing, (diffusion.coefficient as f64 / UM2_PER_S_TO_NM2_PER_US) as f32, time_scale)
+    }
+
+    /// D in nm^2/µs and spacing in nm, as written into Uniforms
+    pub fn diffusion(&self) -> Diffusion {
+        Diffusion::new((self.diffusion_um2_per_s as f64 * UM2_PER_S_TO_NM2_PER_US) as f32, self.voxel_nm)
+    }
+
+    /// Largest stable timestep in µs
+    pub fn stable_timestep_us(&self) -> f32 {
+        self.diffusion().stable_timestep()
+    }
+
+    /// Edge lengths of the whole voxel grid in nm
+    pub fn box_nm(&self, dims: &Dims3) -> P3 {
+        [dims[0] as f32 * self.voxel_nm[0], dims[1] as f32 * self.voxel_nm[1], dims[2] as f32 * self.voxel_nm[2]]
+    }
+
+    /// Simulated µs for a wall-clock interval
+    pub fn sim_us(&self, wall_seconds: f32) -> f32 {
+        wall_seconds * self.time_scale
+    }
+}
+
+/// Sim time for readouts, scaled to ns, µs, ms or s
+pub fn format_time_us(us: f64) -> String {
+    let magnitude = us.abs();
+    if magnitude == 0.0 { "0 µs".to_string() }
+    else if magnitude < 1.0 { format!("{:.3} ns", us * 1.0e3) }
+    else if magnitude < 1.0e3 { format!("{:.3} µs", us) }
+    else if magnitude < US_PER_S { format!("{:.3} ms", us / 1.0e3) }
+    else { format!("{:.3} s", us / US_PER_S) }
+}
+
+/// Lengths for readouts, scaled to nm or µm
+pub fn format_length_nm(nm: f64) -> String {
+    if nm.abs() < NM_PER_UM { format!("{:.2} nm", nm) }
+    else { format!("{:.3} µm", nm / NM_PER_UM) }
+}
@@ -0,0 +1,253 @@
+use crate::world::{
+    boundary::{Boundary, BoundaryConditions},
+    field::{VoxelField, voxel_index},
+    voxel_grid::{Dims3, P3}};
+
+/// Phase-field vesicle (Du, Liu & Wang's model): φ ~ +1 inside the membrane, -1 outside, the membrane is the level set φ = 0
+/// Helfrich bending energy with spontaneous curvature C in its phase-field form,
+/// E = k / (2ε) ∫ W², W = ε L φ - (1/ε) (φ² - 1) (φ + √2 C ε)
+/// Volume V = ∫ (φ + 1) / 2 and area A = ∫ ε/2 |∇φ|² + (φ² - 1)² / (4ε), which is 2√2/3 of the membrane's area
+/// Relaxes by Allen-Cahn (non-conserving) dynamics dφ/dt = -γ (δE/δφ + λ_V δV/δφ + λ_A δA/δφ), with
+/// δE/δφ = (k/ε) (ε L W - (1/ε) (3φ² - 1 + 2√2 C ε φ) W), L W being the bi-laplacian once W's L φ is expanded,
+/// δV/δφ = 1/2 and δA/δφ = -ε L φ + (φ³ - φ) / ε
+/// The Lagrange multipliers λ_V and λ_A are solved for every step so the constrained quantities land back on their targets,
+/// which are V and A when the model was set (or the field reseeded / restored), see multipliers()
+/// Stepped explicitly, see stable_timestep(), and replaces the diffusion of species 0 while set, D is unused
+/// Dirichlet faces act as Neumann (zero flux), the membrane should not feel a reservoir
+/// CPU and GPU implementations live in backend_admin/cpu/vesicle.rs and shaders/vesicle.wgsl
+pub const DEFAULT_RIGIDITY: f32 = 1.0; // k
+pub const DEFAULT_WIDTH: f32 = 2.0; // ε, nm, the membrane is ~ 2√2 ε thick
+pub const DEFAULT_MOBILITY: f32 = 1.0; // γ, per µs
+pub const INITIAL_AXES: P3 = [0.3, 0.2, 0.2]; // semi-axes of the initial ellipsoid, as fractions of the box
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vesicle {
+    pub rigidity: f32, // k, bending rigidity
+    pub width: f32, // ε, nm
+    pub spontaneous_curvature: f32, // C, 1/nm
+    pub mobility: f32, // γ, per µs
+    pub constrain_volume: bool,
+    pub constrain_area: bool
+}
+
+impl Default for Vesicle {
+    fn default() -> Self {
+        Vesicle {
+            rigidity: DEFAULT_RIGIDITY,
+            width: DEFAULT_WIDTH,
+            spontaneous_curvature: 0.0,
+            mobility: DEFAULT_MOBILITY,
+            constrain_volume: true,
+            constrain_area: true
+        }
+    }
+}
+
+/// Sums over every voxel of what a step's multipliers are solved from
+/// v = (φ + 1) / 2, a the area density, h = δA/δφ and g = δE/δφ
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct VesicleSums {
+    pub volume: f64, // Σ v
+    pub area: f64, // Σ a
+    pub area_potential: f64, // Σ h
+    pub area_potential_squared: f64, // Σ h²
+    pub bending_potential: f64, // Σ g
+    pub cross: f64 // Σ h g
+}
+
+impl VesicleSums {
+    /// One voxel's terms, the order the shader's partials hold them in
+    pub fn terms(v: f32, a: f32, h: f32, g: f32) -> [f32; 6] {
+        [v, a, h, h * h, g, h * g]
+    }
+
+    pub fn add(&mut self, terms: &[f32]) {
+        self.volume += terms[0] as f64;
+        self.area += terms[1] as f64;
+        self.area_potential += terms[2] as f64;
+        self.area_potential_squared += terms[3] as f64;
+        self.bending_potential += terms[4] as f64;
+        self.cross += terms[5] as f64;
+    }
+}
+
+impl Vesicle {
+    pub fn new(rigidity: f32, width: f32, spontaneous_curvature: f32, mobility: f32) -> Self {
+        assert!(rigidity >= 0.0 && width > 0.0 && mobility >= 0.0, "Rigidity and mobility should be >= 0.0, width > 0.0\n");
+        Vesicle {
+            rigidity: rigidity,
+            width: width,
+            spontaneous_curvature: spontaneous_curvature,
+            mobility: mobility,
+            ..Vesicle::default()
+        }
+    }
+
+    pub fn with_constraints(mut self, volume: bool, area: bool) -> Self {
+        self.constrain_volume = volume;
+        self.constrain_area = area;
+        self
+    }
+
+    pub fn name(&self) -> String {
+        let constraints = match (self.constrain_volume, self.constrain_area) {
+            (true, true) => ", fixed V and A",
+            (true, false) => ", fixed V",
+            (false, true) => ", fixed A",
+            (false, false) => ""
+        };
+        format!("Vesicle k = {}, ε = {} nm, C = {} /nm, γ = {}{}", self.rigidity, self.width, self.spontaneous_curvature, self.mobility, constraints)
+    }
+
+    /// W = ε L φ - (1/ε) (φ² - 1) (φ + √2 C ε), the membrane's curvature up to a factor, laplacian = L φ
+    pub fn curvature(&self, phi: f32, laplacian: f32) -> f32 {
+        let eps = self.width;
+        (eps * laplacian) - ((((phi * phi) - 1.0) * (phi + (std::f32::consts::SQRT_2 * self.spontaneous_curvature * eps))) / eps)
+    }
+
+    /// δE/δφ from W and laplacian = L W
+    pub fn bending_potential(&self, phi: f32, curvature: f32, laplacian: f32) -> f32 {
+        let eps = self.width;
+        let well = ((3.0 * phi * phi) - 1.0) + (2.0 * std::f32::consts::SQRT_2 * self.spontaneous_curvature * eps * phi);
+        (self.rigidity / eps) * ((eps * laplacian) - ((well * curvature) / eps))
+    }
+
+    /// δA/δφ, laplacian = L φ
+    pub fn area_potential(&self, phi: f32, laplacian: f32) -> f32 {
+        (((phi * phi * phi) - phi) / self.width) - (self.width * laplacian)
+    }
+
+    /// Area per voxel volume, -ε/2 φ L φ standing in for ε/2 |∇φ|² (they sum to the same over a grid with zero flux faces)
+    pub fn area_density(&self, phi: f32, laplacian: f32) -> f32 {
+        let well = (phi * phi) - 1.0;
+        ((well * well) / (4.0 * self.width)) - (0.5 * self.width * phi * laplacian)
+    }
+
+    /// φ' = φ - dt γ (g + λ_V / 2 + λ_A h), the update both backends apply per voxel
+    pub fn relax(&self, phi: f32, bending_potential: f32, area_potential: f32, multipliers: &[f32; 2], timestep: f32) -> f32 {
+        phi - ((timestep * self.mobility) * (bending_potential + (0.5 * multipliers[0]) + (multipliers[1] * area_potential)))
+    }
+
+    /// [λ_V, λ_A] for a step from the sums over the current field, targets being [Σ v, Σ a] to hold
+    /// To first order in dt a step changes Σ v by -dt γ (Σ g/2 + λ_V n/4 + λ_A Σ h/2) and Σ a by -dt γ (Σ h g + λ_V Σ h/2 + λ_A Σ h²),
+    /// the multipliers of the enabled constraints are whatever makes those changes land on the targets
+    /// (the voxel volume scales both sides alike, so it drops out), a disabled constraint's is 0
+    pub fn multipliers(&self, sums: &VesicleSums, targets: &[f64; 2], voxels: usize, timestep: f32) -> [f32; 2] {
+        let rate = (timestep * self.mobility) as f64;
+        if rate <= 0.0 { return [0.0, 0.0]; }
+        let n = voxels as f64;
+        let volume_rhs = ((sums.volume - targets[0]) / rate) - (0.5 * sums.bending_potential);
+        let area_rhs = ((sums.area - targets[1]) / rate) - sums.cross;
+        let (vv, va, aa) = (0.25 * n, 0.5 * sums.area_potential, sums.area_potential_squared);
+        let solve = |rhs: f64, diagonal: f64| if diagonal > 0.0 { rhs / diagonal } else { 0.0 };
+
+        let [volume, area] = match (self.constrain_volume, self.constrain_area) {
+            (true, true) => {
+                let determinant = (vv * aa) - (va * va);
+                if determinant.abs() <= f64::EPSILON * vv * aa { [solve(volume_rhs, vv), 0.0] } // no interface, area can't be steered
+                else { [((aa * volume_rhs) - (va * area_rhs)) / determinant, ((vv * area_rhs) - (va * volume_rhs)) / determinant] }
+            },
+            (true, false) => [solve(volume_rhs, vv), 0.0],
+            (false, true) => [0.0, solve(area_rhs, aa)],
+            (false, false) => [0.0, 0.0]
+        };
+        [volume as f32, area as f32]
+    }
+
+    /// Bound on a stable explicit step, µs
+    /// The linearised step is dominated by γ (k/ε) (ε L - q/ε)², L's spectrum reaching 4 (1/dx² + 1/dy² + 1/dz²)
+    /// and q = 2 + 2√2 |C| ε bounding the well's slope for |φ| <= 1, explicit Euler on a rate λ needs dt <= 1 / λ
+    pub fn stable_timestep(&self, spacing: &P3) -> f32 {
+        let eps = self.width;
+        let spectrum = 4.0 * spacing.iter().map(|d| 1.0 / (d * d)).sum::<f32>();
+        let slope = 2.0 + (2.0 * std::f32::consts::SQRT_2 * self.spontaneous_curvature.abs() * eps);
+        let stiffness = self.mobility * (self.rigidity / eps) * ((eps * spectrum) + (slope / eps)).powi(2);
+        if stiffness == 0.0 { f32::INFINITY } else { 1.0 / stiffness }
+    }
+
+    /// A centred ellipsoid with semi-axes INITIAL_AXES of the box, φ = tanh(d / (√2 ε)) across its surface
+    /// d is the signed distance (positive inside) along the radius, exact on a sphere, in voxel_index order
+    pub fn initial_field(&self, dims: &Dims3, spacing: &P3) -> Vec<f32> {
+        let axes: Vec<f32> = (0..3).map(|i| INITIAL_AXES[i] * dims[i] as f32 * spacing[i]).collect();
+        let mut field = vec![0.0; (dims[0] * dims[1] * dims[2]) as usize];
+        for z in 0..dims[2] {
+            for y in 0..dims[1] {
+                for x in 0..dims[0] {
+                    let offset: Vec<f32> = [x, y, z].iter().enumerate()
+                        .map(|(i, p)| ((*p as f32 + 0.5) - (dims[i] as f32 / 2.0)) * spacing[i]).collect();
+                    let scaled = (0..3).map(|i| (offset[i] / axes[i]).powi(2)).sum::<f32>().sqrt();
+                    let radius = offset.iter().map(|o| o * o).sum::<f32>().sqrt();
+                    let distance = if scaled > 0.0 { radius * ((1.0 / scaled) - 1.0) } else { axes.iter().copied().fold(f32::INFINITY, f32::min) };
+                    field[voxel_index(dims, x, y, z)] = (distance / (std::f32::consts::SQRT_2 * self.width)).tanh();
+                }
+            }
+        }
+        field
+    }
+
+    /// (V in nm³, membrane area in nm²) over the grid, the area scaled by 3/(2√2) from the phase-field A
+    /// Gradients are forward differences, wrapping across periodic faces and zero across the others
+    pub fn measure(&self, field: &VoxelField, spacing: &P3, bounds: &BoundaryConditions) -> (f64, f64) {
+        let dims = field.dims;
+        let voxel = (spacing[0] * spacing[1] * spacing[2]) as f64;
+        let eps = self.width as f64;
+        let (mut volume, mut area) = (0.0, 0.0);
+        for z in 0..dims[2] {
+            for y in 0..dims[1] {
+                for x in 0..dims[0] {
+                    let phi = field.data[voxel_index(&dims, x, y, z)] as f64;
+                    let mut gradient = 0.0;
+                    for (axis, p) in [x, y, z].into_iter().enumerate() {
+                        let next = match (p + 1 < dims[axis], bounds.faces[axis][1]) {
+                            (true, _) => p + 1,
+                            (false, Boundary::Periodic) => 0,
+                            _ => continue
+                        };
+                        let mut n = [x, y, z];
+                        n[axis] = next;
+                        let d = (field.data[voxel_index(&dims, n[0], n[1], n[2])] as f64 - phi) / spacing[axis] as f64;
+                        gradient += d * d;
+                    }
+                    volume += ((phi + 1.0) / 2.0) * voxel;
+                    area += (((eps / 2.0) * gradient) + (((phi * phi) - 1.0).powi(2) / (4.0 * eps))) * voxel;
+                }
+            }
+        }
+        (volume, area * 3.0 / (2.0 * std::f64::consts::SQRT_2))
+    }
+
+    /// Reduced volume V / (4π/3 (A / 4π)^(3/2)), 1 for a sphere and less the further the shape is from one
+    pub fn reduced_volume(volume: f64, area: f64) -> f64 {
+        if area <= 0.0 { return 0.0; }
+        volume / ((4.0 * std::f64::consts::PI / 3.0) * (area / (4.0 * std::f64::consts::PI)).powf(1.5))
+    }
+
+    /// Parses "default" or comma separated overrides of it,
+    /// e.g. "rigidity=2,width=1.5,curvature=0.1,mobility=1,volume=on,area=off"
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut model = Vesicle::default();
+        for setting in spec.split(',').map(str::trim).filter(|s| !s.is_empty() && *s != "default") {
+            let (key, value) = setting.split_once('=').ok_or(format!("Expected <key>=<value>, got '{}'", setting))?;
+            let (key, value) = (key.trim(), value.trim());
+            let switch = || match value {
+                "on" => Ok(true),
+                "off" => Ok(false),
+                other => Err(format!("Bad value '{}' for {}, expected on or off", other, key))
+            };
+            let number = || value.parse::<f32>().map_err(|e| format!("Bad value '{}': {}", value, e));
+            match key {
+                "rigidity" => model.rigidity = number()?,
+                "width" => model.width = number()?,
+                "curvature" => model.spontaneous_curvature = number()?,
+                "mobility" => model.mobility = number()?,
+                "volume" => model.constrain_volume = switch()?,
+                "area" => model.constrain_area = switch()?,
+                other => return Err(format!("Unknown vesicle setting '{}', expected rigidity, width, curvature, mobility, volume or area", other))
+            }
+        }
+        if !(model.rigidity >= 0.0 && model.width > 0.0 && model.mobility >= 0.0 && model.spontaneous_curvature.is_finite()) {
+            return Err("Vesicle rigidity and mobility should be >= 0, width > 0".to_string());
+        }
+        Ok(model)
+    }
+}
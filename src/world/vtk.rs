@@ -0,0 +1,143 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf}
+};
+use crate::world::{
+    field::VoxelField,
+    voxel_grid::{Access, SystemGet, SystemSet, VoxelGrid, P3}
+};
+
+/// VTK structured points export of VoxelFields, for ParaView
+/// Each voxel is written as a cell, so the image has dims + 1 points per axis
+/// and cell (x, y, z) covers the same world space the raymarch pass samples it from, scaled to nm
+const SCALARS_NAME: &str = "concentration";
+
+/// Placement of the image in world coordinates
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VtkGeometry {
+    pub origin: P3, // position of voxel (0, 0, 0)'s min corner, nm
+    pub spacing: P3 // size of one voxel along each axis, nm
+}
+
+impl VtkGeometry {
+    /// Origin is the min corner of the VoxelGrid's world cuboid (see VoxelGrid::new_centered_at_origin)
+    /// Voxels are one world unit wide, as in the raymarch pass, and voxel_nm long in physical units
+    pub fn from_voxel_grid(grid: &VoxelGrid, voxel_nm: &P3) -> Self {
+        let mut origin = [f32::MAX; 3];
+        for i in 0..8 {
+            match grid.get_vertex_at(SystemGet::WORLD(i)) {
+                SystemSet::WORLD(p) => {
+                    for axis in 0..3 { origin[axis] = origin[axis].min(p[axis] * voxel_nm[axis]); }
+                },
+                _ => panic!("Voxel grid returned a non-world vertex.\n")
+            }
+        }
+        VtkGeometry {
+            origin: origin,
+            spacing: *voxel_nm
+        }
+    }
+}
+
+/// Writes a .vti (XML ImageData) or legacy .vtk file, picked by path's extension
+pub fn write_vtk(path: &Path, field: &VoxelField, geometry: &VtkGeometry) -> Result<(), Box<dyn Error>> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("vti") => write_vti(path, field, geometry),
+        Some("vtk") => write_legacy_vtk(path, field, geometry),
+        _ => Err(format!("Unknown VTK extension for {}, expected .vti or .vtk", path.display()).into())
+    }
+}
+
+/// XML ImageData, field stored as raw little endian Float32 in an appended block
+pub fn write_vti(path: &Path, field: &VoxelField, geometry: &VtkGeometry) -> Result<(), Box<dyn Error>> {
+    let [x, y, z] = field.dims;
+    let extent = format!("0 {} 0 {} 0 {}", x, y, z);
+    let mut out = BufWriter::new(File::create(path)?);
+
+    writeln!(out, "<?xml version=\"1.0\"?>")?;
+    writeln!(out, "<VTKFile type=\"ImageData\" version=\"1.0\" byte_order=\"LittleEndian\" header_type=\"UInt64\">")?;
+    writeln!(out, "  <ImageData WholeExtent=\"{}\" Origin=\"{} {} {}\" Spacing=\"{} {} {}\">", extent,
+        geometry.origin[0], geometry.origin[1], geometry.origin[2],
+        geometry.spacing[0], geometry.spacing[1], geometry.spacing[2])?;
+    writeln!(out, "    <Piece Extent=\"{}\">", extent)?;
+    writeln!(out, "      <CellData Scalars=\"{}\">", SCALARS_NAME)?;
+    writeln!(out, "        <DataArray type=\"Float32\" Name=\"{}\" format=\"appended\" offset=\"0\"/>", SCALARS_NAME)?;
+    writeln!(out, "      </CellData>")?;
+    writeln!(out, "    </Piece>")?;
+    writeln!(out, "  </ImageData>")?;
+
+    // appended block: '_' marks the start, then a UInt64 byte count (header_type) before the data
+    write!(out, "  <AppendedData encoding=\"raw\">\n   _")?;
+    out.write_all(&((field.data.len() * 4) as u64).to_le_bytes())?;
+    let data: Vec<u8> = field.data.iter().flat_map(|v| v.to_le_bytes()).collect();
+    out.write_all(&data)?;
+    writeln!(out, "\n  </AppendedData>")?;
+    writeln!(out, "</VTKFile>")?;
+
+    out.flush()?;
+    Ok(())
+}
+
+/// Legacy STRUCTURED_POINTS, binary (legacy VTK is always big endian)
+pub fn write_legacy_vtk(path: &Path, field: &VoxelField, geometry: &VtkGeometry) -> Result<(), Box<dyn Error>> {
+    let [x, y, z] = field.dims;
+    let mut out = BufWriter::new(File::create(path)?);
+
+    writeln!(out, "# vtk DataFile Version 3.0")?;
+    writeln!(out, "bocs voxel field")?;
+    writeln!(out, "BINARY")?;
+    writeln!(out, "DATASET STRUCTURED_POINTS")?;
+    writeln!(out, "DIMENSIONS {} {} {}", x + 1, y + 1, z + 1)?;
+    writeln!(out, "ORIGIN {} {} {}", geometry.origin[0], geometry.origin[1], geometry.origin[2])?;
+    writeln!(out, "SPACING {} {} {}", geometry.spacing[0], geometry.spacing[1], geometry.spacing[2])?;
+    writeln!(out, "CELL_DATA {}", field.data.len())?;
+    writeln!(out, "SCALARS {} float 1", SCALARS_NAME)?;
+    writeln!(out, "LOOKUP_TABLE default")?;
+    let data: Vec<u8> = field.data.iter().flat_map(|v| v.to_be_bytes()).collect();
+    out.write_all(&data)?;
+    writeln!(out)?;
+
+    out.flush()?;
+    Ok(())
+}
+
+/// ParaView time series: one .vti per step beside a .pvd collection indexing them by sim time (µs)
+/// The .pvd is rewritten after every step, so an interrupted run still opens
+pub struct PvdSeries {
+    path: PathBuf,
+    steps: Vec<(f64, PathBuf)> // sim time, .vti file name relative to the .pvd
+}
+
+impl PvdSeries {
+    pub fn new(path: &Path) -> Self {
+        PvdSeries {
+            path: path.to_path_buf(),
+            steps: Vec::new()
+        }
+    }
+
+    /// Writes field as <stem>_<step>.vti next to the .pvd and adds it to the collection at time
+    pub fn write_step(&mut self, time: f64, field: &VoxelField, geometry: &VtkGeometry) -> Result<(), Box<dyn Error>> {
+        let stem = self.path.file_stem().and_then(|s| s.to_str()).ok_or("Series path has no file name")?;
+        let name = PathBuf::from(format!("{}_{:05}.vti", stem, self.steps.len()));
+        write_vti(&self.path.with_file_name(&name), field, geometry)?;
+        self.steps.push((time, name));
+        self.write_collection()
+    }
+
+    fn write_collection(&self) -> Result<(), Box<dyn Error>> {
+        let mut out = BufWriter::new(File::create(&self.path)?);
+        writeln!(out, "<?xml version=\"1.0\"?>")?;
+        writeln!(out, "<VTKFile type=\"Collection\" version=\"1.0\" byte_order=\"LittleEndian\">")?;
+        writeln!(out, "  <Collection>")?;
+        for (time, name) in &self.steps {
+            writeln!(out, "    <DataSet timestep=\"{}\" part=\"0\" file=\"{}\"/>", time, name.display())?;
+        }
+        writeln!(out, "  </Collection>")?;
+        writeln!(out, "</VTKFile>")?;
+        out.flush()?;
+        Ok(())
+    }
+}
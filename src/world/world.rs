@@ -1,32 +1,57 @@
-use crate::{backend_admin::gpu::gfx_context::GraphicsContext, world::{camera::OrbitalCamera, voxel_grid::{P2i, Access, SystemGet, SystemSet, VoxelGrid, Dims3, P3}}};
+use winit::dpi::PhysicalSize;
+use crate::{world::{boundary::BoundaryConditions, brownian_motion::Particles, cahn_hilliard::CahnHilliard, camera::OrbitalCamera, clock::SimClock, integrator::Integrator, network::ReactionNetwork, noise::ThermalNoise, reaction::Reaction, species::SpeciesRegistry, stability::TimestepController, units::PhysicalUnits, vesicle::Vesicle, voxel_grid::{P2i, Access, SystemGet, SystemSet, VoxelGrid, Dims3, P3}}};
 
 /// Manages all World entities
 pub struct World {
     pub voxel_grid: VoxelGrid,
     pub bbox: BoundingBox,
     pub camera: OrbitalCamera,
-    pub right_sf: f32
+    pub right_sf: f32,
+    pub boundaries: BoundaryConditions, // faces of voxel_grid, Neumann unless set
+    pub units: PhysicalUnits, // voxel size, D and time scale, see units.rs
+    pub clock: SimClock, // fixed dt, sim time and step count
+    pub integrator: Integrator, // explicit unless set
+    pub species: SpeciesRegistry, // every field the backends store, species 0 alone unless set
+    pub reaction: Option<Reaction>, // second species and its coupling, plain diffusion unless set
+    pub network: Option<ReactionNetwork>, // mass-action chemistry between any species, indexed as species
+    pub cahn_hilliard: Option<CahnHilliard>, // phase separation of species 0 in place of its diffusion
+    pub vesicle: Option<Vesicle>, // membrane relaxation of species 0 in place of its diffusion, never alongside cahn_hilliard
+    pub noise: Option<ThermalNoise>, // thermal kicks to species 0 after every step, deterministic unless set
+    pub particles: Option<Particles>, // Brownian point particles in the grid's box, none unless set
+    pub stability: TimestepController // splits dt into stable sub-steps
 }
 
 pub type BoundingBox = [P2i; 2];
 
 impl World {
-    pub fn new(d: Dims3, gfx_ctx: &GraphicsContext) -> Self {
+    pub fn new(d: Dims3, size: &PhysicalSize<u32>) -> Self {
         assert!(d[0] > 0 && d[1] > 0 && d[2] > 0);
         let cam_init: P3 = [d[0] as f32 * 2.0, 0.0, 0.0];
         World {
             voxel_grid: VoxelGrid::new_centered_at_origin(d),
             bbox: BoundingBox::default(),
-            camera: OrbitalCamera::new(cam_init, &gfx_ctx.size),
-            right_sf: 0.0
+            camera: OrbitalCamera::new(cam_init, size),
+            right_sf: 0.0,
+            boundaries: BoundaryConditions::default(),
+            units: PhysicalUnits::default(),
+            clock: SimClock::default(),
+            integrator: Integrator::default(),
+            species: SpeciesRegistry::default(),
+            reaction: None,
+            network: None,
+            cahn_hilliard: None,
+            vesicle: None,
+            noise: None,
+            particles: None,
+            stability: TimestepController::default()
         }
     }
 
     /// Projects 8 P3 vertices of VoxelGrid onto camera's near plane as 4 P2s
     /// This is the minimum enclosing square for the voxel_grid (bounding box)
-    pub fn generate_bb_projection(&mut self, gfx_ctx: &GraphicsContext) {
+    pub fn generate_bb_projection(&mut self, size: &PhysicalSize<u32>) {
         // First: convert from pixels into world units
-        let (w, h) = (gfx_ctx.size.width as i32, gfx_ctx.size.height as i32);
+        let (w, h) = (size.width as i32, size.height as i32);
 
         let centre_top = h / 2; // 1:1 vertical pixels and up vector
         self.right_sf = (w as f32 / 2.0) / centre_top as f32; // Right vector's scaling factor from pixels to world garantees FOV 90 in vertical